@@ -0,0 +1,273 @@
+//! `#[derive(JsonApiResource)]`, which implements `jsonapi::resource::JsonApiResource` for a struct
+//! with named fields.
+//!
+//! * `#[jsonapi(type = "articles")]` on the struct names the resource `type`.
+//! * `#[jsonapi(id)]` marks the field holding the `id`, which is converted with `ToString` and
+//!   `FromStr`.
+//! * `#[jsonapi(relationship)]` marks a field holding resource linkage.
+//! * `#[jsonapi(skip)]` leaves a field out of the resource object. It is filled with
+//!   `Default::default()` when converting back.
+//! * `#[jsonapi(rename = "reading-time")]` uses a different member name for an attribute or
+//!   relationship.
+//!
+//! Every other field is an attribute, named after the field.
+
+extern crate jsonapi_name;
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use std::collections::BTreeSet;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Field, Fields, Ident, LitStr};
+
+#[proc_macro_derive(JsonApiResource, attributes(jsonapi))]
+pub fn derive_jsonapi_resource(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+enum Kind {
+    Id,
+    Attribute(String),
+    Relationship(String),
+    Skip,
+}
+
+struct ResourceField<'a> {
+    ident: &'a Ident,
+    kind: Kind,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Named(ref fields) => &fields.named,
+                _ => return Err(syn::Error::new(input.span(), "JsonApiResource requires a struct with named fields")),
+            }
+        }
+        _ => return Err(syn::Error::new(input.span(), "JsonApiResource can only be derived for structs")),
+    };
+
+    let type_ = container_type(input)?;
+    let fields = fields.iter().map(resource_field).collect::<syn::Result<Vec<_>>>()?;
+    check_fields(&fields)?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut to_id = quote!(None);
+    let mut to_members = Vec::new();
+    let mut from_members = Vec::new();
+    let mut has_attributes = false;
+    let mut has_relationships = false;
+
+    for field in &fields {
+        let field_ident = field.ident;
+
+        match field.kind {
+            Kind::Id => {
+                to_id = quote!(Some(__jsonapi::to_id(&self.#field_ident)));
+                from_members.push(quote!(#field_ident: __jsonapi::from_id(resource.id.as_ref())?));
+            }
+            Kind::Attribute(ref name) => {
+                has_attributes = true;
+                to_members.push(quote!(__jsonapi::insert_attribute(&mut attributes, #name, &self.#field_ident)?;));
+                from_members.push(quote!(#field_ident: __jsonapi::take_attribute(&mut attributes, #name)?));
+            }
+            Kind::Relationship(ref name) => {
+                has_relationships = true;
+                to_members.push(quote! {
+                    __jsonapi::insert_relationship(&mut relationships, #name, &self.#field_ident);
+                });
+                from_members.push(quote!(#field_ident: __jsonapi::take_relationship(&mut relationships, #name)?));
+            }
+            Kind::Skip => from_members.push(quote!(#field_ident: ::std::default::Default::default())),
+        }
+    }
+
+    let to_attributes = if has_attributes {
+        quote!(Some(__jsonapi::Attributes(attributes)))
+    } else {
+        quote!(None)
+    };
+
+    let to_relationships = if has_relationships {
+        quote!(Some(__jsonapi::Relationships(relationships)))
+    } else {
+        quote!(None)
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::jsonapi::resource::JsonApiResource for #ident #ty_generics #where_clause {
+            const TYPE: &'static str = #type_;
+
+            #[allow(unused_mut)]
+            fn to_resource_object(&self)
+                                  -> ::std::result::Result<::jsonapi::spec::ResourceObject,
+                                                           ::jsonapi::resource::ResourceError> {
+                use ::jsonapi::resource::__private as __jsonapi;
+
+                let mut attributes = ::jsonapi::spec::Object::new();
+                let mut relationships = ::std::collections::BTreeMap::<::jsonapi::spec::Name,
+                                                                       ::jsonapi::spec::Relationship>::new();
+
+                #(#to_members)*
+
+                Ok(__jsonapi::ResourceObject {
+                    id: #to_id,
+                    lid: None,
+                    type_: __jsonapi::Type(__jsonapi::name(#type_)),
+                    attributes: #to_attributes,
+                    relationships: #to_relationships,
+                    links: None,
+                    meta: None,
+                })
+            }
+
+            #[allow(unused_mut, unused_variables)]
+            fn from_resource_object(resource: ::jsonapi::spec::ResourceObject)
+                                    -> ::std::result::Result<Self, ::jsonapi::resource::ResourceError> {
+                use ::jsonapi::resource::__private as __jsonapi;
+
+                __jsonapi::check_type(&resource, #type_)?;
+
+                let mut attributes = resource.attributes.map(|attributes| attributes.0).unwrap_or_default();
+                let mut relationships = resource.relationships
+                    .map(|relationships| relationships.0)
+                    .unwrap_or_default();
+
+                Ok(#ident {
+                    #(#from_members,)*
+                })
+            }
+        }
+    })
+}
+
+/// Reads `#[jsonapi(type = "...")]` from the struct and checks that it is a valid member name.
+fn container_type(input: &DeriveInput) -> syn::Result<String> {
+    let mut type_ = None;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("jsonapi")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("type") {
+                let value: LitStr = meta.value()?.parse()?;
+                check_name(&value.value(), value.span())?;
+                type_ = Some(value.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported jsonapi attribute, expected `type = \"...\"`"))
+            }
+        })?;
+    }
+
+    type_.ok_or_else(|| {
+        syn::Error::new(Span::call_site(), "JsonApiResource requires a `#[jsonapi(type = \"...\")]` attribute")
+    })
+}
+
+/// Reads the `#[jsonapi(...)]` attributes of a field and checks the member name it maps to.
+fn resource_field<'a>(field: &'a Field) -> syn::Result<ResourceField<'a>> {
+    let ident = field.ident.as_ref().expect("named field");
+    let mut id = false;
+    let mut relationship = false;
+    let mut skip = false;
+    let mut rename = None;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("jsonapi")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                id = true;
+            } else if meta.path.is_ident("relationship") {
+                relationship = true;
+            } else if meta.path.is_ident("skip") {
+                skip = true;
+            } else if meta.path.is_ident("rename") {
+                rename = Some(meta.value()?.parse::<LitStr>()?);
+            } else {
+                return Err(meta.error("unsupported jsonapi attribute, expected `id`, `relationship`, `skip` or \
+                                       `rename = \"...\"`"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    if [id, relationship, skip].iter().filter(|&&set| set).count() > 1 {
+        return Err(syn::Error::new(field.span(), "`id`, `relationship` and `skip` can not be combined"));
+    }
+
+    if rename.is_some() && (id || skip) {
+        return Err(syn::Error::new(field.span(), "`rename` only applies to attributes and relationships"));
+    }
+
+    let (name, span) = match rename {
+        Some(ref rename) => (rename.value(), rename.span()),
+        None => (ident.unraw().to_string(), ident.span()),
+    };
+
+    let kind = if id {
+        Kind::Id
+    } else if skip {
+        Kind::Skip
+    } else {
+        check_name(&name, span)?;
+
+        if relationship {
+            Kind::Relationship(name)
+        } else {
+            if name == "relationships" || name == "links" {
+                return Err(syn::Error::new(span, format!("`{}` is reserved and MUST NOT be used as an attribute name",
+                                                         name)));
+            }
+
+            Kind::Attribute(name)
+        }
+    };
+
+    Ok(ResourceField { ident, kind })
+}
+
+/// Fields for a resource object **MUST** share a common namespace with each other and with `type`
+/// and `id`.
+fn check_fields(fields: &[ResourceField]) -> syn::Result<()> {
+    let mut names = BTreeSet::new();
+    let mut has_id = false;
+
+    for field in fields {
+        let name = match field.kind {
+            Kind::Id => {
+                if has_id {
+                    return Err(syn::Error::new(field.ident.span(), "only one field can be marked `#[jsonapi(id)]`"));
+                }
+
+                has_id = true;
+                continue;
+            }
+            Kind::Attribute(ref name) | Kind::Relationship(ref name) => name,
+            Kind::Skip => continue,
+        };
+
+        if name == "type" || name == "id" || !names.insert(name) {
+            return Err(syn::Error::new(field.ident.span(),
+                                       format!("the field `{}` collides with another field, `type` or `id`", name)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks `name` with the member name rules shared with `jsonapi::spec::Name`.
+fn check_name(name: &str, span: Span) -> syn::Result<()> {
+    jsonapi_name::check_name(name)
+        .map_err(|error| syn::Error::new(span, format!("invalid member name `{}`: {}", name, error)))
+}
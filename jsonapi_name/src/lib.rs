@@ -0,0 +1,102 @@
+//! The member name rules of [JSON API](http://jsonapi.org/format/#document-member-names).
+//!
+//! They are shared by `jsonapi::spec::Name` and the checks `#[derive(JsonApiResource)]` makes at
+//! compile time, which can't call into the `jsonapi` crate since it depends on the derive.
+
+use std::error;
+use std::fmt;
+
+/// Checks `name` against the member name rules.
+pub fn check_name(name: &str) -> Result<(), NameError> {
+    let last = name.chars().count().saturating_sub(1);
+
+    if name.is_empty() {
+        return Err(NameError::Empty);
+    }
+
+    for (position, character) in name.chars().enumerate() {
+        match character {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '\u{80}'..='\u{10FFFF}' => {}
+            '-' | '_' | ' ' => {
+                if position == 0 || position == last {
+                    return Err(NameError::NotAllowedAtBoundary { character, position });
+                }
+            }
+            '+' | ',' | '.' | '[' | ']' | '!' | '"' | '#' | '$' | '%' | '&' | '\'' | '(' | ')' | '*' |
+            '/' | ':' | ';' | '<' | '=' | '>' | '?' | '@' | '\\' | '^' | '`' | '{' | '|' | '}' | '~' => {
+                return Err(NameError::ReservedCharacter { character, position });
+            }
+            _ => return Err(NameError::InvalidCharacter { character, position }),
+        }
+    }
+
+    Ok(())
+}
+
+/// Why a string is not a valid member name. Positions count characters, not bytes, from the start
+/// of the name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameError {
+    /// Member names **MUST** contain at least one character.
+    Empty,
+
+    /// One of the reserved characters, which **MUST NOT** be used in member names.
+    ReservedCharacter {
+        character: char,
+        position: usize,
+    },
+
+    /// `-`, `_` or ` `, which are allowed in member names except as the first or last character.
+    NotAllowedAtBoundary {
+        character: char,
+        position: usize,
+    },
+
+    /// A character in U+0000 to U+007F that is neither allowed nor reserved, such as a control
+    /// character.
+    InvalidCharacter {
+        character: char,
+        position: usize,
+    },
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NameError::Empty => write!(f, "member names MUST contain at least one character"),
+            NameError::ReservedCharacter { character, position } => {
+                write!(f, "reserved character {:?} at position {} MUST NOT be used in member names",
+                       character,
+                       position)
+            }
+            NameError::NotAllowedAtBoundary { character, position } => {
+                write!(f, "character {:?} at position {} is not allowed at the start or end of a member name",
+                       character,
+                       position)
+            }
+            NameError::InvalidCharacter { character, position } => {
+                write!(f, "character {:?} at position {} is not allowed in member names", character, position)
+            }
+        }
+    }
+}
+
+impl error::Error for NameError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_name() {
+        for name in &["articles", "first-name", "a_b c", "名前", "x"] {
+            assert_eq!(check_name(name), Ok(()), "{}", name);
+        }
+
+        assert_eq!(check_name(""), Err(NameError::Empty));
+        assert_eq!(check_name("-a"), Err(NameError::NotAllowedAtBoundary { character: '-', position: 0 }));
+        assert_eq!(check_name("名前_"), Err(NameError::NotAllowedAtBoundary { character: '_', position: 2 }));
+        assert_eq!(check_name("a.b"), Err(NameError::ReservedCharacter { character: '.', position: 1 }));
+        assert_eq!(check_name("a\tb"), Err(NameError::InvalidCharacter { character: '\t', position: 1 }));
+    }
+}
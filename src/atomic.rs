@@ -0,0 +1,844 @@
+//! https://jsonapi.org/ext/atomic
+//!
+//! The Atomic Operations extension provides a means to perform multiple "operations" in a linear
+//! and atomic manner. Operations are a serialized form of the mutations allowed in the base
+//! specification.
+//!
+//! A request document **MUST** contain an `atomic:operations` member, an array of operation
+//! objects. A response to a successful request **MUST** contain an `atomic:results` member, an
+//! array of result objects that correspond in order to the operations. Either is sent with the
+//! media type `application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"`, so a server
+//! supporting the extension negotiates with `Negotiator::new().extension(atomic::EXTENSION)`.
+//!
+//! The `data` of an operation is the primary data of the base specification: a resource object
+//! for an operation on a resource, and resource linkage for an operation on a relationship.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_json;
+
+use de::deserialize_some;
+use pointer::JsonPointer;
+use spec::{Endpoint, Error, Id, JsonAPIObject, Meta, Name, PrimaryData, PrimaryDataSeed, ResourceIdentifierObject,
+           ResourceLinkage, ResourceObject, Source, Type};
+use uri::Uri;
+
+/// The URI of the extension, given in the `ext` media type parameter.
+pub const EXTENSION: &str = "https://jsonapi.org/ext/atomic";
+
+/// A request document holding the operations to perform.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OperationsDocument {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonAPIObject>,
+    #[serde(rename = "atomic:operations")]
+    pub operations: Vec<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// A response document holding the result of each operation, in the order of the operations.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResultsDocument {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonAPIObject>,
+    #[serde(rename = "atomic:results")]
+    pub results: Vec<OperationResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// https://jsonapi.org/ext/atomic#operation-objects
+///
+/// An operation object **MUST** contain an `op` member, and **MAY** contain a `ref` or an `href`
+/// member to target a resource or relationship, but **MUST NOT** contain both. It **MAY** contain
+/// the operation's primary data as `data`, and a `meta` member.
+///
+/// * Adding a resource: `op` is `add`, the optional `href` is the resource collection, and `data`
+///   is the resource object to add.
+/// * Updating a resource: `op` is `update`, the optional target is the resource, and `data` is the
+///   resource object to update.
+/// * Removing a resource: `op` is `remove`, and the target is the resource.
+/// * Adding to, replacing or removing from a relationship: `op` is `add`, `update` or `remove`,
+///   `ref` names the `relationship`, and `data` is resource linkage. Only the members of a to-many
+///   relationship can be added or removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+    pub op: Op,
+    pub target: Option<Target>,
+    /// The primary data, which is `Some(PrimaryData::Null)` when `data` is `null`.
+    pub data: Option<PrimaryData>,
+    pub meta: Option<Meta>,
+}
+
+/// The value of the `op` member.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Op {
+    Add,
+    Update,
+    Remove,
+}
+
+/// What an operation targets: the resource or relationship named by `ref`, or the URI-reference
+/// given as `href`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    Ref(Ref),
+    Href(Uri),
+}
+
+/// https://jsonapi.org/ext/atomic#operation-objects-ref
+///
+/// A `ref` **MUST** contain a `type` and either an `id` or a `lid`, and **MAY** contain a
+/// `relationship` to target a relationship of that resource.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Ref {
+    #[serde(rename = "type")]
+    pub type_: Type,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Id>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relationship: Option<Name>,
+}
+
+/// https://jsonapi.org/ext/atomic#result-objects
+///
+/// The result of a successful operation, with the operation's primary data, if any. A result
+/// without data is serialized as an empty object.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationResult {
+    #[serde(default, deserialize_with = "deserialize_some", skip_serializing_if = "Option::is_none")]
+    pub data: Option<PrimaryData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl OperationsDocument {
+    /// Performs the operations in order with `perform`, stopping at the first one that fails.
+    ///
+    /// Before an operation is performed, every `lid` in it that references a resource added by
+    /// an earlier operation is replaced with the `id` that resource was given, as found in the
+    /// `data` of the earlier operation's result. The errors of a failed operation are rebased
+    /// onto the request document with `rebase_error`.
+    ///
+    /// The operations **MUST** be performed atomically, so `perform` is expected to run them in a
+    /// transaction that is rolled back when an error is returned.
+    pub fn perform<F>(self, mut perform: F) -> Result<ResultsDocument, Vec<Error>>
+        where F: FnMut(Operation) -> Result<OperationResult, Vec<Error>>,
+    {
+        let mut local_ids = LocalIds::new();
+        // Every lid an `add` declared, including those whose result didn't report an `id`.
+        let mut declared = BTreeSet::new();
+        let mut results = Vec::with_capacity(self.operations.len());
+
+        for (index, mut operation) in self.operations.into_iter().enumerate() {
+            local_ids.resolve(&mut operation).map_err(|pointer| {
+                vec![invalid_lid(index, pointer, "a lid MUST reference a resource added by an earlier operation")]
+            })?;
+
+            let added = operation.added_lid();
+
+            if let Some(ref added) = added {
+                if !declared.insert(added.clone()) {
+                    let pointer = JsonPointer::root().child("data").child("lid");
+                    return Err(vec![invalid_lid(index, pointer, "a lid MUST be unique for each type")]);
+                }
+            }
+
+            let result = perform(operation)
+                .map_err(|errors| errors.into_iter().map(|error| rebase_error(index, error)).collect::<Vec<_>>())?;
+
+            if let (Some((type_, lid)), Some(PrimaryData::Single(resource))) = (added, result.data.as_ref()) {
+                if let Some(ref id) = resource.id {
+                    local_ids.insert(type_, lid, id.clone());
+                }
+            }
+
+            results.push(result);
+        }
+
+        Ok(ResultsDocument {
+            jsonapi: None,
+            results,
+            meta: None,
+        })
+    }
+}
+
+impl Operation {
+    /// The relationship targeted by `ref`, if any.
+    pub fn relationship(&self) -> Option<&Name> {
+        match self.target {
+            Some(Target::Ref(ref ref_)) => ref_.relationship.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The `type` and `lid` of a resource without an `id` that this operation adds.
+    fn added_lid(&self) -> Option<(Type, String)> {
+        if self.op != Op::Add || self.relationship().is_some() {
+            return None;
+        }
+
+        match self.data {
+            Some(PrimaryData::Single(ref resource)) if resource.id.is_none() => {
+                resource.lid.clone().map(|lid| (resource.type_.clone(), lid))
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks that the members of the operation fit its `op` and target.
+    fn check(&self) -> Result<(), &'static str> {
+        let relationship = self.relationship().is_some();
+        let href = matches!(self.target, Some(Target::Href(_)));
+
+        match self.data {
+            None if self.op != Op::Remove => Err("an add or update operation MUST contain a data member"),
+            None if relationship => {
+                Err("an operation that removes members of a relationship MUST contain a data member")
+            }
+            None if self.target.is_none() => {
+                Err("an operation that removes a resource MUST contain a ref or href member")
+            }
+            None => Ok(()),
+            Some(ref data) if relationship => {
+                match *data {
+                    PrimaryData::ArrayIdentifier(_) => Ok(()),
+                    _ if self.op == Op::Update => Ok(()),
+                    _ => {
+                        Err("the data of an operation that adds or removes members of a relationship MUST be an \
+                             array of resource identifier objects")
+                    }
+                }
+            }
+            Some(_) if href => Ok(()),
+            Some(_) if self.op == Op::Remove => {
+                Err("an operation that removes a resource MUST NOT contain a data member")
+            }
+            Some(PrimaryData::Single(_)) => Ok(()),
+            Some(_) => Err("the data of an operation on a resource MUST be a single resource object"),
+        }
+    }
+}
+
+impl Serialize for Operation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct RawOperation<'a> {
+            op: Op,
+            #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+            ref_: Option<&'a Ref>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            href: Option<&'a Uri>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            data: Option<&'a PrimaryData>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            meta: Option<&'a Meta>,
+        }
+
+        let (ref_, href) = match self.target {
+            Some(Target::Ref(ref ref_)) => (Some(ref_), None),
+            Some(Target::Href(ref href)) => (None, Some(href)),
+            None => (None, None),
+        };
+
+        RawOperation {
+            op: self.op,
+            ref_,
+            href,
+            data: self.data.as_ref(),
+            meta: self.meta.as_ref(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Operation {
+    fn deserialize<D>(deserializer: D) -> Result<Operation, D::Error>
+        where D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawOperation {
+            op: Op,
+            #[serde(rename = "ref")]
+            ref_: Option<Ref>,
+            href: Option<Uri>,
+            #[serde(default, deserialize_with = "deserialize_some")]
+            data: Option<serde_json::Value>,
+            meta: Option<Meta>,
+        }
+
+        let raw = RawOperation::deserialize(deserializer)?;
+
+        let target = match (raw.ref_, raw.href) {
+            (Some(_), Some(_)) => {
+                return Err(de::Error::custom("an operation MUST NOT contain both ref and href members"));
+            }
+            (Some(ref_), None) => Some(Target::Ref(ref_)),
+            (None, Some(href)) => Some(Target::Href(href)),
+            (None, None) => None,
+        };
+
+        // The data of an `add` or `update` that doesn't name a relationship is always a resource
+        // object, even when it has nothing but a `type` and a `lid`. Any other `href` of a `remove`
+        // may still address a relationship, so its data is classified by its members.
+        let endpoint = match target {
+            Some(Target::Ref(Ref { relationship: Some(_), .. })) => Endpoint::Relationship,
+            Some(Target::Href(ref href)) if is_relationship_href(href) => Endpoint::Relationship,
+            Some(Target::Href(_)) if raw.op == Op::Remove => Endpoint::Unknown,
+            _ => Endpoint::Resource,
+        };
+
+        let data = match raw.data {
+            Some(data) => {
+                let seed = PrimaryDataSeed::new(endpoint).strict(true);
+                Some(seed.deserialize(data).map_err(de::Error::custom)?)
+            }
+            None => None,
+        };
+
+        let operation = Operation {
+            op: raw.op,
+            target,
+            data,
+            meta: raw.meta,
+        };
+
+        operation.check().map_err(de::Error::custom)?;
+
+        Ok(operation)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ref {
+    fn deserialize<D>(deserializer: D) -> Result<Ref, D::Error>
+        where D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RawRef {
+            #[serde(rename = "type")]
+            type_: Type,
+            id: Option<Id>,
+            lid: Option<String>,
+            relationship: Option<Name>,
+        }
+
+        let raw = RawRef::deserialize(deserializer)?;
+
+        if raw.id.is_some() == raw.lid.is_some() {
+            return Err(de::Error::custom("a ref MUST contain either an id or a lid member"));
+        }
+
+        Ok(Ref {
+            type_: raw.type_,
+            id: raw.id,
+            lid: raw.lid,
+            relationship: raw.relationship,
+        })
+    }
+}
+
+/// The ids given to the resources added with a `lid`, by `type` and `lid`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LocalIds(BTreeMap<(Type, String), Id>);
+
+impl LocalIds {
+    pub fn new() -> LocalIds {
+        LocalIds::default()
+    }
+
+    /// Records that the resource of `type_` added with `lid` was given `id`.
+    pub fn insert(&mut self, type_: Type, lid: String, id: Id) -> Option<Id> {
+        self.0.insert((type_, lid), id)
+    }
+
+    pub fn get(&self, type_: &Type, lid: &str) -> Option<&Id> {
+        self.0.get(&(type_.clone(), lid.to_owned()))
+    }
+
+    /// Replaces each `lid` in `operation` with the `id` of the resource it references. The
+    /// resource that an `add` operation adds keeps its own `lid`.
+    ///
+    /// Fails with the pointer, within the operation, of a `lid` that references no known
+    /// resource.
+    pub fn resolve(&self, operation: &mut Operation) -> Result<(), JsonPointer> {
+        let adds = operation.op == Op::Add;
+
+        if let Some(Target::Ref(ref mut ref_)) = operation.target {
+            self.resolve_lid(&ref_.type_, &mut ref_.id, &mut ref_.lid, JsonPointer::root().child("ref"))?;
+        }
+
+        let data = match operation.data {
+            Some(ref mut data) => data,
+            None => return Ok(()),
+        };
+
+        let single = matches!(*data, PrimaryData::Single(_) | PrimaryData::SingleIdentifier(_));
+        let pointer = |index: usize| {
+            let pointer = JsonPointer::root().child("data");
+            if single { pointer } else { pointer.child(index) }
+        };
+
+        for (index, resource) in data.resources_mut().iter_mut().enumerate() {
+            self.resolve_resource(resource, !adds, &pointer(index))?;
+        }
+
+        for (index, identifier) in data.identifiers_mut().iter_mut().enumerate() {
+            self.resolve_identifier(identifier, pointer(index))?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve_resource(&self,
+                        resource: &mut ResourceObject,
+                        resolve_own: bool,
+                        pointer: &JsonPointer)
+                        -> Result<(), JsonPointer> {
+        if resolve_own {
+            self.resolve_lid(&resource.type_, &mut resource.id, &mut resource.lid, pointer.clone())?;
+        }
+
+        let relationships = match resource.relationships {
+            Some(ref mut relationships) => relationships,
+            None => return Ok(()),
+        };
+
+        for (name, relationship) in &mut relationships.0 {
+            let pointer = pointer.child("relationships").child(name).child("data");
+
+            match relationship.data {
+                Some(ResourceLinkage::Single(ref mut identifier)) => self.resolve_identifier(identifier, pointer)?,
+                Some(ResourceLinkage::Array(ref mut identifiers)) => {
+                    for (index, identifier) in identifiers.iter_mut().enumerate() {
+                        self.resolve_identifier(identifier, pointer.child(index))?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_identifier(&self,
+                          identifier: &mut ResourceIdentifierObject,
+                          pointer: JsonPointer)
+                          -> Result<(), JsonPointer> {
+        self.resolve_lid(&identifier.type_, &mut identifier.id, &mut identifier.lid, pointer)
+    }
+
+    /// Replaces `lid` with the `id` it references, unless there already is an `id`.
+    fn resolve_lid(&self,
+                   type_: &Type,
+                   id: &mut Option<Id>,
+                   lid: &mut Option<String>,
+                   pointer: JsonPointer)
+                   -> Result<(), JsonPointer> {
+        if id.is_some() {
+            return Ok(());
+        }
+
+        if let Some(ref local) = *lid {
+            *id = Some(self.get(type_, local).cloned().ok_or_else(|| pointer.child("lid"))?);
+            *lid = None;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `href` is a relationship link, which by the recommended URL design ends in
+/// `/relationships/NAME`: https://jsonapi.org/recommendations/#urls-relationships
+fn is_relationship_href(href: &Uri) -> bool {
+    let mut segments = href.path().trim_end_matches('/').rsplit('/');
+
+    match (segments.next(), segments.next()) {
+        (Some(name), Some("relationships")) => !name.is_empty(),
+        _ => false,
+    }
+}
+
+/// The pointer to the operation at `index` in a request document.
+pub fn operation_pointer(index: usize) -> JsonPointer {
+    JsonPointer::root().child("atomic:operations").child(index)
+}
+
+/// Rebases the `source.pointer` of an error raised by the operation at `index`, which is relative
+/// to the operation, onto the request document: `/data/attributes/title` becomes
+/// `/atomic:operations/1/data/attributes/title`. An error without a source is pointed at the
+/// operation itself.
+pub fn rebase_error(index: usize, mut error: Error) -> Error {
+    let operation = operation_pointer(index);
+
+    match error.source {
+        Some(Source { pointer: Some(ref mut pointer), .. }) => {
+            *pointer = operation.tokens().iter().chain(pointer.tokens()).collect();
+        }
+        Some(_) => {}
+        None => {
+            error.source = Some(Source {
+                pointer: Some(operation),
+                ..Source::default()
+            })
+        }
+    }
+
+    error
+}
+
+fn invalid_lid(index: usize, pointer: JsonPointer, detail: &str) -> Error {
+    rebase_error(index,
+                 Error {
+                     status: Some("400".to_owned()),
+                     title: Some("Invalid lid".to_owned()),
+                     detail: Some(detail.to_owned()),
+                     source: Some(Source {
+                         pointer: Some(pointer),
+                         ..Source::default()
+                     }),
+                     ..Error::default()
+                 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{self, json, Value};
+
+    fn operations(value: Value) -> OperationsDocument {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn round_trip(value: Value) {
+        let document = operations(value.clone());
+        assert_eq!(serde_json::to_value(&document).unwrap(), value);
+    }
+
+    fn added(type_: &str, id: &str) -> OperationResult {
+        OperationResult {
+            data: Some(PrimaryData::Single(serde_json::from_value(json!({"type": type_, "id": id})).unwrap())),
+            meta: None,
+        }
+    }
+
+    fn pointer(error: &Error) -> String {
+        error.source.as_ref().unwrap().pointer.as_ref().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_round_trip_resource_examples() {
+        round_trip(json!({
+            "atomic:operations": [{
+                "op": "add",
+                "href": "/blogPosts",
+                "data": {
+                    "type": "articles",
+                    "attributes": {"title": "JSON API paints my bikeshed!"}
+                }
+            }]
+        }));
+        round_trip(json!({
+            "atomic:operations": [{
+                "op": "update",
+                "data": {
+                    "type": "articles",
+                    "id": "13",
+                    "attributes": {"title": "To TDD or Not"}
+                }
+            }]
+        }));
+        round_trip(json!({
+            "atomic:operations": [{
+                "op": "remove",
+                "ref": {"type": "articles", "id": "13"}
+            }]
+        }));
+    }
+
+    #[test]
+    fn test_round_trip_relationship_examples() {
+        round_trip(json!({
+            "atomic:operations": [{
+                "op": "update",
+                "ref": {"type": "articles", "id": "13", "relationship": "author"},
+                "data": {"type": "people", "id": "9"}
+            }]
+        }));
+        round_trip(json!({
+            "atomic:operations": [{
+                "op": "update",
+                "ref": {"type": "articles", "id": "13", "relationship": "author"},
+                "data": null
+            }]
+        }));
+        for op in &["add", "update", "remove"] {
+            round_trip(json!({
+                "atomic:operations": [{
+                    "op": op,
+                    "ref": {"type": "articles", "id": "1", "relationship": "comments"},
+                    "data": [{"type": "comments", "id": "123"}]
+                }]
+            }));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_lid_example() {
+        let value = json!({
+            "atomic:operations": [{
+                "op": "add",
+                "data": {
+                    "type": "authors",
+                    "lid": "a",
+                    "attributes": {"name": "dgeb"}
+                }
+            }, {
+                "op": "add",
+                "data": {
+                    "type": "articles",
+                    "lid": "b",
+                    "attributes": {"title": "Ember Hamster"},
+                    "relationships": {
+                        "author": {"data": {"type": "authors", "lid": "a"}}
+                    }
+                }
+            }]
+        });
+        round_trip(value);
+
+        // The data of an `add` is a resource object, even with only a `type` and a `lid`.
+        let document = operations(json!({
+            "atomic:operations": [{"op": "add", "href": "/authors", "data": {"type": "authors", "lid": "a"}}]
+        }));
+        assert!(matches!(document.operations[0].data, Some(PrimaryData::Single(_))));
+    }
+
+    #[test]
+    fn test_round_trip_results() {
+        let value = json!({
+            "atomic:results": [{
+                "data": {
+                    "links": {"self": "http://example.com/blogPosts/13"},
+                    "type": "articles",
+                    "id": "13",
+                    "attributes": {"title": "JSON API paints my bikeshed!"}
+                }
+            }, {}]
+        });
+        let document: ResultsDocument = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(document.results[1], OperationResult::default());
+        assert_eq!(serde_json::to_value(&document).unwrap(), value);
+    }
+
+    #[test]
+    fn test_ref_and_href_rejected() {
+        let value = json!({
+            "atomic:operations": [{
+                "op": "remove",
+                "ref": {"type": "articles", "id": "13"},
+                "href": "/articles/13"
+            }]
+        });
+        let error = serde_json::from_value::<OperationsDocument>(value).unwrap_err();
+        assert!(error.to_string().contains("MUST NOT contain both ref and href"));
+
+        let value = json!({
+            "atomic:operations": [{"op": "remove", "ref": {"type": "articles", "id": "13", "lid": "a"}}]
+        });
+        assert!(serde_json::from_value::<OperationsDocument>(value).is_err());
+    }
+
+    #[test]
+    fn test_perform_resolves_lids() {
+        let document = operations(json!({
+            "atomic:operations": [{
+                "op": "add",
+                "data": {"type": "authors", "lid": "a", "attributes": {"name": "dgeb"}}
+            }, {
+                "op": "add",
+                "data": {
+                    "type": "articles",
+                    "lid": "b",
+                    "relationships": {
+                        "author": {"data": {"type": "authors", "lid": "a"}},
+                        "editors": {"data": [{"type": "authors", "id": "7"}, {"type": "authors", "lid": "a"}]}
+                    }
+                }
+            }, {
+                "op": "update",
+                "ref": {"type": "articles", "lid": "b", "relationship": "reviewers"},
+                "data": [{"type": "authors", "lid": "a"}]
+            }, {
+                "op": "update",
+                "data": {"type": "authors", "lid": "a", "attributes": {"name": "Dan"}}
+            }]
+        }));
+
+        let mut performed = Vec::new();
+        let results = document.perform(|operation| {
+            let result = match operation.added_lid() {
+                Some((ref type_, _)) if type_.0.as_str() == "authors" => added("authors", "1"),
+                Some(_) => added("articles", "2"),
+                None => OperationResult::default(),
+            };
+            performed.push(serde_json::to_value(&operation).unwrap());
+            Ok(result)
+        }).unwrap();
+
+        assert_eq!(results.results.len(), 4);
+        assert_eq!(performed[0]["data"]["lid"], "a");
+        assert_eq!(performed[1]["data"]["lid"], "b");
+        assert_eq!(performed[1]["data"]["relationships"]["author"]["data"],
+                   json!({"type": "authors", "id": "1"}));
+        assert_eq!(performed[1]["data"]["relationships"]["editors"]["data"],
+                   json!([{"type": "authors", "id": "7"}, {"type": "authors", "id": "1"}]));
+        assert_eq!(performed[2]["ref"], json!({"type": "articles", "id": "2", "relationship": "reviewers"}));
+        assert_eq!(performed[2]["data"], json!([{"type": "authors", "id": "1"}]));
+        assert_eq!(performed[3]["data"], json!({"type": "authors", "id": "1", "attributes": {"name": "Dan"}}));
+    }
+
+    #[test]
+    fn test_perform_resolves_lids_in_relationship_hrefs() {
+        let document = operations(json!({
+            "atomic:operations": [{
+                "op": "add",
+                "data": {"type": "comments", "lid": "c"}
+            }, {
+                "op": "add",
+                "href": "/articles/1/relationships/comments",
+                "data": [{"type": "comments", "lid": "c"}]
+            }, {
+                "op": "update",
+                "href": "https://example.com/articles/1/relationships/author/",
+                "data": {"type": "comments", "lid": "c"}
+            }, {
+                "op": "add",
+                "href": "/comments",
+                "data": {"type": "comments", "lid": "d"}
+            }]
+        }));
+
+        let mut performed = Vec::new();
+        let results = document.perform(|operation| {
+            let result = match operation.added_lid() {
+                Some((_, ref lid)) if lid == "c" => added("comments", "5"),
+                Some(_) => added("comments", "6"),
+                None => OperationResult::default(),
+            };
+            performed.push(serde_json::to_value(&operation).unwrap());
+            Ok(result)
+        }).unwrap();
+
+        assert_eq!(results.results.len(), 4);
+        assert_eq!(performed[1]["data"], json!([{"type": "comments", "id": "5"}]));
+        assert_eq!(performed[2]["data"], json!({"type": "comments", "id": "5"}));
+        assert_eq!(performed[3]["data"], json!({"type": "comments", "lid": "d"}));
+    }
+
+    #[test]
+    fn test_is_relationship_href() {
+        for href in &["/articles/1/relationships/comments", "/articles/1/relationships/author/",
+                      "https://example.com/articles/1/relationships/author?include=author"] {
+            assert!(is_relationship_href(&Uri::parse(href).unwrap()), "{}", href);
+        }
+
+        for href in &["/articles/1", "/articles/1/author", "/articles/1/relationships", "/relationships/"] {
+            assert!(!is_relationship_href(&Uri::parse(href).unwrap()), "{}", href);
+        }
+    }
+
+    #[test]
+    fn test_perform_unknown_lid() {
+        let document = operations(json!({
+            "atomic:operations": [{
+                "op": "remove",
+                "ref": {"type": "articles", "id": "1"}
+            }, {
+                "op": "remove",
+                "ref": {"type": "articles", "lid": "missing"}
+            }]
+        }));
+
+        let errors = document.perform(|_| Ok(OperationResult::default())).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].status.as_deref(), Some("400"));
+        assert_eq!(pointer(&errors[0]), "/atomic:operations/1/ref/lid");
+    }
+
+    #[test]
+    fn test_perform_duplicate_lid() {
+        // The first `add` isn't given an `id`, but its `lid` is still taken.
+        let document = operations(json!({
+            "atomic:operations": [{
+                "op": "add",
+                "data": {"type": "authors", "lid": "a"}
+            }, {
+                "op": "add",
+                "data": {"type": "authors", "lid": "a"}
+            }]
+        }));
+
+        let mut count = 0;
+        let errors = document.perform(|_| {
+            count += 1;
+            Ok(OperationResult::default())
+        }).unwrap_err();
+        assert_eq!(count, 1);
+        assert_eq!(pointer(&errors[0]), "/atomic:operations/1/data/lid");
+    }
+
+    #[test]
+    fn test_perform_rebases_errors() {
+        let document = operations(json!({
+            "atomic:operations": [{
+                "op": "update",
+                "data": {"type": "articles", "id": "13", "attributes": {"title": ""}}
+            }]
+        }));
+
+        let errors = document.perform(|_| {
+            Err(vec![Error {
+                         source: Some(Source {
+                             pointer: Some(JsonPointer::parse("/data/attributes/title").unwrap()),
+                             ..Source::default()
+                         }),
+                         ..Error::default()
+                     }])
+        }).unwrap_err();
+        assert_eq!(pointer(&errors[0]), "/atomic:operations/0/data/attributes/title");
+    }
+
+    #[test]
+    fn test_rebase_error() {
+        let error = rebase_error(2, Error::default());
+        assert_eq!(pointer(&error), "/atomic:operations/2");
+
+        let error = Error {
+            source: Some(Source {
+                pointer: Some(JsonPointer::parse("/data/relationships/a~1b").unwrap()),
+                ..Source::default()
+            }),
+            ..Error::default()
+        };
+        assert_eq!(pointer(&rebase_error(1, error)), "/atomic:operations/1/data/relationships/a~1b");
+
+        let error = Error {
+            source: Some(Source {
+                parameter: Some("include".to_owned()),
+                ..Source::default()
+            }),
+            ..Error::default()
+        };
+        let rebased = rebase_error(0, error.clone());
+        assert_eq!(rebased, error);
+    }
+}
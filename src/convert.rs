@@ -0,0 +1,831 @@
+//! Conversions between the `schema` and `spec` models of a document.
+//!
+//! Both models describe the same JSON, so converting a document gives the same result as
+//! serializing it and reading the JSON as the other model. Where reading JSON ignores a member,
+//! such as a `null` link or an empty `included`, so does the conversion, and primary data without
+//! `attributes`, `relationships` or `links` becomes resource identifier objects in the `spec`
+//! model, as with `spec::Endpoint::Unknown`.
+//!
+//! When one model can represent something the other can't, the conversion fails with a
+//! `ConversionError` naming the rule that the input breaks.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use serde_json;
+
+use error::ValidationError;
+use schema;
+use spec;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConversionError {
+    /// http://jsonapi.org/format/#document-top-level
+    ///
+    /// A `schema::Info` document only contains `meta`, which is valid, but a `spec::Document`
+    /// has no variant without `data` or `errors`.
+    MetaOnlyDocument,
+
+    /// `#/definitions/data` is either a resource or an array of resources, so the schema cannot
+    /// represent `null` primary data.
+    NullPrimaryData,
+
+    /// `#/definitions/resource` and `#/definitions/linkage` require an `id`, which a resource
+    /// object that originates at the client **MAY** omit, and which a resource identifier object
+    /// **MAY** replace with a `lid` since JSON API 1.1.
+    MissingId {
+        type_: String,
+    },
+
+    /// The schema describes JSON API 1.0, so it has no place for a member added in JSON API 1.1,
+    /// such as `lid`, `jsonapi.ext` or the `rel` of a link object.
+    UnsupportedMember {
+        member: &'static str,
+    },
+
+    /// `#/definitions/linkage` has `"additionalProperties": false`, so it cannot carry the `meta`
+    /// of a resource identifier object.
+    LinkageMeta {
+        type_: String,
+        id: String,
+    },
+
+    /// http://jsonapi.org/format/#document-links
+    ///
+    /// A link **MUST** be represented as either a string containing the link's URL or a link
+    /// object.
+    InvalidLink {
+        name: String,
+    },
+
+    /// `#/definitions/links` and `#/definitions/pagination` only allow a string URL for `self`,
+    /// `first`, `last`, `prev` and `next`.
+    LinkObjectNotAllowed {
+        name: String,
+    },
+
+    /// `#/definitions/failure` has no `links` member.
+    FailureLinks,
+
+    /// `#/definitions/attributes` and `#/definitions/relationships` only allow member names
+    /// matching `\w[-\w_]*`, and attributes may not be named `relationships` or `links`.
+    Validation(ValidationError),
+
+    /// http://jsonapi.org/format/#document-member-names
+    ///
+    /// A member name or `type` accepted by the schema that breaks the member name rules of the
+    /// specification.
+    InvalidName {
+        name: String,
+        error: spec::NameError,
+    },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConversionError::MetaOnlyDocument => {
+                write!(f, "a meta-only document can't be represented by spec::Document")
+            }
+            ConversionError::NullPrimaryData => {
+                write!(f, "the schema's primary data MUST be a resource or an array of resources")
+            }
+            ConversionError::MissingId { ref type_ } => {
+                write!(f, "the schema requires an id for the resource of type `{}`", type_)
+            }
+            ConversionError::UnsupportedMember { member } => {
+                write!(f, "the schema has no `{}` member, which was added in JSON API 1.1", member)
+            }
+            ConversionError::LinkageMeta { ref type_, ref id } => {
+                write!(f, "the schema's linkage does not allow meta on `{}` `{}`", type_, id)
+            }
+            ConversionError::InvalidLink { ref name } => {
+                write!(f, "the link `{}` MUST be either a string or a link object", name)
+            }
+            ConversionError::LinkObjectNotAllowed { ref name } => {
+                write!(f, "the schema requires the link `{}` to be a string", name)
+            }
+            ConversionError::FailureLinks => {
+                write!(f, "the schema does not allow links in a failure document")
+            }
+            ConversionError::Validation(ref error) => error.fmt(f),
+            ConversionError::InvalidName { ref name, ref error } => write!(f, "`{}`: {}", name, error),
+        }
+    }
+}
+
+impl error::Error for ConversionError {}
+
+impl From<ValidationError> for ConversionError {
+    fn from(error: ValidationError) -> ConversionError {
+        ConversionError::Validation(error)
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// schema -> spec
+
+fn spec_name(name: String) -> Result<spec::Name, ConversionError> {
+    spec::Name::parse(name.as_str()).map_err(|error| ConversionError::InvalidName { name, error })
+}
+
+impl TryFrom<schema::Document> for spec::Document {
+    type Error = ConversionError;
+
+    fn try_from(document: schema::Document) -> Result<spec::Document, ConversionError> {
+        match document {
+            schema::Document::Success(success) => {
+                Ok(spec::Document::Data {
+                    jsonapi: success.jsonapi.map(spec_jsonapi),
+                    data: spec_primary_data(success.data)?,
+                    meta: success.meta.map(spec_meta),
+                    links: success.links.map(spec_top_level_links).transpose()?,
+                    included: spec_resource_objects(success.included.unwrap_or_default())?,
+                })
+            }
+            schema::Document::Failure(failure) => {
+                Ok(spec::Document::Error {
+                    jsonapi: failure.jsonapi.map(spec_jsonapi),
+                    errors: failure.errors.into_iter().map(spec_error).collect::<Result<_, _>>()?,
+                    meta: failure.meta.map(spec_meta),
+                    links: None,
+                })
+            }
+            schema::Document::Info(_) => Err(ConversionError::MetaOnlyDocument),
+        }
+    }
+}
+
+/// Reads primary data the way `spec::Document` reads it from JSON, where an object without
+/// `attributes`, `relationships` or `links` is a resource identifier object.
+fn spec_primary_data(data: schema::Data) -> Result<spec::PrimaryData, ConversionError> {
+    fn is_identifier(resource: &schema::Resource) -> bool {
+        resource.attributes.is_none() && resource.relationships.is_none() && resource.links.is_none()
+    }
+
+    match data {
+        schema::Data::Single(resource) => {
+            if is_identifier(&resource) {
+                spec_resource_identifier(resource).map(spec::PrimaryData::SingleIdentifier)
+            } else {
+                spec_resource(resource).map(spec::PrimaryData::Single)
+            }
+        }
+        schema::Data::Array(resources) => {
+            if !resources.is_empty() && resources.iter().all(is_identifier) {
+                let identifiers = resources.into_iter().map(spec_resource_identifier).collect::<Result<_, _>>()?;
+                Ok(spec::PrimaryData::ArrayIdentifier(identifiers))
+            } else {
+                spec_resource_objects(resources).map(spec::PrimaryData::Array)
+            }
+        }
+    }
+}
+
+fn spec_resource_identifier(resource: schema::Resource) -> Result<spec::ResourceIdentifierObject, ConversionError> {
+    let mut identifier = spec::ResourceIdentifierObject::new(spec::Type(spec_name(resource.type_)?),
+                                                             spec::Id(resource.id));
+    identifier.meta = resource.meta.map(spec_meta);
+
+    Ok(identifier)
+}
+
+fn spec_resource_objects(resources: Vec<schema::Resource>) -> Result<Vec<spec::ResourceObject>, ConversionError> {
+    resources.into_iter().map(spec_resource).collect()
+}
+
+fn spec_resource(resource: schema::Resource) -> Result<spec::ResourceObject, ConversionError> {
+    let relationships = match resource.relationships {
+        Some(relationships) => {
+            let relationships = relationships.into_relationships()
+                .into_iter()
+                .map(|(name, relationship)| Ok((spec_name(name)?, spec_relationship(relationship)?)))
+                .collect::<Result<_, ConversionError>>()?;
+
+            Some(spec::Relationships(relationships))
+        }
+        None => None,
+    };
+
+    Ok(spec::ResourceObject {
+        id: Some(spec::Id(resource.id)),
+        lid: None,
+        type_: spec::Type(spec_name(resource.type_)?),
+        attributes: resource.attributes.map(|attributes| {
+            spec::Attributes(attributes.into_attributes().0.into_iter().collect())
+        }),
+        relationships,
+        links: resource.links.map(spec_links).transpose()?.map(spec::ResourceLinks),
+        meta: resource.meta.map(spec_meta),
+    })
+}
+
+fn spec_relationship(relationship: schema::Relationship) -> Result<spec::Relationship, ConversionError> {
+    Ok(spec::Relationship {
+        links: relationship.links.map(spec_links).transpose()?,
+        data: relationship.data.map(|data| {
+            match data {
+                schema::RelationshipData::Empty => Ok(spec::ResourceLinkage::Null),
+                schema::RelationshipData::Single(linkage) => {
+                    spec_identifier(linkage).map(spec::ResourceLinkage::Single)
+                }
+                schema::RelationshipData::Array(linkages) => {
+                    let identifiers = linkages.into_iter().map(spec_identifier).collect::<Result<_, _>>()?;
+                    Ok(spec::ResourceLinkage::Array(identifiers))
+                }
+            }
+        }).transpose()?,
+        meta: relationship.meta.map(spec_meta),
+    })
+}
+
+fn spec_identifier(linkage: schema::Linkage) -> Result<spec::ResourceIdentifierObject, ConversionError> {
+    Ok(spec::ResourceIdentifierObject::new(spec::Type(spec_name(linkage.type_)?), spec::Id(linkage.id)))
+}
+
+fn spec_top_level_links(links: schema::LinksAndPagination) -> Result<spec::TopLevelLinks, ConversionError> {
+    let schema::LinksAndPagination { pagination, links } = links;
+    let mut spec_links = spec_links(schema::Links {
+        self_: None,
+        related: None,
+        links: links.links,
+    })?;
+
+    let pagination = vec![
+        ("first", pagination.first),
+        ("last", pagination.last),
+        ("prev", pagination.prev),
+        ("next", pagination.next),
+    ];
+
+    for (name, href) in pagination {
+        if let Some(href) = href {
+            spec_links.0.insert(spec_name(name.to_owned())?, spec::Link::Url(href));
+        }
+    }
+
+    Ok(spec::TopLevelLinks {
+        self_: links.self_.map(spec::Link::Url),
+        related: links.related.map(spec_link),
+        describedby: spec_links.0.remove("describedby"),
+        links: spec_links,
+    })
+}
+
+fn spec_links(links: schema::Links) -> Result<spec::Links, ConversionError> {
+    let mut spec_links = BTreeMap::new();
+
+    if let Some(href) = links.self_ {
+        spec_links.insert(spec_name("self".to_owned())?, spec::Link::Url(href));
+    }
+
+    if let Some(link) = links.related {
+        spec_links.insert(spec_name("related".to_owned())?, spec_link(link));
+    }
+
+    for (name, value) in links.links.0 {
+        // A `null` link is the same as an omitted one.
+        if value.is_null() {
+            continue;
+        }
+
+        let link = serde_json::from_value(value).map_err(|_| ConversionError::InvalidLink { name: name.clone() })?;
+        spec_links.insert(spec_name(name)?, link);
+    }
+
+    Ok(spec::Links(spec_links))
+}
+
+fn spec_link(link: schema::Link) -> spec::Link {
+    match link {
+        schema::Link::Url(href) => spec::Link::Url(href),
+        schema::Link::Object { href, meta } => {
+            spec::Link::Object {
+                href,
+                rel: None,
+                describedby: None,
+                title: None,
+                type_: None,
+                hreflang: Vec::new(),
+                meta: meta.map(spec_meta),
+            }
+        }
+    }
+}
+
+fn spec_meta(meta: schema::Meta) -> spec::Meta {
+    spec::Meta((meta.0).0.into_iter().collect())
+}
+
+fn spec_jsonapi(jsonapi: schema::JsonAPI) -> spec::JsonAPIObject {
+    spec::JsonAPIObject {
+        version: jsonapi.version,
+        ext: Vec::new(),
+        profile: Vec::new(),
+        meta: jsonapi.meta.map(spec_meta),
+    }
+}
+
+fn spec_error(error: schema::Error) -> Result<spec::Error, ConversionError> {
+    Ok(spec::Error {
+        id: error.id,
+        links: error.links.map(spec_links).transpose()?,
+        status: error.status,
+        code: error.code,
+        title: error.title,
+        detail: error.detail,
+        source: error.source.map(|source| {
+            spec::Source {
+                pointer: source.pointer,
+                parameter: source.parameter,
+                header: source.header,
+            }
+        }),
+        meta: error.meta.map(spec_meta),
+    })
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// spec -> schema
+
+impl TryFrom<spec::Document> for schema::Document {
+    type Error = ConversionError;
+
+    fn try_from(document: spec::Document) -> Result<schema::Document, ConversionError> {
+        match document {
+            spec::Document::Data { jsonapi, data, meta, links, included } => {
+                let data = match data {
+                    spec::PrimaryData::Null => return Err(ConversionError::NullPrimaryData),
+                    spec::PrimaryData::Single(resource) => schema::Data::Single(schema_resource(resource)?),
+                    spec::PrimaryData::SingleIdentifier(identifier) => {
+                        schema::Data::Single(schema_identifier(identifier)?)
+                    }
+                    spec::PrimaryData::Array(resources) => {
+                        schema::Data::Array(resources.into_iter().map(schema_resource).collect::<Result<_, _>>()?)
+                    }
+                    spec::PrimaryData::ArrayIdentifier(identifiers) => {
+                        schema::Data::Array(identifiers.into_iter().map(schema_identifier).collect::<Result<_, _>>()?)
+                    }
+                };
+
+                let included = if included.is_empty() {
+                    None
+                } else {
+                    Some(included.into_iter().map(schema_resource).collect::<Result<_, _>>()?)
+                };
+
+                Ok(schema::Document::Success(schema::Success {
+                    data,
+                    included,
+                    meta: meta.map(schema_meta),
+                    links: links.map(schema_links_and_pagination).transpose()?,
+                    jsonapi: jsonapi.map(schema_jsonapi).transpose()?,
+                }))
+            }
+            spec::Document::Error { jsonapi, errors, meta, links } => {
+                if links.is_some() {
+                    return Err(ConversionError::FailureLinks);
+                }
+
+                Ok(schema::Document::Failure(schema::Failure {
+                    errors: errors.into_iter().map(schema_error).collect::<Result<_, _>>()?,
+                    meta: meta.map(schema_meta),
+                    jsonapi: jsonapi.map(schema_jsonapi).transpose()?,
+                }))
+            }
+        }
+    }
+}
+
+fn schema_resource(resource: spec::ResourceObject) -> Result<schema::Resource, ConversionError> {
+    let id = match resource.id {
+        Some(id) => id.as_str().to_owned(),
+        None => return Err(ConversionError::MissingId { type_: resource.type_.as_str().to_owned() }),
+    };
+
+    if resource.lid.is_some() {
+        return Err(ConversionError::UnsupportedMember { member: "lid" });
+    }
+
+    let attributes = match resource.attributes {
+        Some(attributes) => Some(schema::Attributes::new(schema::Object(attributes.0.into_iter().collect()))?),
+        None => None,
+    };
+
+    let relationships = match resource.relationships {
+        Some(relationships) => {
+            let mut schema_relationships = BTreeMap::new();
+
+            for (name, relationship) in relationships.0 {
+                schema_relationships.insert(name.as_str().to_owned(), schema_relationship(relationship)?);
+            }
+
+            Some(schema::Relationships::new(schema_relationships)?)
+        }
+        None => None,
+    };
+
+    let resource = schema::Resource {
+        type_: resource.type_.as_str().to_owned(),
+        id,
+        attributes,
+        relationships,
+        links: resource.links.map(|links| schema_links(links.0)).transpose()?,
+        meta: resource.meta.map(schema_meta),
+    };
+
+    resource.check_fields()?;
+
+    Ok(resource)
+}
+
+fn schema_identifier(identifier: spec::ResourceIdentifierObject) -> Result<schema::Resource, ConversionError> {
+    Ok(schema::Resource {
+        type_: identifier.type_.as_str().to_owned(),
+        id: schema_identifier_id(&identifier)?,
+        attributes: None,
+        relationships: None,
+        links: None,
+        meta: identifier.meta.map(schema_meta),
+    })
+}
+
+/// The `id` of a resource identifier object, which the schema requires, and which it can't
+/// replace with a `lid`.
+fn schema_identifier_id(identifier: &spec::ResourceIdentifierObject) -> Result<String, ConversionError> {
+    let id = match identifier.id {
+        Some(ref id) => id.as_str().to_owned(),
+        None => return Err(ConversionError::MissingId { type_: identifier.type_.as_str().to_owned() }),
+    };
+
+    if identifier.lid.is_some() {
+        return Err(ConversionError::UnsupportedMember { member: "lid" });
+    }
+
+    Ok(id)
+}
+
+fn schema_relationship(relationship: spec::Relationship) -> Result<schema::Relationship, ConversionError> {
+    let data = match relationship.data {
+        Some(spec::ResourceLinkage::Null) => Some(schema::RelationshipData::Empty),
+        Some(spec::ResourceLinkage::Single(identifier)) => {
+            Some(schema::RelationshipData::Single(schema_linkage(identifier)?))
+        }
+        Some(spec::ResourceLinkage::Array(identifiers)) => {
+            let linkages = identifiers.into_iter().map(schema_linkage).collect::<Result<_, _>>()?;
+            Some(schema::RelationshipData::Array(linkages))
+        }
+        None => None,
+    };
+
+    Ok(schema::Relationship {
+        links: relationship.links.map(schema_links).transpose()?,
+        data,
+        meta: relationship.meta.map(schema_meta),
+    })
+}
+
+fn schema_linkage(identifier: spec::ResourceIdentifierObject) -> Result<schema::Linkage, ConversionError> {
+    let id = schema_identifier_id(&identifier)?;
+
+    if identifier.meta.is_some() {
+        return Err(ConversionError::LinkageMeta {
+            type_: identifier.type_.as_str().to_owned(),
+            id,
+        });
+    }
+
+    Ok(schema::Linkage {
+        type_: identifier.type_.as_str().to_owned(),
+        id,
+    })
+}
+
+fn schema_links_and_pagination(links: spec::TopLevelLinks) -> Result<schema::LinksAndPagination, ConversionError> {
+    let spec::TopLevelLinks { self_, related, describedby, links: mut others } = links;
+    let mut pagination = schema::Pagination::default();
+
+    // `#/definitions/links` allows any other link, so `describedby` is kept among them.
+    if let Some(link) = describedby {
+        others.0.insert(spec_name("describedby".to_owned())?, link);
+    }
+
+    for &name in &["first", "last", "prev", "next"] {
+        let href = match others.0.remove(name) {
+            Some(link) => Some(schema_url(name, link)?),
+            None => None,
+        };
+
+        match name {
+            "first" => pagination.first = href,
+            "last" => pagination.last = href,
+            "prev" => pagination.prev = href,
+            _ => pagination.next = href,
+        }
+    }
+
+    let mut links = schema_links(others)?;
+    links.self_ = match self_ {
+        Some(link) => Some(schema_url("self", link)?),
+        None => None,
+    };
+    links.related = related.map(schema_link).transpose()?;
+
+    Ok(schema::LinksAndPagination {
+        pagination,
+        links,
+    })
+}
+
+fn schema_links(links: spec::Links) -> Result<schema::Links, ConversionError> {
+    let mut schema_links = schema::Links::default();
+
+    for (name, link) in links.0 {
+        match name.as_str() {
+            "self" => schema_links.self_ = Some(schema_url("self", link)?),
+            "related" => schema_links.related = Some(schema_link(link)?),
+            _ => {
+                let value = serde_json::to_value(&link).expect("links always serialize");
+                schema_links.links.0.insert(name.as_str().to_owned(), value);
+            }
+        }
+    }
+
+    Ok(schema_links)
+}
+
+fn schema_link(link: spec::Link) -> Result<schema::Link, ConversionError> {
+    match link {
+        spec::Link::Url(href) => Ok(schema::Link::Url(href)),
+        spec::Link::Object { href, rel, describedby, title, type_, hreflang, meta } => {
+            let members = [
+                ("rel", rel.is_some()),
+                ("describedby", describedby.is_some()),
+                ("title", title.is_some()),
+                ("type", type_.is_some()),
+                ("hreflang", !hreflang.is_empty()),
+            ];
+
+            if let Some(&(member, _)) = members.iter().find(|&&(_, present)| present) {
+                return Err(ConversionError::UnsupportedMember { member });
+            }
+
+            Ok(schema::Link::Object {
+                href,
+                meta: meta.map(schema_meta),
+            })
+        }
+    }
+}
+
+/// Unwraps a link that the schema only allows as a string URL.
+fn schema_url(name: &str, link: spec::Link) -> Result<schema::Uri, ConversionError> {
+    match link {
+        spec::Link::Url(href) => Ok(href),
+        spec::Link::Object { .. } => Err(ConversionError::LinkObjectNotAllowed { name: name.to_owned() }),
+    }
+}
+
+fn schema_meta(meta: spec::Meta) -> schema::Meta {
+    schema::Meta(schema::Object(meta.0.into_iter().collect()))
+}
+
+fn schema_jsonapi(jsonapi: spec::JsonAPIObject) -> Result<schema::JsonAPI, ConversionError> {
+    if !jsonapi.ext.is_empty() {
+        return Err(ConversionError::UnsupportedMember { member: "ext" });
+    }
+
+    if !jsonapi.profile.is_empty() {
+        return Err(ConversionError::UnsupportedMember { member: "profile" });
+    }
+
+    Ok(schema::JsonAPI {
+        version: jsonapi.version,
+        meta: jsonapi.meta.map(schema_meta),
+    })
+}
+
+fn schema_error(error: spec::Error) -> Result<schema::Error, ConversionError> {
+    Ok(schema::Error {
+        id: error.id,
+        links: error.links.map(schema_links).transpose()?,
+        status: error.status,
+        code: error.code,
+        title: error.title,
+        detail: error.detail,
+        source: error.source.map(|source| {
+            schema::Source {
+                pointer: source.pointer,
+                parameter: source.parameter,
+                header: source.header,
+            }
+        }),
+        meta: error.meta.map(schema_meta),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// Converts the schema document read from `value` to the spec document read from it, and back.
+    fn schema_round_trip(value: Value) {
+        let schema: schema::Document = serde_json::from_value(value.clone()).unwrap();
+        let spec = spec::Document::try_from(schema.clone()).unwrap();
+
+        assert_eq!(spec, serde_json::from_value::<spec::Document>(value).unwrap());
+        assert_eq!(schema::Document::try_from(spec).unwrap(), schema);
+    }
+
+    /// Converts the spec document read from `value` to the schema document read from it, and back.
+    fn spec_round_trip(value: Value) {
+        let spec: spec::Document = serde_json::from_value(value.clone()).unwrap();
+        let schema = schema::Document::try_from(spec.clone()).unwrap();
+
+        assert_eq!(schema, serde_json::from_value::<schema::Document>(value).unwrap());
+        assert_eq!(spec::Document::try_from(schema).unwrap(), spec);
+    }
+
+    fn round_trip(value: Value) {
+        schema_round_trip(value.clone());
+        spec_round_trip(value);
+    }
+
+    fn spec_error(value: Value) -> ConversionError {
+        let spec: spec::Document = serde_json::from_value(value).unwrap();
+        schema::Document::try_from(spec).unwrap_err()
+    }
+
+    fn schema_error(value: Value) -> ConversionError {
+        let schema: schema::Document = serde_json::from_value(value).unwrap();
+        spec::Document::try_from(schema).unwrap_err()
+    }
+
+    #[test]
+    fn test_round_trip_compound_document() {
+        round_trip(json!({
+            "jsonapi": {"version": "1.0", "meta": {"server": "example"}},
+            "links": {
+                "self": "http://example.com/articles",
+                "describedby": "http://example.com/schemas/articles",
+                "next": "http://example.com/articles?page[offset]=2",
+                "last": "http://example.com/articles?page[offset]=10"
+            },
+            "data": [{
+                "type": "articles",
+                "id": "1",
+                "attributes": {"title": "JSON API paints my bikeshed!", "tags": ["a", "b"]},
+                "relationships": {
+                    "author": {
+                        "links": {
+                            "self": "http://example.com/articles/1/relationships/author",
+                            "related": {"href": "http://example.com/articles/1/author", "meta": {"count": 1}}
+                        },
+                        "data": {"type": "people", "id": "9"}
+                    },
+                    "comments": {"data": [{"type": "comments", "id": "5"}, {"type": "comments", "id": "12"}]},
+                    "editor": {"data": null}
+                },
+                "links": {"self": "http://example.com/articles/1"},
+                "meta": {"views": 3}
+            }],
+            "included": [{
+                "type": "people",
+                "id": "9",
+                "attributes": {"first-name": "Dan"}
+            }],
+            "meta": {"total": 1}
+        }));
+    }
+
+    #[test]
+    fn test_round_trip_identifiers() {
+        round_trip(json!({"data": {"type": "people", "id": "9", "meta": {"role": "author"}}}));
+        round_trip(json!({"data": [{"type": "tags", "id": "1"}, {"type": "tags", "id": "2"}]}));
+        round_trip(json!({"data": [{"type": "tags", "id": "1"}, {"type": "tags", "id": "2", "attributes": {}}]}));
+        round_trip(json!({"data": []}));
+    }
+
+    #[test]
+    fn test_round_trip_errors() {
+        round_trip(json!({
+            "jsonapi": {"version": "1.1"},
+            "errors": [{
+                "id": "1",
+                "links": {"about": "http://example.com/errors/1"},
+                "status": "422",
+                "code": "blank",
+                "title": "Invalid attribute",
+                "detail": "the title MUST NOT be blank",
+                "source": {"pointer": "/data/attributes/title"},
+                "meta": {"field": "title"}
+            }, {
+                "status": "406",
+                "source": {"header": "Accept", "parameter": "include"}
+            }],
+            "meta": {"request": "abc"}
+        }));
+    }
+
+    #[test]
+    fn test_meta_only_document() {
+        let error = schema_error(json!({"meta": {"total": 0}}));
+        assert_eq!(error, ConversionError::MetaOnlyDocument);
+        assert_eq!(error.to_string(), "a meta-only document can't be represented by spec::Document");
+    }
+
+    #[test]
+    fn test_null_primary_data() {
+        let error = spec_error(json!({"data": null}));
+        assert_eq!(error, ConversionError::NullPrimaryData);
+        assert!(error.to_string().contains("MUST be a resource or an array of resources"));
+    }
+
+    #[test]
+    fn test_missing_id() {
+        let error = spec_error(json!({"data": {"type": "articles", "attributes": {"title": "Ember Hamster"}}}));
+        assert_eq!(error, ConversionError::MissingId { type_: "articles".to_owned() });
+        assert!(error.to_string().contains("requires an id"));
+
+        let error = spec_error(json!({
+            "data": {
+                "type": "articles",
+                "id": "1",
+                "relationships": {"author": {"data": {"type": "people", "lid": "a"}}}
+            }
+        }));
+        assert_eq!(error, ConversionError::MissingId { type_: "people".to_owned() });
+    }
+
+    #[test]
+    fn test_linkage_meta() {
+        let error = spec_error(json!({
+            "data": {
+                "type": "articles",
+                "id": "1",
+                "relationships": {"author": {"data": {"type": "people", "id": "9", "meta": {}}}}
+            }
+        }));
+        assert_eq!(error, ConversionError::LinkageMeta { type_: "people".to_owned(), id: "9".to_owned() });
+        assert!(error.to_string().contains("does not allow meta"));
+    }
+
+    #[test]
+    fn test_invalid_link() {
+        let error = schema_error(json!({"errors": [{"links": {"about": 1}}]}));
+        assert_eq!(error, ConversionError::InvalidLink { name: "about".to_owned() });
+        assert!(error.to_string().contains("MUST be either a string or a link object"));
+    }
+
+    #[test]
+    fn test_link_object_not_allowed() {
+        for name in &["self", "first", "next"] {
+            let error = spec_error(json!({"links": {*name: {"href": "http://example.com"}}, "data": []}));
+            assert_eq!(error, ConversionError::LinkObjectNotAllowed { name: (*name).to_owned() });
+            assert!(error.to_string().contains("to be a string"));
+        }
+    }
+
+    #[test]
+    fn test_failure_links() {
+        let error = spec_error(json!({"links": {"self": "http://example.com"}, "errors": []}));
+        assert_eq!(error, ConversionError::FailureLinks);
+        assert!(error.to_string().contains("does not allow links in a failure document"));
+    }
+
+    #[test]
+    fn test_invalid_name() {
+        let error = schema_error(json!({"data": {"type": "-articles", "id": "1"}}));
+        assert!(matches!(error, ConversionError::InvalidName { ref name, .. } if name == "-articles"));
+        assert!(error.to_string().contains("not allowed at the start or end of a member name"));
+    }
+
+    #[test]
+    fn test_validation() {
+        // A member name may contain a space, which the schema's pattern doesn't allow.
+        let error = spec_error(json!({"data": {"type": "people", "id": "9", "attributes": {"first name": "Dan"}}}));
+        let name = "first name".to_owned();
+        assert_eq!(error, ConversionError::Validation(ValidationError::InvalidName { name }));
+        assert!(error.to_string().contains("invalid member name"));
+    }
+
+    #[test]
+    fn test_unsupported_member() {
+        let errors = [
+            (json!({"data": {"type": "articles", "id": "1", "lid": "a", "attributes": {}}}), "lid"),
+            (json!({"jsonapi": {"ext": ["https://jsonapi.org/ext/atomic"]}, "data": []}), "ext"),
+            (json!({"jsonapi": {"profile": ["http://example.com/profile"]}, "data": []}), "profile"),
+            (json!({"links": {"related": {"href": "http://example.com", "rel": "related"}}, "data": []}), "rel"),
+            (json!({"links": {"related": {"href": "http://example.com", "hreflang": "en"}}, "data": []}), "hreflang"),
+        ];
+
+        for (value, member) in errors.iter().cloned() {
+            let error = spec_error(value);
+            assert_eq!(error, ConversionError::UnsupportedMember { member });
+            assert!(error.to_string().contains("added in JSON API 1.1"));
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Deserializer};
+
+/// Deserialize a member that is present in the input into `Some`, even when its value is `null`.
+///
+/// Combined with `#[serde(default)]`, this lets an `Option<T>` distinguish between a missing member
+/// (`None`) and one whose value is `null` (`Some(T)` for a `T` that accepts `null`).
+pub fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where T: Deserialize<'de>,
+          D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
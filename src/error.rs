@@ -0,0 +1,167 @@
+use std::collections::BTreeSet;
+use std::error;
+use std::fmt;
+
+use serde_json::Value;
+
+use schema;
+
+/// A member of a resource object that breaks the naming rules shared by the `spec` and `schema`
+/// models.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// A member name that does not match the member name pattern.
+    InvalidName {
+        name: String,
+    },
+
+    /// An attribute named `relationships` or `links`, or an object in an attribute with such a
+    /// member. Both names are reserved by the specification for future use.
+    ReservedMember {
+        name: String,
+    },
+
+    /// http://jsonapi.org/format/#document-resource-object-fields
+    ///
+    /// Fields for a resource object **MUST** share a common namespace with each other and with
+    /// `type` and `id`. In other words, a resource can not have an attribute or relationship named
+    /// `type` or `id`, or an attribute and a relationship with the same name.
+    NameCollision {
+        name: String,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::InvalidName { ref name } => write!(f, "invalid member name `{}`", name),
+            ValidationError::ReservedMember { ref name } => {
+                write!(f, "`{}` is reserved and MUST NOT be used as an attribute name or within an attribute", name)
+            }
+            ValidationError::NameCollision { ref name } => {
+                write!(f, "the field `{}` collides with another field, `type` or `id`", name)
+            }
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+/// http://jsonapi.org/format/#document-resource-object-attributes
+///
+/// Checks an attribute of a resource object. `relationships` and `links` are reserved and **MUST
+/// NOT** be used as attribute names, nor as members of an object within the attribute's value.
+pub fn check_attribute(name: &str, value: &Value) -> Result<(), ValidationError> {
+    if name == "relationships" || name == "links" {
+        return Err(ValidationError::ReservedMember { name: name.to_owned() });
+    }
+
+    check_attribute_value(value)
+}
+
+/// http://jsonapi.org/format/#document-resource-object-attributes
+///
+/// Any object that constitutes or is contained in an attribute **MUST NOT** contain a
+/// `relationships` or `links` member. Checks the value of an attribute and every object nested in
+/// it.
+pub fn check_attribute_value(value: &Value) -> Result<(), ValidationError> {
+    match *value {
+        Value::Object(ref object) => {
+            for (name, value) in object {
+                if name == "relationships" || name == "links" {
+                    return Err(ValidationError::ReservedMember { name: name.clone() });
+                }
+
+                check_attribute_value(value)?;
+            }
+        }
+        Value::Array(ref values) => {
+            for value in values {
+                check_attribute_value(value)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// http://jsonapi.org/format/#document-resource-object-fields
+///
+/// Fields for a resource object **MUST** share a common namespace with each other and with `type`
+/// and `id`. Checks the attribute and relationship names of a resource object.
+pub fn check_fields<'a, A, R>(attributes: A, relationships: R) -> Result<(), ValidationError>
+    where A: IntoIterator<Item = &'a str>,
+          R: IntoIterator<Item = &'a str>,
+{
+    let mut fields = BTreeSet::new();
+
+    for name in attributes.into_iter().chain(relationships) {
+        if name == "type" || name == "id" || !fields.insert(name) {
+            return Err(ValidationError::NameCollision { name: name.to_owned() });
+        }
+    }
+
+    Ok(())
+}
+
+/// A query parameter that a request can't be served with. It converts into a 400 Bad Request
+/// `schema::Error` whose `source.parameter` names the parameter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterError {
+    pub parameter: String,
+    pub detail: String,
+}
+
+impl ParameterError {
+    pub fn new<S: Into<String>>(parameter: &str, detail: S) -> ParameterError {
+        ParameterError {
+            parameter: parameter.to_owned(),
+            detail: detail.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.detail)
+    }
+}
+
+impl error::Error for ParameterError {}
+
+impl From<ParameterError> for schema::Error {
+    fn from(error: ParameterError) -> schema::Error {
+        schema::Error::bad_parameter(&error.parameter, error.detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_attribute() {
+        assert_eq!(check_attribute("title", &json!({"tags": [{"name": "rust"}]})), Ok(()));
+
+        for name in &["relationships", "links"] {
+            assert_eq!(check_attribute(name, &json!("value")),
+                       Err(ValidationError::ReservedMember { name: name.to_string() }));
+            assert_eq!(check_attribute("title", &json!([{"nested": {*name: 1}}])),
+                       Err(ValidationError::ReservedMember { name: name.to_string() }));
+        }
+    }
+
+    #[test]
+    fn test_check_fields() {
+        assert_eq!(check_fields(vec!["title", "body"], vec!["author"]), Ok(()));
+
+        for (attributes, relationships, name) in [(vec!["type"], vec![], "type"),
+                                                  (vec![], vec!["id"], "id"),
+                                                  (vec!["author"], vec!["author"], "author")] {
+            assert_eq!(check_fields(attributes, relationships),
+                       Err(ValidationError::NameCollision { name: name.to_owned() }));
+        }
+    }
+}
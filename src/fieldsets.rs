@@ -0,0 +1,322 @@
+//! http://jsonapi.org/format/#fetching-sparse-fieldsets
+//!
+//! A client **MAY** request that an endpoint return only specific fields in the response on a
+//! per-type basis by including a `fields[TYPE]` parameter.
+//!
+//! The value of the `fields` parameter **MUST** be a comma-separated (U+002C COMMA, ",") list that
+//! refers to the name(s) of the fields to be returned.
+//!
+//! If a client requests a restricted set of fields for a given resource type, an endpoint **MUST
+//! NOT** include additional fields in resource objects of that type in its response.
+//!
+//! ```text
+//! GET /articles?include=author&fields[articles]=title,body&fields[people]=name
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error;
+use std::fmt;
+
+use error::ParameterError;
+use schema;
+use spec::{Name, NameError, ResourceObject, Type};
+use uri::{encode_query_component, parse_query};
+
+/// The fields requested for each resource type. Types without a fieldset keep all their fields.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fieldsets(BTreeMap<Type, BTreeSet<Name>>);
+
+impl Fieldsets {
+    pub fn new() -> Fieldsets {
+        Fieldsets::default()
+    }
+
+    /// Reads the `fields[TYPE]` parameters of a query string, ignoring every other parameter.
+    ///
+    /// An empty value requests no fields at all. A type given more than once gets the union of
+    /// the fields.
+    pub fn parse(query: &str) -> Result<Fieldsets, FieldsetError> {
+        let mut fieldsets = Fieldsets::new();
+
+        for (name, value) in parse_query(query) {
+            if name == "fields" || name.starts_with("fields[") {
+                fieldsets.add_parameter(&name, &value)?;
+            }
+        }
+
+        Ok(fieldsets)
+    }
+
+    /// Adds the decoded parameter `name=value`, where `name` **MUST** be `fields[TYPE]`.
+    pub fn add_parameter(&mut self, name: &str, value: &str) -> Result<(), FieldsetError> {
+        let type_ = match name.strip_prefix("fields[").and_then(|rest| rest.strip_suffix(']')) {
+            Some(type_) if !type_.contains(['[', ']']) => type_,
+            _ => return Err(FieldsetError::Parameter { name: name.to_owned() }),
+        };
+
+        let type_ = Name::parse(type_).map(Type).map_err(|error| {
+            FieldsetError::Type {
+                type_: type_.to_owned(),
+                error,
+            }
+        })?;
+
+        let fields = if value.is_empty() {
+            Vec::new()
+        } else {
+            value.split(',')
+                .map(|field| {
+                    Name::parse(field).map_err(|error| {
+                        FieldsetError::Field {
+                            type_: type_.clone(),
+                            field: field.to_owned(),
+                            error,
+                        }
+                    })
+                })
+                .collect::<Result<_, _>>()?
+        };
+
+        self.insert(type_, fields);
+        Ok(())
+    }
+
+    /// Adds `fields` to the fieldset of `type_`.
+    pub fn insert<I>(&mut self, type_: Type, fields: I)
+        where I: IntoIterator<Item = Name>,
+    {
+        self.0.entry(type_).or_default().extend(fields);
+    }
+
+    /// The fieldset of `type_`, if one was requested.
+    pub fn get(&self, type_: &Type) -> Option<&BTreeSet<Name>> {
+        self.0.get(type_)
+    }
+
+    /// Whether resource objects of `type_` keep the field `field`.
+    pub fn includes(&self, type_: &Type, field: &str) -> bool {
+        self.get(type_).is_none_or(|fields| fields.contains(field))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Type, &BTreeSet<Name>)> {
+        self.0.iter()
+    }
+
+    /// Removes the attributes and relationships of `resource` that are not in the fieldset of its
+    /// type. An attributes or relationships object left empty is removed as well.
+    pub fn prune(&self, resource: &mut ResourceObject) {
+        let fields = match self.get(&resource.type_) {
+            Some(fields) => fields,
+            None => return,
+        };
+
+        if let Some(mut attributes) = resource.attributes.take() {
+            attributes.0.retain(|name, _| fields.contains(name.as_str()));
+
+            if !attributes.0.is_empty() {
+                resource.attributes = Some(attributes);
+            }
+        }
+
+        if let Some(mut relationships) = resource.relationships.take() {
+            relationships.0.retain(|name, _| fields.contains(name));
+
+            if !relationships.0.is_empty() {
+                resource.relationships = Some(relationships);
+            }
+        }
+    }
+}
+
+/// The query string form, `fields[articles]=body,title&fields[people]=name`.
+impl fmt::Display for Fieldsets {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, (type_, fields)) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str("&")?;
+            }
+
+            write!(f, "fields[{}]=", encode_query_component(type_.as_str()))?;
+
+            for (index, field) in fields.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(",")?;
+                }
+
+                f.write_str(&encode_query_component(field.as_str()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Why a `fields` parameter could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldsetError {
+    /// A parameter in the `fields` family that is not of the form `fields[TYPE]`.
+    Parameter {
+        name: String,
+    },
+
+    /// A `TYPE` that is not a valid member name.
+    Type {
+        type_: String,
+        error: NameError,
+    },
+
+    /// A field that is not a valid member name.
+    Field {
+        type_: Type,
+        field: String,
+        error: NameError,
+    },
+}
+
+impl fmt::Display for FieldsetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldsetError::Parameter { ref name } => {
+                write!(f, "sparse fieldsets MUST be requested with a `fields[TYPE]` parameter, found `{}`", name)
+            }
+            FieldsetError::Type { ref type_, ref error } => write!(f, "invalid type `{}`: {}", type_, error),
+            FieldsetError::Field { ref type_, ref field, ref error } => {
+                write!(f, "invalid field `{}` of type `{}`: {}", field, type_.as_str(), error)
+            }
+        }
+    }
+}
+
+impl error::Error for FieldsetError {}
+
+/// An error naming the failing `fields` parameter.
+impl From<FieldsetError> for ParameterError {
+    fn from(error: FieldsetError) -> ParameterError {
+        let parameter = match error {
+            FieldsetError::Parameter { ref name } => name.clone(),
+            FieldsetError::Type { ref type_, .. } => format!("fields[{}]", type_),
+            FieldsetError::Field { ref type_, .. } => format!("fields[{}]", type_.as_str()),
+        };
+
+        ParameterError::new(&parameter, error.to_string())
+    }
+}
+
+/// A 400 Bad Request whose `source.parameter` is the failing `fields` parameter.
+impl From<FieldsetError> for schema::Error {
+    fn from(error: FieldsetError) -> schema::Error {
+        ParameterError::from(error).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{self, json};
+
+    fn type_(type_: &str) -> Type {
+        Type(Name::parse(type_).unwrap())
+    }
+
+    fn fields(fieldsets: &Fieldsets, type_: &str) -> Option<Vec<String>> {
+        fieldsets.get(&self::type_(type_))
+            .map(|fields| fields.iter().map(|field| field.as_str().to_owned()).collect())
+    }
+
+    #[test]
+    fn test_parse() {
+        let query = "include=author&fields[articles]=title,body&fields%5Bpeople%5D=name&sort=-id";
+        let fieldsets = Fieldsets::parse(query).unwrap();
+        assert_eq!(fields(&fieldsets, "articles"), Some(vec!["body".to_owned(), "title".to_owned()]));
+        assert_eq!(fields(&fieldsets, "people"), Some(vec!["name".to_owned()]));
+        assert_eq!(fields(&fieldsets, "comments"), None);
+        assert!(fieldsets.includes(&type_("articles"), "title"));
+        assert!(!fieldsets.includes(&type_("articles"), "author"));
+        assert!(fieldsets.includes(&type_("comments"), "body"));
+    }
+
+    #[test]
+    fn test_parse_empty_and_repeated() {
+        let fieldsets = Fieldsets::parse("fields[people]=&fields[articles]=title&fields[articles]=body").unwrap();
+        assert_eq!(fields(&fieldsets, "people"), Some(Vec::new()));
+        assert_eq!(fields(&fieldsets, "articles"), Some(vec!["body".to_owned(), "title".to_owned()]));
+        assert!(Fieldsets::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Fieldsets::parse("fields=title"), Err(FieldsetError::Parameter { name: "fields".to_owned() }));
+        assert_eq!(Fieldsets::parse("fields[articles][x]=title"),
+                   Err(FieldsetError::Parameter { name: "fields[articles][x]".to_owned() }));
+        assert_eq!(Fieldsets::parse("fields[articles=title"),
+                   Err(FieldsetError::Parameter { name: "fields[articles".to_owned() }));
+        assert!(matches!(Fieldsets::parse("fields[-articles]=title"), Err(FieldsetError::Type { .. })));
+        assert!(matches!(Fieldsets::parse("fields[articles]=title,,body"), Err(FieldsetError::Field { .. })));
+        assert!(matches!(Fieldsets::parse("fields[articles]=title,"), Err(FieldsetError::Field { .. })));
+    }
+
+    #[test]
+    fn test_prune_keeps_type_and_id() {
+        let fieldsets = Fieldsets::parse("fields[articles]=title,comments&fields[people]=").unwrap();
+
+        let mut article: ResourceObject = serde_json::from_value(json!({
+            "type": "articles",
+            "id": "1",
+            "attributes": {"title": "JSON API paints my bikeshed!", "body": "The shortest article. Ever."},
+            "relationships": {"author": {"data": {"type": "people", "id": "9"}}},
+            "links": {"self": "http://example.com/articles/1"},
+            "meta": {"views": 10}
+        })).unwrap();
+        fieldsets.prune(&mut article);
+        assert_eq!(serde_json::to_value(&article).unwrap(), json!({
+            "type": "articles",
+            "id": "1",
+            "attributes": {"title": "JSON API paints my bikeshed!"},
+            "links": {"self": "http://example.com/articles/1"},
+            "meta": {"views": 10}
+        }));
+
+        let mut person: ResourceObject = serde_json::from_value(json!({
+            "type": "people",
+            "id": "9",
+            "attributes": {"name": "Dan"},
+            "relationships": {"articles": {"data": []}}
+        })).unwrap();
+        fieldsets.prune(&mut person);
+        assert_eq!(serde_json::to_value(&person).unwrap(), json!({"type": "people", "id": "9"}));
+
+        let mut comment: ResourceObject = serde_json::from_value(json!({
+            "type": "comments",
+            "id": "5",
+            "attributes": {"body": "First!"}
+        })).unwrap();
+        fieldsets.prune(&mut comment);
+        assert_eq!(serde_json::to_value(&comment).unwrap()["attributes"], json!({"body": "First!"}));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let query = "fields[articles]=body,title&fields[people]=&fields[caf%C3%A9s]=men%C3%BC";
+        let fieldsets = Fieldsets::parse(query).unwrap();
+        assert_eq!(fields(&fieldsets, "cafés"), Some(vec!["menü".to_owned()]));
+
+        let displayed = fieldsets.to_string();
+        assert_eq!(displayed, "fields[articles]=body,title&fields[caf%C3%A9s]=men%C3%BC&fields[people]=");
+        assert_eq!(Fieldsets::parse(&displayed).unwrap(), fieldsets);
+        assert_eq!(Fieldsets::new().to_string(), "");
+    }
+
+    #[test]
+    fn test_error_parameter() {
+        let error = ParameterError::from(Fieldsets::parse("fields[articles]=ti@tle").unwrap_err());
+        assert_eq!(error.parameter, "fields[articles]");
+
+        let error = schema::Error::from(Fieldsets::parse("fields=title").unwrap_err());
+        assert_eq!(error.status.as_deref(), Some("400"));
+        assert_eq!(error.source.unwrap().parameter.as_deref(), Some("fields"));
+    }
+}
@@ -0,0 +1,641 @@
+//! http://jsonapi.org/format/#fetching-filtering
+//!
+//! The `filter` query parameter is reserved for filtering data. Servers and clients **SHOULD** use
+//! this key for filtering operations.
+//!
+//! The specification is agnostic about filtering strategies, so the parameters of the `filter`
+//! family are read by a `FilterSyntax`. `BracketSyntax`, used by `Filter::parse`, understands
+//!
+//! ```text
+//! filter[title]=JSON                        title equals "JSON"
+//! filter[created][gt]=2017                  created is greater than 2017
+//! filter[author.name][in]=Dan,Yehuda        the name of the author is Dan or Yehuda
+//! filter[or][0][a]=1&filter[or][1][b]=2     a equals 1 or b equals 2
+//! filter[not][status]=draft                 status is not "draft"
+//! ```
+//!
+//! As in sort fields, a dot-separated field follows to-one relationships, so `author.name` is the
+//! `name` attribute of the resource the `author` relationship links to.
+//!
+//! Parameters at the same level are combined with `and`, so `filter[and][0]...` is only needed
+//! inside an `or`. A field named `and`, `or` or `not` can't be filtered on, and `and`, `or` and
+//! `not` groups can be nested at most `MAX_NESTING` deep.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+
+use serde_json::{self, Value};
+
+use error::ParameterError;
+use sort::compare;
+use spec::{Name, ResourceIndex, ResourceObject};
+use uri::{encode_query_component, parse_query};
+
+/// How deeply `BracketSyntax` lets `and`, `or` and `not` groups nest, which bounds the recursion of
+/// parsing, `matches` and `query_pairs` for the filters it reads.
+pub const MAX_NESTING: usize = 32;
+
+/// A parsed filter, to be evaluated with `matches` or translated for a backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Matches when every filter matches, so an empty `And` matches everything.
+    And(Vec<Filter>),
+
+    /// Matches when any filter matches.
+    Or(Vec<Filter>),
+
+    Not(Box<Filter>),
+
+    Condition(Condition),
+}
+
+/// Compares the attribute at `path` with `value`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition {
+    /// The to-one relationships to follow, if any, then the attribute to compare. `id` refers to
+    /// the resource's `id`.
+    pub path: Vec<Name>,
+    pub operator: Operator,
+
+    /// The decoded value of the parameter. It is a comma-separated list for `In`, and `true` or
+    /// `false` for `Null`.
+    pub value: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+
+    /// A string attribute containing the value, or an array attribute with an element equal to it.
+    Contains,
+
+    /// An attribute equal to one of the comma-separated values.
+    In,
+
+    /// An attribute that is missing or `null` when the value is `true`, and present otherwise.
+    Null,
+}
+
+/// A parameter of the `filter` family.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilterParam {
+    /// The decoded parameter name, such as `filter[created][gt]`.
+    pub name: String,
+
+    /// The bracketed keys of the name, such as `["created", "gt"]`.
+    pub keys: Vec<String>,
+    pub value: String,
+}
+
+/// A language for the `filter` family of parameters.
+pub trait FilterSyntax {
+    type Filter;
+
+    /// Reads the `filter` parameters of a request, which may be empty. The error names the
+    /// failing parameter.
+    fn parse(&self, params: &[FilterParam]) -> Result<Self::Filter, ParameterError>;
+}
+
+/// The bracketed syntax described in the module documentation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BracketSyntax;
+
+/// Reads the `filter` family of parameters of a query string with `syntax`.
+pub fn parse<S: FilterSyntax>(query: &str, syntax: &S) -> Result<S::Filter, ParameterError> {
+    let params = parse_query(query)
+        .into_iter()
+        .filter(|(name, _)| name == "filter" || name.starts_with("filter["))
+        .map(|(name, value)| FilterParam::new(name, value))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    syntax.parse(&params)
+}
+
+impl Filter {
+    /// Reads the `filter` family of parameters of a query string with `BracketSyntax`.
+    pub fn parse(query: &str) -> Result<Filter, ParameterError> {
+        parse(query, &BracketSyntax)
+    }
+
+    /// Whether the filter matches `resource`, reading dot-separated fields through the resource
+    /// objects in `index`.
+    pub fn matches(&self, resource: &ResourceObject, index: &ResourceIndex) -> bool {
+        match *self {
+            Filter::And(ref filters) => filters.iter().all(|filter| filter.matches(resource, index)),
+            Filter::Or(ref filters) => filters.iter().any(|filter| filter.matches(resource, index)),
+            Filter::Not(ref filter) => !filter.matches(resource, index),
+            Filter::Condition(ref condition) => condition.matches(resource, index),
+        }
+    }
+
+    /// Keeps the resource objects in `resources` that the filter matches, following dot-separated
+    /// fields to the resource objects in `resources` and `included`.
+    pub fn retain(&self, resources: &mut Vec<ResourceObject>, included: &[ResourceObject]) {
+        let matches = {
+            let index = ResourceIndex::new(resources, included);
+            resources.iter().map(|resource| self.matches(resource, &index)).collect::<Vec<_>>()
+        };
+
+        let mut matches = matches.into_iter();
+        resources.retain(|_| matches.next().unwrap_or(false));
+    }
+
+    /// The `name=value` pairs of the filter in `BracketSyntax`.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        self.push_pairs("filter", &mut pairs);
+        pairs
+    }
+
+    fn push_pairs(&self, prefix: &str, pairs: &mut Vec<(String, String)>) {
+        match *self {
+            Filter::And(ref filters) => {
+                // Conditions can share a level, but two `or` or `not` groups at one level would be
+                // read back as one.
+                let groups = filters.iter()
+                    .filter(|filter| !matches!(**filter, Filter::Condition(_)))
+                    .collect::<Vec<_>>();
+
+                for filter in filters {
+                    if let Filter::Condition(_) = *filter {
+                        filter.push_pairs(prefix, pairs);
+                    }
+                }
+
+                match groups.len() {
+                    0 => {}
+                    1 => groups[0].push_pairs(prefix, pairs),
+                    _ => {
+                        for (index, filter) in groups.iter().enumerate() {
+                            filter.push_pairs(&format!("{}[and][{}]", prefix, index), pairs);
+                        }
+                    }
+                }
+            }
+            Filter::Or(ref filters) => {
+                for (index, filter) in filters.iter().enumerate() {
+                    filter.push_pairs(&format!("{}[or][{}]", prefix, index), pairs);
+                }
+            }
+            Filter::Not(ref filter) => filter.push_pairs(&format!("{}[not]", prefix), pairs),
+            Filter::Condition(ref condition) => {
+                let path = condition.path.iter().map(Name::as_str).collect::<Vec<_>>().join(".");
+                let name = match condition.operator {
+                    Operator::Eq => format!("{}[{}]", prefix, path),
+                    operator => format!("{}[{}][{}]", prefix, path, operator.as_str()),
+                };
+
+                pairs.push((name, condition.value.clone()));
+            }
+        }
+    }
+}
+
+/// The `BracketSyntax` form of the filter, encoded for use in a query string.
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, (name, value)) in self.query_pairs().iter().enumerate() {
+            if index > 0 {
+                f.write_str("&")?;
+            }
+
+            write!(f, "{}={}", encode_query_component(name), encode_query_component(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Condition {
+    /// Values are compared with `sort::compare`, after reading the parameter value as a number,
+    /// boolean or `null` when the attribute is one. `Lt`, `Le`, `Gt` and `Ge` only match values of
+    /// the same JSON type.
+    pub fn matches(&self, resource: &ResourceObject, index: &ResourceIndex) -> bool {
+        let attribute = index.field(resource, &self.path);
+
+        match self.operator {
+            Operator::Null => attribute.is_null() == (self.value == "true"),
+            Operator::In => self.values().any(|value| compare(&attribute, &coerce(value, &attribute)).is_eq()),
+            Operator::Contains => {
+                match attribute {
+                    Value::String(ref string) => string.contains(self.value.as_str()),
+                    Value::Array(ref elements) => {
+                        elements.iter().any(|element| compare(element, &coerce(&self.value, element)).is_eq())
+                    }
+                    _ => false,
+                }
+            }
+            operator => {
+                let value = coerce(&self.value, &attribute);
+                let ordering = compare(&attribute, &value);
+                let comparable = mem::discriminant(&attribute) == mem::discriminant(&value);
+
+                match operator {
+                    Operator::Eq => ordering == Ordering::Equal,
+                    Operator::Ne => ordering != Ordering::Equal,
+                    Operator::Lt => comparable && ordering == Ordering::Less,
+                    Operator::Le => comparable && ordering != Ordering::Greater,
+                    Operator::Gt => comparable && ordering == Ordering::Greater,
+                    _ => comparable && ordering != Ordering::Less,
+                }
+            }
+        }
+    }
+
+    /// The comma-separated values of an `In` condition.
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        self.value.split(',')
+    }
+}
+
+impl Operator {
+    pub fn parse(operator: &str) -> Option<Operator> {
+        match operator {
+            "eq" => Some(Operator::Eq),
+            "ne" => Some(Operator::Ne),
+            "lt" => Some(Operator::Lt),
+            "le" => Some(Operator::Le),
+            "gt" => Some(Operator::Gt),
+            "ge" => Some(Operator::Ge),
+            "contains" => Some(Operator::Contains),
+            "in" => Some(Operator::In),
+            "null" => Some(Operator::Null),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Operator::Eq => "eq",
+            Operator::Ne => "ne",
+            Operator::Lt => "lt",
+            Operator::Le => "le",
+            Operator::Gt => "gt",
+            Operator::Ge => "ge",
+            Operator::Contains => "contains",
+            Operator::In => "in",
+            Operator::Null => "null",
+        }
+    }
+}
+
+impl FilterParam {
+    /// Splits `name` into its bracketed keys, rejecting names that are not of the form
+    /// `filter[...]...` with at least one key.
+    pub fn new(name: String, value: String) -> Result<FilterParam, ParameterError> {
+        let detail = "filter parameters MUST be of the form `filter[...]`";
+        let mut keys = Vec::new();
+        let mut rest = match name.strip_prefix("filter") {
+            Some(rest) if !rest.is_empty() => rest,
+            _ => return Err(ParameterError::new(&name, detail)),
+        };
+
+        while !rest.is_empty() {
+            match rest.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+                Some((key, remaining)) if !key.contains('[') => {
+                    keys.push(key.to_owned());
+                    rest = remaining;
+                }
+                _ => return Err(ParameterError::new(&name, detail)),
+            }
+        }
+
+        Ok(FilterParam { name, keys, value })
+    }
+
+    fn error<S: Into<String>>(&self, detail: S) -> ParameterError {
+        ParameterError::new(&self.name, detail)
+    }
+}
+
+impl FilterSyntax for BracketSyntax {
+    type Filter = Filter;
+
+    fn parse(&self, params: &[FilterParam]) -> Result<Filter, ParameterError> {
+        parse_level(&params.iter().collect::<Vec<_>>(), 0, 0)
+    }
+}
+
+/// Reads the parameters whose first `depth` keys have already been read, combining them with
+/// `and`. `nesting` counts the groups those keys opened.
+fn parse_level(params: &[&FilterParam], depth: usize, nesting: usize) -> Result<Filter, ParameterError> {
+    if nesting > MAX_NESTING {
+        let detail = format!("filter groups MUST NOT be nested more than {} deep", MAX_NESTING);
+        return Err(params[0].error(detail));
+    }
+
+    let mut filters = Vec::new();
+    let mut groups = BTreeMap::<&str, BTreeMap<u64, Vec<&FilterParam>>>::new();
+    let mut negated = Vec::new();
+
+    for &param in params {
+        let keys = &param.keys[depth..];
+
+        match keys.first().map(String::as_str) {
+            None => return Err(param.error("expected a field, `and`, `or` or `not`")),
+            Some(keyword @ "and") | Some(keyword @ "or") => {
+                let index = keys.get(1)
+                    .and_then(|index| index.parse().ok())
+                    .ok_or_else(|| param.error(format!("`{}` MUST be followed by an index such as `[0]`", keyword)))?;
+
+                groups.entry(keyword).or_default().entry(index).or_default().push(param);
+            }
+            Some("not") => negated.push(param),
+            Some(field) => filters.push(Filter::Condition(condition(param, field, &keys[1..])?)),
+        }
+    }
+
+    for (keyword, children) in groups {
+        let children = children.values()
+            .map(|params| parse_level(params, depth + 2, nesting + 1))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if keyword == "or" {
+            filters.push(Filter::Or(children));
+            continue;
+        }
+
+        // An `and` group inside an `and` adds nothing, so its filters join this level.
+        for child in children {
+            match child {
+                Filter::And(children) => filters.extend(children),
+                child => filters.push(child),
+            }
+        }
+    }
+
+    if !negated.is_empty() {
+        filters.push(Filter::Not(Box::new(parse_level(&negated, depth + 1, nesting + 1)?)));
+    }
+
+    if filters.len() == 1 {
+        Ok(filters.remove(0))
+    } else {
+        Ok(Filter::And(filters))
+    }
+}
+
+fn condition(param: &FilterParam, field: &str, keys: &[String]) -> Result<Condition, ParameterError> {
+    let path = field.split('.')
+        .map(Name::parse)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| param.error(format!("invalid filter field `{}`: {}", field, error)))?;
+
+    let operator = match *keys {
+        [] => Operator::Eq,
+        [ref operator] => {
+            Operator::parse(operator).ok_or_else(|| param.error(format!("unknown filter operator `{}`", operator)))?
+        }
+        _ => return Err(param.error("a filter field can only be followed by an operator")),
+    };
+
+    if operator == Operator::Null && param.value != "true" && param.value != "false" {
+        return Err(param.error("the value of a `null` filter MUST be `true` or `false`"));
+    }
+
+    Ok(Condition {
+        path,
+        operator,
+        value: param.value.clone(),
+    })
+}
+
+/// Reads a parameter value as the JSON type of `like`, falling back to a string.
+fn coerce(value: &str, like: &Value) -> Value {
+    let coerced = match *like {
+        Value::Null | Value::Bool(_) | Value::Number(_) => serde_json::from_str(value).ok(),
+        _ => None,
+    };
+
+    match coerced {
+        Some(coerced) if mem::discriminant(&coerced) == mem::discriminant(like) => coerced,
+        _ => Value::String(value.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn param(name: &str) -> FilterParam {
+        FilterParam::new(name.to_owned(), "1".to_owned()).unwrap()
+    }
+
+    fn param_error(name: &str) -> ParameterError {
+        FilterParam::new(name.to_owned(), "1".to_owned()).unwrap_err()
+    }
+
+    #[test]
+    fn test_filter_param_keys() {
+        assert_eq!(param("filter[created][gt]").keys, vec!["created", "gt"]);
+        assert_eq!(param("filter[]").keys, vec![""]);
+    }
+
+    #[test]
+    fn test_filter_param_rejects_malformed_names() {
+        for name in &["filter", "sort[x]", "f", "", "é", "filtré[x]", "filter[a", "filter[a]b", "filter[a[b]]"] {
+            assert_eq!(param_error(name).parameter, *name);
+        }
+    }
+
+    fn condition(path: &str, operator: Operator, value: &str) -> Filter {
+        Filter::Condition(Condition {
+            path: path.split('.').map(|name| Name::parse(name).unwrap()).collect(),
+            operator,
+            value: value.to_owned(),
+        })
+    }
+
+    fn matches(query: &str, attributes: Value) -> bool {
+        let resource = serde_json::from_value(json!({"type": "articles", "id": "1", "attributes": attributes}));
+        Filter::parse(query).unwrap().matches(&resource.unwrap(), &ResourceIndex::new(&[], &[]))
+    }
+
+    #[test]
+    fn test_condition_operators() {
+        let article = json!({"title": "JSON API", "count": 5, "tags": ["rust", "json"], "draft": false});
+
+        for query in &["filter[title]=JSON API",
+                       "filter[title][eq]=JSON API",
+                       "filter[title][ne]=XML",
+                       "filter[count][lt]=10",
+                       "filter[count][le]=5",
+                       "filter[count][gt]=4.5",
+                       "filter[count][ge]=5",
+                       "filter[title][contains]=API",
+                       "filter[tags][contains]=json",
+                       "filter[count][in]=1,5",
+                       "filter[id][in]=2,1",
+                       "filter[draft]=false",
+                       "filter[subtitle][null]=true",
+                       "filter[title][null]=false",
+                       "filter[subtitle][ne]=JSON API"] {
+            assert!(matches(query, article.clone()), "{}", query);
+        }
+
+        for query in &["filter[title]=json api",
+                       "filter[title][ne]=JSON API",
+                       "filter[count][lt]=5",
+                       "filter[count][le]=4",
+                       "filter[count][gt]=5",
+                       "filter[count][ge]=6",
+                       "filter[title][contains]=XML",
+                       "filter[tags][contains]=xml",
+                       "filter[count][contains]=5",
+                       "filter[count][in]=1,2",
+                       "filter[subtitle][null]=false",
+                       "filter[title][null]=true",
+                       "filter[subtitle]=JSON API"] {
+            assert!(!matches(query, article.clone()), "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_coerce() {
+        assert_eq!(coerce("5", &json!(1)), json!(5));
+        assert_eq!(coerce("2.5", &json!(1)), json!(2.5));
+        assert_eq!(coerce("true", &json!(false)), json!(true));
+        assert_eq!(coerce("null", &Value::Null), Value::Null);
+        assert_eq!(coerce("5", &json!("x")), json!("5"));
+        assert_eq!(coerce("abc", &json!(1)), json!("abc"));
+        assert_eq!(coerce("1", &json!(true)), json!("1"));
+        assert_eq!(coerce("5", &Value::Null), json!("5"));
+        assert_eq!(coerce("[1]", &json!([1])), json!("[1]"));
+    }
+
+    #[test]
+    fn test_ordering_operators_only_compare_the_same_type() {
+        // The value is read as a string when it isn't a number, so `sort::compare` would order it
+        // after every number.
+        for operator in &["lt", "le", "gt", "ge"] {
+            assert!(!matches(&format!("filter[count][{}]=abc", operator), json!({"count": 5})), "{}", operator);
+            assert!(!matches(&format!("filter[count][{}]=5", operator), json!({})), "{}", operator);
+        }
+        assert!(matches("filter[count][ne]=abc", json!({"count": 5})));
+
+        // Strings compare by code point, not numerically.
+        assert!(matches("filter[version][gt]=10", json!({"version": "9"})));
+        assert!(matches("filter[version][lt]=b", json!({"version": "a"})));
+    }
+
+    #[test]
+    fn test_parse_grammar() {
+        assert_eq!(Filter::parse("filter[title]=JSON").unwrap(), condition("title", Operator::Eq, "JSON"));
+        assert_eq!(Filter::parse("filter[a]=1&filter[b][gt]=2").unwrap(),
+                   Filter::And(vec![condition("a", Operator::Eq, "1"), condition("b", Operator::Gt, "2")]));
+        assert_eq!(Filter::parse("filter[or][0][a]=1&filter[or][1][b]=2").unwrap(),
+                   Filter::Or(vec![condition("a", Operator::Eq, "1"), condition("b", Operator::Eq, "2")]));
+        assert_eq!(Filter::parse("filter[not][a][in]=1,2").unwrap(),
+                   Filter::Not(Box::new(condition("a", Operator::In, "1,2"))));
+
+        // An `and` group joins the level it is in, and the members of a group are combined with `and`.
+        assert_eq!(Filter::parse("filter[and][0][a]=1&filter[and][1][b]=2&filter[c]=3").unwrap(),
+                   Filter::And(vec![condition("c", Operator::Eq, "3"),
+                                    condition("a", Operator::Eq, "1"),
+                                    condition("b", Operator::Eq, "2")]));
+        assert_eq!(Filter::parse("filter[or][1][c]=3&filter[or][0][a]=1&filter[or][0][b]=2").unwrap(),
+                   Filter::Or(vec![Filter::And(vec![condition("a", Operator::Eq, "1"),
+                                                    condition("b", Operator::Eq, "2")]),
+                                   condition("c", Operator::Eq, "3")]));
+        assert_eq!(Filter::parse("filter[not][a]=1&filter[not][b]=2").unwrap(),
+                   Filter::Not(Box::new(Filter::And(vec![condition("a", Operator::Eq, "1"),
+                                                         condition("b", Operator::Eq, "2")]))));
+
+        assert!(matches("filter[or][0][count]=1&filter[or][1][not][title]=XML", json!({"count": 5})));
+        assert!(!matches("filter[not][or][0][count]=5&filter[not][or][1][title]=XML", json!({"count": 5})));
+        assert!(matches("filter[and][0][count]=5&filter[and][1][count][gt]=1", json!({"count": 5})));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let filter = Filter::And(vec![
+            condition("title", Operator::Contains, "JSON API"),
+            Filter::Or(vec![condition("count", Operator::Gt, "1"), condition("author.name", Operator::Eq, "Dan")]),
+            Filter::Not(Box::new(condition("tags", Operator::In, "a,b"))),
+        ]);
+        assert_eq!(filter.query_pairs(), vec![
+            ("filter[title][contains]".to_owned(), "JSON API".to_owned()),
+            ("filter[and][0][or][0][count][gt]".to_owned(), "1".to_owned()),
+            ("filter[and][0][or][1][author.name]".to_owned(), "Dan".to_owned()),
+            ("filter[and][1][not][tags][in]".to_owned(), "a,b".to_owned()),
+        ]);
+        assert_eq!(Filter::parse(&filter.to_string()).unwrap(), filter);
+
+        for query in &["filter[title]=JSON",
+                       "filter[or][0][a]=1&filter[or][1][not][b][ne]=2",
+                       "filter[a][null]=true"] {
+            let filter = Filter::parse(query).unwrap();
+            assert_eq!(Filter::parse(&filter.to_string()).unwrap(), filter, "{}", query);
+        }
+
+        assert_eq!(Filter::And(Vec::new()).to_string(), "");
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_parameter() {
+        for &(query, parameter, detail) in &[
+            ("filter[a][b][c]=1", "filter[a][b][c]", "a filter field can only be followed by an operator"),
+            ("filter[a][like]=1", "filter[a][like]", "unknown filter operator `like`"),
+            ("filter[or][x][a]=1", "filter[or][x][a]", "`or` MUST be followed by an index such as `[0]`"),
+            ("filter[and][a]=1", "filter[and][a]", "`and` MUST be followed by an index such as `[0]`"),
+            ("filter[a][null]=yes", "filter[a][null]", "the value of a `null` filter MUST be `true` or `false`"),
+            ("filter[not]=1", "filter[not]", "expected a field, `and`, `or` or `not`"),
+            ("filter[title]=1&filter[or][0][x][bad]=2", "filter[or][0][x][bad]", "unknown filter operator `bad`"),
+            ("filter=1", "filter", "filter parameters MUST be of the form `filter[...]`"),
+        ] {
+            let error = Filter::parse(query).unwrap_err();
+            assert_eq!((error.parameter.as_str(), error.detail.as_str()), (parameter, detail), "{}", query);
+        }
+
+        let error = Filter::parse("filter[a..b]=1").unwrap_err();
+        assert_eq!(error.parameter, "filter[a..b]");
+        assert!(error.detail.starts_with("invalid filter field `a..b`"));
+    }
+
+    #[test]
+    fn test_dotted_fields_follow_relationships() {
+        let mut articles: Vec<ResourceObject> = serde_json::from_value(json!([
+            {"type": "articles", "id": "1", "relationships": {"author": {"data": {"type": "people", "id": "9"}}}},
+            {"type": "articles", "id": "2", "relationships": {"author": {"data": {"type": "people", "id": "2"}}}},
+            {"type": "articles", "id": "3", "attributes": {"author": {"name": "Dan"}}}
+        ])).unwrap();
+        let included: Vec<ResourceObject> = serde_json::from_value(json!([
+            {"type": "people", "id": "9", "attributes": {"name": "Dan"}},
+            {"type": "people", "id": "2", "attributes": {"name": "Yehuda"}}
+        ])).unwrap();
+
+        Filter::parse("filter[author.name]=Dan").unwrap().retain(&mut articles, &included);
+        assert_eq!(articles.iter().map(|article| article.id.as_ref().unwrap().as_str()).collect::<Vec<_>>(),
+                   vec!["1"]);
+    }
+
+    #[test]
+    fn test_parse_max_nesting() {
+        let nested = |depth| format!("filter{}[a]=1", "[not]".repeat(depth));
+
+        let filter = Filter::parse(&nested(MAX_NESTING)).unwrap();
+        assert_eq!(Filter::parse(&filter.to_string()).unwrap(), filter);
+
+        let error = Filter::parse(&nested(MAX_NESTING + 1)).unwrap_err();
+        assert_eq!(error.parameter, format!("filter{}[a]", "[not]".repeat(MAX_NESTING + 1)));
+        assert_eq!(error.detail, "filter groups MUST NOT be nested more than 32 deep");
+
+        let error = Filter::parse(&format!("filter{}[a]=1", "[or][0]".repeat(5000))).unwrap_err();
+        assert_eq!(error.detail, "filter groups MUST NOT be nested more than 32 deep");
+        assert!(Filter::parse(&nested(5000)).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_bare_filter() {
+        assert!(Filter::parse("filter=1").is_err());
+        assert_eq!(Filter::parse("sort=title").unwrap(), Filter::And(Vec::new()));
+    }
+}
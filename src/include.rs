@@ -0,0 +1,224 @@
+//! http://jsonapi.org/format/#fetching-includes
+//!
+//! An endpoint **MAY** support an `include` request parameter to allow the client to customize
+//! which related resources should be returned.
+//!
+//! The value of the `include` parameter **MUST** be a comma-separated (U+002C COMMA, ",") list of
+//! relationship paths. A relationship path is a dot-separated (U+002E FULL-STOP, ".") list of
+//! relationship names.
+//!
+//! If a server is unable to identify a relationship path or does not support inclusion of
+//! resources from a path, it **MUST** respond with 400 Bad Request.
+//!
+//! ```text
+//! GET /articles/1?include=comments.author,author
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use error::ParameterError;
+use spec::Name;
+use uri::encode_query_component;
+
+/// The longest relationship path `QueryParams::parse` accepts. Paths are stored, merged and
+/// printed recursively, so an unbounded path could overflow the stack.
+pub const MAX_DEPTH: usize = 32;
+
+/// The relationship paths of an `include` parameter, as a tree of relationship names.
+///
+/// Inclusion of a path implies inclusion of its prefixes, so `comments.author` also includes
+/// `comments`, and paths that repeat or are a prefix of another path are merged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IncludePaths(BTreeMap<Name, IncludePaths>);
+
+impl IncludePaths {
+    pub fn new() -> IncludePaths {
+        IncludePaths::default()
+    }
+
+    /// Parses the decoded value of an `include` parameter, rejecting paths of more than
+    /// `max_depth` relationships. A `max_depth` of 0 rejects every path, for endpoints that don't
+    /// support `include`.
+    ///
+    /// An empty value includes nothing. The error names the `include` parameter, and converts into
+    /// a 400 Bad Request.
+    pub fn parse(value: &str, max_depth: usize) -> Result<IncludePaths, ParameterError> {
+        let mut paths = IncludePaths::new();
+
+        if value.is_empty() {
+            return Ok(paths);
+        }
+
+        for path in value.split(',') {
+            let names = path.split('.')
+                .map(Name::parse)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| {
+                    let detail = format!("invalid relationship path `{}`: {}", path, error);
+                    ParameterError::new("include", detail)
+                })?;
+
+            if names.len() > max_depth {
+                let detail = if max_depth == 0 {
+                    "the endpoint does not support inclusion of related resources".to_owned()
+                } else {
+                    format!("the relationship path `{}` is longer than the {} relationships supported",
+                            path,
+                            max_depth)
+                };
+
+                return Err(ParameterError::new("include", detail));
+            }
+
+            paths.insert(names);
+        }
+
+        Ok(paths)
+    }
+
+    /// Adds the relationship path made of `names`.
+    pub fn insert<I>(&mut self, names: I)
+        where I: IntoIterator<Item = Name>,
+    {
+        let mut node = self;
+
+        for name in names {
+            node = node.0.entry(name).or_default();
+        }
+    }
+
+    /// Adds the paths of `other`.
+    pub fn merge(&mut self, other: IncludePaths) {
+        for (name, paths) in other.0 {
+            self.0.entry(name).or_default().merge(paths);
+        }
+    }
+
+    /// Whether the dot-separated relationship path `path`, or a longer path it is a prefix of, is
+    /// included.
+    pub fn contains(&self, path: &str) -> bool {
+        let mut node = self;
+
+        for name in path.split('.') {
+            node = match node.0.get(name) {
+                Some(node) => node,
+                None => return false,
+            };
+        }
+
+        true
+    }
+
+    /// The paths that continue through the relationship `name`.
+    pub fn get(&self, name: &str) -> Option<&IncludePaths> {
+        self.0.get(name)
+    }
+
+    /// The relationships included at this level, each with the paths that continue through it.
+    pub fn relationships(&self) -> impl Iterator<Item = (&Name, &IncludePaths)> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The number of relationships in the longest path.
+    pub fn depth(&self) -> usize {
+        self.0.values().map(|paths| paths.depth() + 1).max().unwrap_or(0)
+    }
+
+    /// The dot-separated paths that are not a prefix of another path, in order. Each can be passed
+    /// to `spec::Document::resolve_include`.
+    pub fn paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+
+        for (name, children) in &self.0 {
+            if children.is_empty() {
+                paths.push(name.as_str().to_owned());
+            } else {
+                paths.extend(children.paths().into_iter().map(|path| format!("{}.{}", name, path)));
+            }
+        }
+
+        paths
+    }
+}
+
+/// The normalized value of the parameter, such as `author,comments.author`, encoded for use in a
+/// query string.
+impl fmt::Display for IncludePaths {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, path) in self.paths().iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+
+            f.write_str(&encode_query_component(path))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detail(value: &str, max_depth: usize) -> String {
+        let error = IncludePaths::parse(value, max_depth).unwrap_err();
+        assert_eq!(error.parameter, "include");
+        error.detail
+    }
+
+    #[test]
+    fn test_parse() {
+        let paths = IncludePaths::parse("comments.author,author,comments", 2).unwrap();
+        assert_eq!(paths.paths(), vec!["author", "comments.author"]);
+        assert_eq!(paths.depth(), 2);
+        assert!(paths.contains("comments"));
+        assert!(paths.contains("comments.author"));
+        assert!(!paths.contains("comments.article"));
+        assert!(!paths.contains("editor"));
+        assert!(paths.get("comments").unwrap().contains("author"));
+        assert!(paths.get("author").unwrap().is_empty());
+
+        let relationships = paths.relationships().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+        assert_eq!(relationships, vec!["author", "comments"]);
+
+        assert!(IncludePaths::parse("", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_max_depth() {
+        assert_eq!(IncludePaths::parse("comments.author.articles", 3).unwrap().depth(), 3);
+        assert_eq!(detail("author,comments.author.articles", 2),
+                   "the relationship path `comments.author.articles` is longer than the 2 relationships supported");
+        assert_eq!(detail("author", 0), "the endpoint does not support inclusion of related resources");
+    }
+
+    #[test]
+    fn test_parse_malformed_paths() {
+        assert!(detail("comments..author", 3).starts_with("invalid relationship path `comments..author`: "));
+        assert!(detail("author,", 3).starts_with("invalid relationship path ``: "));
+        assert!(detail(",author", 3).starts_with("invalid relationship path ``: "));
+        assert!(detail("author.", 3).starts_with("invalid relationship path `author.`: "));
+        assert!(detail("-author", 3).starts_with("invalid relationship path `-author`: "));
+    }
+
+    #[test]
+    fn test_display() {
+        let paths = IncludePaths::parse("comments.author,author,café", 2).unwrap();
+        assert_eq!(paths.to_string(), "author,caf%C3%A9,comments.author");
+        assert_eq!(IncludePaths::new().to_string(), "");
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut paths = IncludePaths::parse("comments,author", 2).unwrap();
+        paths.merge(IncludePaths::parse("comments.author,tags", 2).unwrap());
+        assert_eq!(paths.paths(), vec!["author", "comments.author", "tags"]);
+        assert_eq!(paths, IncludePaths::parse("tags,comments.author,author", 2).unwrap());
+    }
+}
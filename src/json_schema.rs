@@ -0,0 +1,767 @@
+//! http://json-schema.org/draft-04/json-schema-validation
+//!
+//! A JSON Schema draft-04 validator, used to check documents against the official JSON API schema
+//! quoted throughout the `schema` model. The schema is embedded verbatim as `SCHEMA`.
+//!
+//! The validator supports the keywords that schema uses, along with the other structural keywords
+//! of draft-04: `$ref` to the same schema, `type`, `enum`, `allOf`, `anyOf`, `oneOf`, `not`,
+//! `properties`, `patternProperties`, `additionalProperties`, `required`, `minProperties`,
+//! `maxProperties`, `items`, `additionalItems`, `minItems`, `maxItems`, `uniqueItems`,
+//! `minLength`, `maxLength`, `pattern` and `format: uri`. Other formats are not checked.
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::sync::LazyLock;
+
+use fancy_regex::Regex;
+use serde_json::{self, Map, Value};
+
+use pointer::JsonPointer;
+use uri::{percent_decode, Uri};
+
+/// The official JSON API schema, published at http://jsonapi.org/schema.
+pub const SCHEMA: &str = include_str!("schema.json");
+
+/// `SCHEMA`, parsed and compiled on first use.
+static JSONAPI: LazyLock<Schema> = LazyLock::new(|| {
+    let root = serde_json::from_str(SCHEMA).expect("embedded schema is valid JSON");
+    Schema::new(root).expect("embedded schema is valid")
+});
+
+/// A draft-04 schema, with its regular expressions compiled.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    root: Value,
+    patterns: BTreeMap<String, Regex>,
+}
+
+impl Schema {
+    /// The embedded JSON API schema, which is only compiled once.
+    pub fn jsonapi() -> &'static Schema {
+        &JSONAPI
+    }
+
+    /// Compiles `root`, failing if one of its `pattern` or `patternProperties` regular
+    /// expressions can't be compiled.
+    pub fn new(root: Value) -> Result<Schema, SchemaError> {
+        let mut patterns = BTreeMap::new();
+        compile_patterns(&root, &JsonPointer::root(), &mut patterns)?;
+
+        Ok(Schema { root, patterns })
+    }
+
+    /// Validates `instance`, returning every error found.
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<SchemaError>> {
+        let mut errors = Vec::new();
+        self.check(&self.root, &JsonPointer::root(), instance, &JsonPointer::root(), &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn is_valid(&self, instance: &Value) -> bool {
+        self.validate(instance).is_ok()
+    }
+
+    /// Checks `instance` against the subschema `schema`, found at `schema_path`.
+    fn check(&self,
+             schema: &Value,
+             schema_path: &JsonPointer,
+             instance: &Value,
+             instance_path: &JsonPointer,
+             errors: &mut Vec<SchemaError>) {
+        let schema = match *schema {
+            Value::Object(ref schema) => schema,
+            _ => return,
+        };
+
+        let mut error = |keyword: &str, kind: ErrorKind| {
+            errors.push(SchemaError {
+                instance_path: instance_path.clone(),
+                schema_path: schema_path.child(keyword),
+                kind,
+            });
+        };
+
+        // In draft-04, `$ref` replaces every other keyword of the schema it appears in.
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match self.resolve(reference) {
+                Some((target, target_path)) => self.check(target, &target_path, instance, instance_path, errors),
+                None => error("$ref", ErrorKind::UnresolvedReference { reference: reference.to_owned() }),
+            }
+
+            return;
+        }
+
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match *types {
+                Value::String(ref type_) => vec![type_],
+                Value::Array(ref types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+
+            if !types.iter().any(|type_| has_type(instance, type_)) {
+                error("type", ErrorKind::Type { expected: types.iter().map(|&type_| type_.to_owned()).collect() });
+            }
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if !values.contains(instance) {
+                error("enum", ErrorKind::Enum);
+            }
+        }
+
+        let subschemas = |keyword: &str| -> Vec<(JsonPointer, &Value)> {
+            schema.get(keyword)
+                .and_then(Value::as_array)
+                .map(|subschemas| {
+                    subschemas.iter()
+                        .enumerate()
+                        .map(|(index, subschema)| (schema_path.child(keyword).child(index), subschema))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let all_of = subschemas("allOf");
+        let any_of = subschemas("anyOf");
+        let one_of = subschemas("oneOf");
+
+        for (path, subschema) in all_of {
+            self.check(subschema, &path, instance, instance_path, errors);
+        }
+
+        if !any_of.is_empty() {
+            let causes: Vec<_> = any_of.iter()
+                .map(|&(ref path, subschema)| self.check_branch(subschema, path, instance, instance_path))
+                .collect();
+
+            if causes.iter().all(|cause| !cause.is_empty()) {
+                errors.push(SchemaError {
+                    instance_path: instance_path.clone(),
+                    schema_path: schema_path.child("anyOf"),
+                    kind: ErrorKind::AnyOf { causes },
+                });
+            }
+        }
+
+        if !one_of.is_empty() {
+            let causes: Vec<_> = one_of.iter()
+                .map(|&(ref path, subschema)| self.check_branch(subschema, path, instance, instance_path))
+                .collect();
+            let matched: Vec<usize> = causes.iter()
+                .enumerate()
+                .filter(|&(_, cause)| cause.is_empty())
+                .map(|(index, _)| index)
+                .collect();
+
+            if matched.len() != 1 {
+                let causes = if matched.is_empty() { causes } else { Vec::new() };
+
+                errors.push(SchemaError {
+                    instance_path: instance_path.clone(),
+                    schema_path: schema_path.child("oneOf"),
+                    kind: ErrorKind::OneOf { matched, causes },
+                });
+            }
+        }
+
+        if let Some(not) = schema.get("not") {
+            let path = schema_path.child("not");
+
+            if self.check_branch(not, &path, instance, instance_path).is_empty() {
+                errors.push(SchemaError {
+                    instance_path: instance_path.clone(),
+                    schema_path: path,
+                    kind: ErrorKind::Not,
+                });
+            }
+        }
+
+        match *instance {
+            Value::Object(ref object) => self.check_object(schema, schema_path, object, instance_path, errors),
+            Value::Array(ref array) => self.check_array(schema, schema_path, array, instance_path, errors),
+            Value::String(ref string) => self.check_string(schema, schema_path, string, instance_path, errors),
+            _ => {}
+        }
+    }
+
+    /// Checks one branch of an `anyOf`, `oneOf` or `not`, returning its errors.
+    fn check_branch(&self,
+                    schema: &Value,
+                    schema_path: &JsonPointer,
+                    instance: &Value,
+                    instance_path: &JsonPointer)
+                    -> Vec<SchemaError> {
+        let mut errors = Vec::new();
+        self.check(schema, schema_path, instance, instance_path, &mut errors);
+        errors
+    }
+
+    fn check_object(&self,
+                    schema: &Map<String, Value>,
+                    schema_path: &JsonPointer,
+                    object: &Map<String, Value>,
+                    instance_path: &JsonPointer,
+                    errors: &mut Vec<SchemaError>) {
+        let mut error = |keyword: &str, kind: ErrorKind| {
+            errors.push(SchemaError {
+                instance_path: instance_path.clone(),
+                schema_path: schema_path.child(keyword),
+                kind,
+            });
+        };
+
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for property in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(property) {
+                    error("required", ErrorKind::Required { property: property.to_owned() });
+                }
+            }
+        }
+
+        if let Some(min) = schema.get("minProperties").and_then(Value::as_u64) {
+            if (object.len() as u64) < min {
+                error("minProperties", ErrorKind::MinProperties { min });
+            }
+        }
+
+        if let Some(max) = schema.get("maxProperties").and_then(Value::as_u64) {
+            if (object.len() as u64) > max {
+                error("maxProperties", ErrorKind::MaxProperties { max });
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let pattern_properties = schema.get("patternProperties").and_then(Value::as_object);
+        let additional_properties = schema.get("additionalProperties");
+
+        for (name, value) in object {
+            let value_path = instance_path.child(name);
+            let mut matched = false;
+
+            if let Some(subschema) = properties.and_then(|properties| properties.get(name)) {
+                matched = true;
+                self.check(subschema, &schema_path.child("properties").child(name), value, &value_path, errors);
+            }
+
+            for (pattern, subschema) in pattern_properties.into_iter().flatten() {
+                if self.is_match(pattern, name) {
+                    matched = true;
+                    self.check(subschema,
+                               &schema_path.child("patternProperties").child(pattern),
+                               value,
+                               &value_path,
+                               errors);
+                }
+            }
+
+            if matched {
+                continue;
+            }
+
+            match additional_properties {
+                Some(&Value::Bool(false)) => {
+                    errors.push(SchemaError {
+                        instance_path: instance_path.clone(),
+                        schema_path: schema_path.child("additionalProperties"),
+                        kind: ErrorKind::AdditionalProperty { property: name.clone() },
+                    });
+                }
+                Some(subschema @ &Value::Object(_)) => {
+                    self.check(subschema, &schema_path.child("additionalProperties"), value, &value_path, errors);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_array(&self,
+                   schema: &Map<String, Value>,
+                   schema_path: &JsonPointer,
+                   array: &[Value],
+                   instance_path: &JsonPointer,
+                   errors: &mut Vec<SchemaError>) {
+        match schema.get("items") {
+            Some(items @ &Value::Object(_)) => {
+                for (index, item) in array.iter().enumerate() {
+                    self.check(items, &schema_path.child("items"), item, &instance_path.child(index), errors);
+                }
+            }
+            Some(Value::Array(items)) => {
+                for (index, item) in array.iter().enumerate() {
+                    match items.get(index) {
+                        Some(subschema) => {
+                            self.check(subschema,
+                                       &schema_path.child("items").child(index),
+                                       item,
+                                       &instance_path.child(index),
+                                       errors);
+                        }
+                        None => {
+                            match schema.get("additionalItems") {
+                                Some(&Value::Bool(false)) => {
+                                    errors.push(SchemaError {
+                                        instance_path: instance_path.clone(),
+                                        schema_path: schema_path.child("additionalItems"),
+                                        kind: ErrorKind::AdditionalItems { max: items.len() },
+                                    });
+                                    break;
+                                }
+                                Some(subschema @ &Value::Object(_)) => {
+                                    self.check(subschema,
+                                               &schema_path.child("additionalItems"),
+                                               item,
+                                               &instance_path.child(index),
+                                               errors);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let mut error = |keyword: &str, kind: ErrorKind| {
+            errors.push(SchemaError {
+                instance_path: instance_path.clone(),
+                schema_path: schema_path.child(keyword),
+                kind,
+            });
+        };
+
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (array.len() as u64) < min {
+                error("minItems", ErrorKind::MinItems { min });
+            }
+        }
+
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if (array.len() as u64) > max {
+                error("maxItems", ErrorKind::MaxItems { max });
+            }
+        }
+
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicate = (0..array.len())
+                .flat_map(|second| (0..second).map(move |first| (first, second)))
+                .find(|&(first, second)| array[first] == array[second]);
+
+            if let Some((first, second)) = duplicate {
+                error("uniqueItems", ErrorKind::UniqueItems { first, second });
+            }
+        }
+    }
+
+    fn check_string(&self,
+                    schema: &Map<String, Value>,
+                    schema_path: &JsonPointer,
+                    string: &str,
+                    instance_path: &JsonPointer,
+                    errors: &mut Vec<SchemaError>) {
+        let mut error = |keyword: &str, kind: ErrorKind| {
+            errors.push(SchemaError {
+                instance_path: instance_path.clone(),
+                schema_path: schema_path.child(keyword),
+                kind,
+            });
+        };
+
+        let length = string.chars().count() as u64;
+
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                error("minLength", ErrorKind::MinLength { min });
+            }
+        }
+
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                error("maxLength", ErrorKind::MaxLength { max });
+            }
+        }
+
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            if !self.is_match(pattern, string) {
+                error("pattern", ErrorKind::Pattern { pattern: pattern.to_owned() });
+            }
+        }
+
+        // A "uri" is an absolute URI, as opposed to a relative reference.
+        let format = schema.get("format").and_then(Value::as_str);
+
+        if format == Some("uri") && !Uri::parse(string).is_ok_and(|uri| uri.is_absolute()) {
+            error("format", ErrorKind::Format { format: "uri".to_owned() });
+        }
+    }
+
+    fn is_match(&self, pattern: &str, string: &str) -> bool {
+        self.patterns
+            .get(pattern)
+            .is_some_and(|regex| regex.is_match(string).unwrap_or(false))
+    }
+
+    /// Resolves a `$ref` to a JSON Pointer fragment of this schema, such as `#/definitions/link`.
+    fn resolve(&self, reference: &str) -> Option<(&Value, JsonPointer)> {
+        if !reference.starts_with('#') {
+            return None;
+        }
+
+        let pointer = JsonPointer::parse(&percent_decode(&reference[1..])).ok()?;
+        let target = pointer.resolve(&self.root)?;
+
+        Some((target, pointer))
+    }
+}
+
+/// Validates `instance` against the embedded JSON API schema.
+pub fn validate(instance: &Value) -> Result<(), Vec<SchemaError>> {
+    Schema::jsonapi().validate(instance)
+}
+
+/// draft-04 primitive types. An "integer" is any number without a fractional part.
+fn has_type(instance: &Value, type_: &str) -> bool {
+    match (type_, instance) {
+        ("null", &Value::Null) |
+        ("boolean", &Value::Bool(_)) |
+        ("object", &Value::Object(_)) |
+        ("array", &Value::Array(_)) |
+        ("number", &Value::Number(_)) |
+        ("string", &Value::String(_)) => true,
+        ("integer", Value::Number(number)) => {
+            number.is_i64() || number.is_u64() || number.as_f64().is_some_and(|number| number.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+/// Compiles every `pattern` and `patternProperties` regular expression found in `schema`.
+fn compile_patterns(schema: &Value,
+                    path: &JsonPointer,
+                    patterns: &mut BTreeMap<String, Regex>)
+                    -> Result<(), SchemaError> {
+    let mut compile = |pattern: &str, path: JsonPointer| -> Result<(), SchemaError> {
+        if !patterns.contains_key(pattern) {
+            let regex = Regex::new(&translate_pattern(pattern)).map_err(|error| {
+                SchemaError {
+                    instance_path: JsonPointer::root(),
+                    schema_path: path,
+                    kind: ErrorKind::InvalidPattern { pattern: pattern.to_owned(), message: error.to_string() },
+                }
+            })?;
+
+            patterns.insert(pattern.to_owned(), regex);
+        }
+
+        Ok(())
+    };
+
+    match *schema {
+        Value::Object(ref object) => {
+            if let Some(pattern) = object.get("pattern").and_then(Value::as_str) {
+                compile(pattern, path.child("pattern"))?;
+            }
+
+            if let Some(properties) = object.get("patternProperties").and_then(Value::as_object) {
+                for pattern in properties.keys() {
+                    compile(pattern, path.child("patternProperties").child(pattern))?;
+                }
+            }
+
+            for (name, value) in object {
+                compile_patterns(value, &path.child(name), patterns)?;
+            }
+        }
+        Value::Array(ref array) => {
+            for (index, value) in array.iter().enumerate() {
+                compile_patterns(value, &path.child(index), patterns)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Regular expressions in a schema follow ECMA 262, where `\d` and `\w` only match ASCII
+/// characters, while the `regex` crate matches any Unicode digit or word character. Rewrites them
+/// as ASCII character classes.
+fn translate_pattern(pattern: &str) -> String {
+    let mut translated = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    let mut in_class = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let escaped = match chars.next() {
+                    Some(escaped) => escaped,
+                    None => {
+                        translated.push(c);
+                        break;
+                    }
+                };
+
+                match (escaped, in_class) {
+                    ('d', false) => translated.push_str("[0-9]"),
+                    ('d', true) => translated.push_str("0-9"),
+                    ('D', false) => translated.push_str("[^0-9]"),
+                    ('w', false) => translated.push_str("[A-Za-z0-9_]"),
+                    ('w', true) => translated.push_str("A-Za-z0-9_"),
+                    ('W', false) => translated.push_str("[^A-Za-z0-9_]"),
+                    _ => {
+                        translated.push(c);
+                        translated.push(escaped);
+                    }
+                }
+            }
+            '[' if !in_class => {
+                in_class = true;
+                translated.push(c);
+            }
+            ']' if in_class => {
+                in_class = false;
+                translated.push(c);
+            }
+            c => translated.push(c),
+        }
+    }
+
+    translated
+}
+
+/// An instance that does not match a schema, or a schema that can't be compiled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaError {
+    /// The value that failed validation.
+    pub instance_path: JsonPointer,
+    /// The keyword that failed, such as `/definitions/resource/required`.
+    pub schema_path: JsonPointer,
+    pub kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+    Type {
+        expected: Vec<String>,
+    },
+    Enum,
+    Required {
+        property: String,
+    },
+    AdditionalProperty {
+        property: String,
+    },
+    MinProperties {
+        min: u64,
+    },
+    MaxProperties {
+        max: u64,
+    },
+    AdditionalItems {
+        max: usize,
+    },
+    MinItems {
+        min: u64,
+    },
+    MaxItems {
+        max: u64,
+    },
+
+    /// The items at indices `first` and `second` are equal.
+    UniqueItems {
+        first: usize,
+        second: usize,
+    },
+    MinLength {
+        min: u64,
+    },
+    MaxLength {
+        max: u64,
+    },
+    Pattern {
+        pattern: String,
+    },
+    Format {
+        format: String,
+    },
+
+    /// The instance matched none of the `anyOf` subschemas; `causes` holds the errors of each.
+    AnyOf {
+        causes: Vec<Vec<SchemaError>>,
+    },
+
+    /// The instance must match exactly one of the `oneOf` subschemas. When it matched several,
+    /// `matched` holds their indices; when it matched none, `causes` holds the errors of each.
+    OneOf {
+        matched: Vec<usize>,
+        causes: Vec<Vec<SchemaError>>,
+    },
+    Not,
+    UnresolvedReference {
+        reference: String,
+    },
+    InvalidPattern {
+        pattern: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}`: ", self.instance_path)?;
+
+        match self.kind {
+            ErrorKind::Type { ref expected } => write!(f, "expected type {}", expected.join(" or "))?,
+            ErrorKind::Enum => write!(f, "value is not one of the enumerated values")?,
+            ErrorKind::Required { ref property } => write!(f, "missing required property `{}`", property)?,
+            ErrorKind::AdditionalProperty { ref property } => write!(f, "property `{}` is not allowed", property)?,
+            ErrorKind::MinProperties { min } => write!(f, "expected at least {} properties", min)?,
+            ErrorKind::MaxProperties { max } => write!(f, "expected at most {} properties", max)?,
+            ErrorKind::AdditionalItems { max } => write!(f, "expected at most {} items", max)?,
+            ErrorKind::MinItems { min } => write!(f, "expected at least {} items", min)?,
+            ErrorKind::MaxItems { max } => write!(f, "expected at most {} items", max)?,
+            ErrorKind::UniqueItems { first, second } => write!(f, "items {} and {} are equal", first, second)?,
+            ErrorKind::MinLength { min } => write!(f, "expected at least {} characters", min)?,
+            ErrorKind::MaxLength { max } => write!(f, "expected at most {} characters", max)?,
+            ErrorKind::Pattern { ref pattern } => write!(f, "does not match the pattern `{}`", pattern)?,
+            ErrorKind::Format { ref format } => write!(f, "is not a valid `{}`", format)?,
+            ErrorKind::AnyOf { .. } => write!(f, "does not match any of the `anyOf` schemas")?,
+            ErrorKind::OneOf { ref matched, .. } => {
+                if matched.is_empty() {
+                    write!(f, "does not match any of the `oneOf` schemas")?
+                } else {
+                    write!(f, "matches more than one of the `oneOf` schemas")?
+                }
+            }
+            ErrorKind::Not => write!(f, "matches the `not` schema")?,
+            ErrorKind::UnresolvedReference { ref reference } => write!(f, "can't resolve `$ref` `{}`", reference)?,
+            ErrorKind::InvalidPattern { ref pattern, ref message } => {
+                write!(f, "invalid regular expression `{}`: {}", pattern, message)?
+            }
+        }
+
+        write!(f, " (schema `{}`)", self.schema_path)
+    }
+}
+
+impl error::Error for SchemaError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The JSON API schema, validating against one of its definitions.
+    fn definition(name: &str) -> Schema {
+        let mut root: Value = serde_json::from_str(SCHEMA).unwrap();
+        let object = root.as_object_mut().unwrap();
+        object.remove("oneOf");
+        object.insert("$ref".to_owned(), json!(format!("#/definitions/{}", name)));
+
+        Schema::new(root).unwrap()
+    }
+
+    fn kinds(schema: &Schema, instance: Value) -> Vec<(String, ErrorKind)> {
+        schema.validate(&instance)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| (error.instance_path.to_string(), error.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_jsonapi_schema_is_compiled_once() {
+        assert!(std::ptr::eq(Schema::jsonapi(), Schema::jsonapi()));
+        assert!(validate(&json!({"data": []})).is_ok());
+        assert!(validate(&json!({"meta": {"total": 0}})).is_ok());
+        assert!(validate(&json!({"errors": [{"status": "404"}]})).is_ok());
+    }
+
+    #[test]
+    fn test_one_of() {
+        let errors = validate(&json!({"data": "x"})).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].schema_path.to_string(), "/oneOf");
+        match errors[0].kind {
+            ErrorKind::OneOf { ref matched, ref causes } => {
+                assert!(matched.is_empty());
+                assert_eq!(causes.len(), 3);
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+
+        let schema = Schema::new(json!({"oneOf": [{"type": "string"}, {"minLength": 1}]})).unwrap();
+        assert!(schema.is_valid(&json!("")));
+        assert!(matches!(kinds(&schema, json!("a"))[..],
+                         [(_, ErrorKind::OneOf { ref matched, .. })] if *matched == [0, 1]));
+    }
+
+    #[test]
+    fn test_any_of() {
+        let schema = definition("relationshipToOne");
+        assert!(schema.is_valid(&json!(null)));
+        assert!(schema.is_valid(&json!({})));
+        assert!(schema.is_valid(&json!({"type": "people", "id": "9"})));
+
+        match kinds(&schema, json!({"type": "people"}))[..] {
+            [(ref path, ErrorKind::AnyOf { ref causes })] => {
+                assert_eq!(path, "");
+                assert_eq!(causes.len(), 2);
+            }
+            ref errors => panic!("unexpected {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn test_unique_items() {
+        let schema = definition("relationshipToMany");
+        assert!(schema.is_valid(&json!([{"type": "tags", "id": "1"}, {"type": "tags", "id": "2"}])));
+        assert_eq!(kinds(&schema, json!([{"type": "tags", "id": "1"}, {"type": "tags", "id": "1"}])),
+                   vec![("".to_owned(), ErrorKind::UniqueItems { first: 0, second: 1 })]);
+    }
+
+    #[test]
+    fn test_attribute_lookahead() {
+        let schema = definition("attributes");
+        assert!(schema.is_valid(&json!({"title": 1, "linksCount": 2, "relationships-seen": 3, "my_links": 4})));
+
+        for name in &["relationships", "links"] {
+            let errors = kinds(&schema, json!({"title": 1, *name: {}}));
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(errors[0].1, ErrorKind::AdditionalProperty { ref property } if property == name));
+        }
+
+        assert_eq!(kinds(&schema, json!({"-title": 1})).len(), 1);
+    }
+
+    #[test]
+    fn test_additional_properties_false() {
+        let schema = definition("resource");
+        assert!(schema.is_valid(&json!({"type": "articles", "id": "1", "meta": {"extra": true}})));
+
+        let errors = kinds(&schema, json!({"type": "articles", "id": "1", "extra": true}));
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].1, ErrorKind::AdditionalProperty { ref property } if property == "extra"));
+    }
+
+    #[test]
+    fn test_format_uri() {
+        let schema = definition("links");
+        assert!(schema.is_valid(&json!({"self": "http://example.com/articles/1"})));
+        assert!(schema.is_valid(&json!({"related": {"href": "urn:isbn:0451450523"}})));
+
+        for uri in &["/articles/1", "http://example.com/a b", "1http://example.com"] {
+            assert_eq!(kinds(&schema, json!({"self": uri})),
+                       vec![("/self".to_owned(), ErrorKind::Format { format: "uri".to_owned() })],
+                       "{}",
+                       uri);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Data types for reading and writing [JSON API](http://jsonapi.org) documents.
+//!
+//! The crate carries two models of a document:
+//!
+//! * `spec`, which follows the prose of the specification at http://jsonapi.org/format/.
+//! * `schema`, which follows the official JSON Schema published at http://jsonapi.org/schema.
+//!   `json_schema` validates arbitrary JSON against that schema directly.
+//!
+//! Rust types can be mapped to resource objects with `#[derive(JsonApiResource)]`, described in
+//! the `resource` module.
+
+extern crate fancy_regex;
+extern crate jsonapi_derive;
+extern crate jsonapi_name;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+mod de;
+
+pub mod atomic;
+pub mod convert;
+pub mod error;
+pub mod filter;
+pub mod fieldsets;
+pub mod include;
+pub mod json_schema;
+pub mod media_type;
+pub mod normalize;
+pub mod page;
+pub mod pointer;
+pub mod query;
+pub mod resource;
+pub mod schema;
+pub mod sort;
+pub mod spec;
+pub mod uri;
+pub mod validate;
+
+pub use jsonapi_derive::JsonApiResource;
+pub use resource::JsonApiResource;
@@ -0,0 +1,482 @@
+//! http://jsonapi.org/format/#content-negotiation
+//!
+//! The JSON:API media type is `application/vnd.api+json`. It **MAY** be given the `ext` and
+//! `profile` media type parameters, each a space-separated list of URIs that identify the
+//! extensions and profiles applied to a document.
+//!
+//! Servers **MUST** respond with a 415 Unsupported Media Type status code if a request specifies
+//! the header `Content-Type: application/vnd.api+json` with any media type parameters other than
+//! `ext` or `profile`, or with an `ext` parameter that contains an unsupported extension URI.
+//!
+//! Servers **MUST** respond with a 406 Not Acceptable status code if a request's `Accept` header
+//! contains an instance of the JSON:API media type, and all instances of that media type are
+//! modified with a media type parameter other than `ext` or `profile`, or with an `ext`
+//! parameter that contains an unsupported extension URI.
+
+use std::error;
+use std::fmt;
+
+use schema;
+
+/// `application/vnd.api+json`
+pub const JSON_API: &str = "application/vnd.api+json";
+
+/// A media type such as `application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"`.
+///
+/// The type, subtype and parameter names are case-insensitive, and are kept in lowercase.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaType {
+    pub type_: String,
+    pub subtype: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl MediaType {
+    /// `application/vnd.api+json` without parameters.
+    pub fn jsonapi() -> MediaType {
+        MediaType {
+            type_: "application".to_owned(),
+            subtype: "vnd.api+json".to_owned(),
+            params: Vec::new(),
+        }
+    }
+
+    /// https://tools.ietf.org/html/rfc7231#section-3.1.1.1
+    ///
+    /// `media-type = type "/" subtype *( OWS ";" OWS parameter )`, where a parameter value is a
+    /// token or a quoted string.
+    pub fn parse(input: &str) -> Result<MediaType, MediaTypeError> {
+        let error = |reason| MediaTypeError { media_type: input.to_owned(), reason };
+        let mut parts = split_unquoted(input, ';').into_iter();
+
+        let essence = parts.next().unwrap_or("").trim();
+        let (type_, subtype) = essence.split_once('/')
+            .ok_or_else(|| error("a media type MUST have a type and a subtype"))?;
+
+        if !is_token(type_) || !is_token(subtype) {
+            return Err(error("the type and subtype MUST be tokens"));
+        }
+
+        let mut params = Vec::new();
+
+        for param in parts {
+            let param = param.trim();
+
+            if param.is_empty() {
+                continue;
+            }
+
+            let (name, value) = param.split_once('=')
+                .ok_or_else(|| error("a media type parameter MUST have a value"))?;
+
+            if !is_token(name) {
+                return Err(error("a media type parameter name MUST be a token"));
+            }
+
+            let value = match value.strip_prefix('"') {
+                Some(quoted) => {
+                    unquote(quoted).ok_or_else(|| error("a quoted parameter value MUST end with a quote"))?
+                }
+                None if is_token(value) => value.to_owned(),
+                None => return Err(error("a media type parameter value MUST be a token or a quoted string")),
+            };
+
+            params.push((name.to_ascii_lowercase(), value));
+        }
+
+        Ok(MediaType {
+            type_: type_.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params,
+        })
+    }
+
+    pub fn is_jsonapi(&self) -> bool {
+        self.type_ == "application" && self.subtype == "vnd.api+json"
+    }
+
+    /// The value of the first parameter named `name`.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|&(param, _)| param.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    /// Adds a parameter.
+    pub fn with_param<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> MediaType {
+        self.params.push((name.into().to_ascii_lowercase(), value.into()));
+        self
+    }
+
+    /// The extension URIs of the `ext` parameter.
+    pub fn ext(&self) -> Vec<&str> {
+        self.param("ext").map_or_else(Vec::new, |ext| ext.split_whitespace().collect())
+    }
+
+    /// The profile URIs of the `profile` parameter.
+    pub fn profile(&self) -> Vec<&str> {
+        self.param("profile").map_or_else(Vec::new, |profile| profile.split_whitespace().collect())
+    }
+}
+
+/// Parameter values that are not tokens are quoted.
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+
+        for (name, value) in &self.params {
+            if is_token(value) {
+                write!(f, "; {}={}", name, value)?;
+            } else {
+                write!(f, "; {}=\"{}\"", name, value.replace('\\', "\\\\").replace('"', "\\\""))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An element of an `Accept` header: a media range and its weight.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AcceptItem {
+    /// The media range, with the parameters before the `q` weight.
+    pub media_type: MediaType,
+    pub quality: f32,
+}
+
+/// https://tools.ietf.org/html/rfc7231#section-5.3.2
+///
+/// Reads the comma-separated media ranges of an `Accept` header, skipping the ones that can't be
+/// parsed. Parameters after the `q` weight are accept extensions, and are dropped.
+pub fn parse_accept(accept: &str) -> Vec<AcceptItem> {
+    split_unquoted(accept, ',')
+        .into_iter()
+        .filter(|item| !item.trim().is_empty())
+        .filter_map(|item| MediaType::parse(item).ok())
+        .map(|mut media_type| {
+            let mut quality = 1.0;
+
+            if let Some(q) = media_type.params.iter().position(|(name, _)| name == "q") {
+                quality = media_type.params[q].1.parse().unwrap_or(0.0);
+                media_type.params.truncate(q);
+            }
+
+            AcceptItem { media_type, quality }
+        })
+        .collect()
+}
+
+/// Applies the content negotiation rules for a server that supports the given extensions and
+/// profiles.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Negotiator {
+    extensions: Vec<String>,
+    profiles: Vec<String>,
+}
+
+/// The outcome of negotiation: the status to respond with, and the `Content-Type` of the
+/// response. An error response is still a JSON:API document, so it has a `Content-Type` too.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Negotiation {
+    pub status: u16,
+    pub content_type: MediaType,
+}
+
+impl Negotiator {
+    pub fn new() -> Negotiator {
+        Negotiator::default()
+    }
+
+    /// Supports the extension identified by `uri`.
+    pub fn extension<S: Into<String>>(mut self, uri: S) -> Negotiator {
+        self.extensions.push(uri.into());
+        self
+    }
+
+    /// Supports the profile identified by `uri`.
+    pub fn profile<S: Into<String>>(mut self, uri: S) -> Negotiator {
+        self.profiles.push(uri.into());
+        self
+    }
+
+    /// Negotiates with the `Content-Type` and `Accept` headers of a request.
+    ///
+    /// A request body that is not a JSON:API document gets a 415. An `Accept` header without a
+    /// JSON:API instance, such as `*/*`, accepts any response. The response applies the
+    /// extensions of the chosen `Accept` instance, or of the request when there is none, and the
+    /// requested profiles the server supports.
+    pub fn negotiate(&self, content_type: Option<&str>, accept: Option<&str>) -> Negotiation {
+        let request = match content_type.map(MediaType::parse) {
+            Some(Ok(media_type)) => {
+                if !media_type.is_jsonapi() || !self.is_supported(&media_type) {
+                    return Negotiation::new(415, MediaType::jsonapi());
+                }
+
+                Some(media_type)
+            }
+            Some(Err(_)) => return Negotiation::new(415, MediaType::jsonapi()),
+            None => None,
+        };
+
+        let items = accept.map_or_else(Vec::new, parse_accept);
+        let instances = items.iter().filter(|item| item.media_type.is_jsonapi() && item.quality > 0.0);
+        let mut chosen: Option<&AcceptItem> = None;
+        let mut any = false;
+
+        for item in instances {
+            any = true;
+
+            if self.is_supported(&item.media_type) && chosen.is_none_or(|chosen| item.quality > chosen.quality) {
+                chosen = Some(item);
+            }
+        }
+
+        if any && chosen.is_none() {
+            return Negotiation::new(406, MediaType::jsonapi());
+        }
+
+        let mut response = MediaType::jsonapi();
+        let source = chosen.map(|item| &item.media_type).or(request.as_ref());
+
+        if let Some(source) = source {
+            let ext = source.ext();
+
+            if !ext.is_empty() {
+                response = response.with_param("ext", ext.join(" "));
+            }
+
+            let profile = source.profile()
+                .into_iter()
+                .filter(|profile| self.profiles.iter().any(|supported| supported == profile))
+                .collect::<Vec<_>>();
+
+            if !profile.is_empty() {
+                response = response.with_param("profile", profile.join(" "));
+            }
+        }
+
+        Negotiation::new(200, response)
+    }
+
+    /// Whether a JSON:API media type only has `ext` and `profile` parameters, with supported
+    /// extensions. Unknown profiles are ignored.
+    fn is_supported(&self, media_type: &MediaType) -> bool {
+        media_type.params.iter().all(|(name, _)| name == "ext" || name == "profile") &&
+        media_type.ext().iter().all(|ext| self.extensions.iter().any(|supported| supported == ext))
+    }
+}
+
+impl Negotiation {
+    fn new(status: u16, content_type: MediaType) -> Negotiation {
+        Negotiation { status, content_type }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == 200
+    }
+
+    /// The error object to respond with when negotiation failed, whose `source.header` names the
+    /// request header at fault.
+    pub fn error(&self) -> Option<schema::Error> {
+        let (title, header, detail) = match self.status {
+            415 => {
+                ("Unsupported Media Type",
+                 "Content-Type",
+                 "the request body MUST be sent as application/vnd.api+json with only supported `ext` and \
+                  `profile` parameters")
+            }
+            406 => {
+                ("Not Acceptable",
+                 "Accept",
+                 "the Accept header MUST allow application/vnd.api+json with only supported `ext` and `profile` \
+                  parameters")
+            }
+            _ => return None,
+        };
+
+        Some(schema::Error {
+            status: Some(self.status.to_string()),
+            title: Some(title.to_owned()),
+            detail: Some(detail.to_owned()),
+            source: Some(schema::Source {
+                header: Some(header.to_owned()),
+                ..schema::Source::default()
+            }),
+            ..schema::Error::default()
+        })
+    }
+}
+
+/// Why a media type could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaTypeError {
+    pub media_type: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for MediaTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid media type `{}`: {}", self.media_type, self.reason)
+    }
+}
+
+impl error::Error for MediaTypeError {}
+
+/// `token = 1*tchar`
+fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+/// Reads the rest of a quoted string after its opening quote, which **MUST** end with the closing
+/// quote.
+fn unquote(quoted: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return if chars.as_str().is_empty() { Some(value) } else { None },
+            '\\' => value.push(chars.next()?),
+            c => value.push(c),
+        }
+    }
+
+    None
+}
+
+/// Splits on `separator` outside of quoted strings.
+fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (index, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&s[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&s[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATOMIC: &str = "https://jsonapi.org/ext/atomic";
+    const TIMESTAMPS: &str = "http://example.com/profiles/timestamps";
+
+    fn negotiator() -> Negotiator {
+        Negotiator::new().extension(ATOMIC).profile(TIMESTAMPS)
+    }
+
+    fn negotiate(content_type: Option<&str>, accept: Option<&str>) -> (u16, String) {
+        let negotiation = negotiator().negotiate(content_type, accept);
+        (negotiation.status, negotiation.content_type.to_string())
+    }
+
+    #[test]
+    fn test_parse() {
+        let media_type = MediaType::parse("Application/Vnd.API+JSON ; EXT=\"https://jsonapi.org/ext/atomic \
+                                           https://example.com/ext/a\\\"b\"; profile=x;").unwrap();
+        assert!(media_type.is_jsonapi());
+        assert_eq!(media_type.ext(), vec![ATOMIC, "https://example.com/ext/a\"b"]);
+        assert_eq!(media_type.param("Ext"), media_type.param("ext"));
+        assert_eq!(media_type.profile(), vec!["x"]);
+        assert_eq!(media_type.to_string(),
+                   "application/vnd.api+json; ext=\"https://jsonapi.org/ext/atomic https://example.com/ext/a\\\"b\"; \
+                    profile=x");
+        assert_eq!(MediaType::parse(&media_type.to_string()).unwrap(), media_type);
+        assert_eq!(MediaType::parse(JSON_API).unwrap(), MediaType::jsonapi());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let reason = |input| MediaType::parse(input).unwrap_err().reason;
+        assert_eq!(reason("application"), "a media type MUST have a type and a subtype");
+        assert_eq!(reason("application/vnd api"), "the type and subtype MUST be tokens");
+        assert_eq!(reason("application/json; charset"), "a media type parameter MUST have a value");
+        assert_eq!(reason("application/json; ext=\"open"), "a quoted parameter value MUST end with a quote");
+        assert_eq!(reason("application/json; ext=\"a\"b"), "a quoted parameter value MUST end with a quote");
+        assert_eq!(reason("application/json; ext=a b"),
+                   "a media type parameter value MUST be a token or a quoted string");
+    }
+
+    #[test]
+    fn test_parse_accept() {
+        let accept = "text/html, application/vnd.api+json; ext=\"a,b\"; q=0.5; level=1, nonsense, */*;q=0";
+        let items = parse_accept(accept);
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].media_type.ext(), vec!["a,b"]);
+        assert_eq!(items[1].media_type.params.len(), 1);
+        assert_eq!(items[1].quality, 0.5);
+        assert_eq!(items[2].media_type.type_, "*");
+        assert_eq!(items[2].quality, 0.0);
+    }
+
+    #[test]
+    fn test_negotiate_415() {
+        for content_type in &["application/json",
+                              "application/vnd.api+json; charset=utf-8",
+                              "application/vnd.api+json; ext=\"https://example.com/ext/unsupported\"",
+                              "application/vnd.api+json; ext=\"https://jsonapi.org/ext/atomic https://e.com/x\"",
+                              "application/vnd.api+json; ext=\"open"] {
+            assert_eq!(negotiate(Some(content_type), Some(JSON_API)),
+                       (415, JSON_API.to_owned()),
+                       "{}",
+                       content_type);
+        }
+
+        let error = negotiator().negotiate(Some("application/json"), None).error().unwrap();
+        assert_eq!(error.status.as_deref(), Some("415"));
+        assert_eq!(error.source.unwrap().header.as_deref(), Some("Content-Type"));
+    }
+
+    #[test]
+    fn test_negotiate_406() {
+        for accept in &["application/vnd.api+json; charset=utf-8",
+                        "application/vnd.api+json; ext=\"https://example.com/ext/unsupported\", text/html",
+                        "application/vnd.api+json; q=0, application/vnd.api+json; version=1"] {
+            assert_eq!(negotiate(None, Some(accept)), (406, JSON_API.to_owned()), "{}", accept);
+        }
+
+        let error = negotiator().negotiate(None, Some("application/vnd.api+json; charset=utf-8")).error().unwrap();
+        assert_eq!(error.status.as_deref(), Some("406"));
+        assert_eq!(error.source.unwrap().header.as_deref(), Some("Accept"));
+    }
+
+    #[test]
+    fn test_negotiate_200() {
+        assert_eq!(negotiate(None, None), (200, JSON_API.to_owned()));
+        assert_eq!(negotiate(Some(JSON_API), Some("*/*")), (200, JSON_API.to_owned()));
+        assert_eq!(negotiate(None, Some("application/vnd.api+json; charset=utf-8, application/vnd.api+json")),
+                   (200, JSON_API.to_owned()));
+        assert!(negotiator().negotiate(None, None).error().is_none());
+        assert!(negotiator().negotiate(None, None).is_success());
+    }
+
+    #[test]
+    fn test_negotiate_ext_and_profile() {
+        let atomic = format!("{}; ext=\"{}\"", JSON_API, ATOMIC);
+
+        assert_eq!(negotiate(Some(&atomic), None), (200, atomic.clone()));
+        assert_eq!(negotiate(Some(&atomic), Some("*/*")), (200, atomic.clone()));
+        assert_eq!(negotiate(Some(&atomic), Some(JSON_API)), (200, JSON_API.to_owned()));
+        assert_eq!(negotiate(None, Some(&format!("{}; q=0.5, {}", atomic, JSON_API))), (200, JSON_API.to_owned()));
+        assert_eq!(negotiate(None, Some(&format!("{}; q=0.9, {}; q=0.5", atomic, JSON_API))), (200, atomic.clone()));
+        assert_eq!(negotiate(None, Some(&format!("{}; q=0, {}", atomic, JSON_API))), (200, JSON_API.to_owned()));
+
+        let profiles = format!("{}; profile=\"http://example.com/profiles/unknown {}\"", JSON_API, TIMESTAMPS);
+        assert_eq!(negotiate(None, Some(&profiles)),
+                   (200, format!("{}; profile=\"{}\"", JSON_API, TIMESTAMPS)));
+        assert_eq!(negotiate(None, Some("application/vnd.api+json; profile=\"http://example.com/profiles/unknown\"")),
+                   (200, JSON_API.to_owned()));
+    }
+}
@@ -0,0 +1,467 @@
+//! Builds compound documents from nested records, the reverse of `spec::Document::denormalize`.
+//!
+//! A `ResourceDescriptor` says which `type` a record has, which member holds its `id`, and which
+//! members are relationships to other records. Every other member becomes an attribute. Related
+//! records are placed in `included`, and the relationship members are replaced by resource linkage.
+//!
+//! ```text
+//! {"id": 1, "title": "JSON API paints my bikeshed!", "author": {"id": 9, "name": "Dan"}}
+//! ```
+//!
+//! with a descriptor of type `articles` whose `author` relationship is of type `people` becomes
+//!
+//! ```text
+//! {
+//!   "data": {
+//!     "type": "articles",
+//!     "id": "1",
+//!     "attributes": {"title": "JSON API paints my bikeshed!"},
+//!     "relationships": {"author": {"data": {"type": "people", "id": "9"}}}
+//!   },
+//!   "included": [{"type": "people", "id": "9", "attributes": {"name": "Dan"}}]
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+
+use serde::ser::Serialize;
+use serde_json::{self, Value};
+
+use error::{check_attribute, ValidationError};
+use pointer::JsonPointer;
+use spec::{Attributes, Document, Id, MergeConflict, Name, NameError, Object, PrimaryData, Relationship,
+           Relationships, ResourceIdentifierObject, ResourceLinkage, ResourceObject, Type};
+
+/// Describes how the records of one type map to resource objects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceDescriptor {
+    type_: Type,
+    id: String,
+    relationships: BTreeMap<String, ResourceDescriptor>,
+}
+
+impl ResourceDescriptor {
+    /// Describes records of `type_` whose `id` is held in their `id` member.
+    pub fn new(type_: Type) -> ResourceDescriptor {
+        ResourceDescriptor {
+            type_,
+            id: "id".to_owned(),
+            relationships: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the `id` from the member `key` instead.
+    pub fn id_key<S: Into<String>>(mut self, key: S) -> ResourceDescriptor {
+        self.id = key.into();
+        self
+    }
+
+    /// Treats the member `key` as a relationship to records described by `related`.
+    ///
+    /// The member may hold a nested record, an array of them, or `null`. It may also hold the bare
+    /// `id` of a related record, or an array of them, in which case the relationship gets linkage
+    /// but nothing is included.
+    pub fn relationship<S: Into<String>>(mut self, key: S, related: ResourceDescriptor) -> ResourceDescriptor {
+        self.relationships.insert(key.into(), related);
+        self
+    }
+
+    pub fn type_(&self) -> &Type {
+        &self.type_
+    }
+}
+
+/// Normalizes the JSON form of `records`, such as a struct or a `Vec` of structs, into a
+/// compound document.
+pub fn normalize<T: Serialize>(records: &T, descriptor: &ResourceDescriptor) -> Result<Document, NormalizeError> {
+    let value = serde_json::to_value(records).map_err(|error| {
+        NormalizeError::Serialization { message: error.to_string() }
+    })?;
+
+    normalize_value(&value, descriptor)
+}
+
+/// Normalizes a record, or an array of records, into a compound document. Records of the same
+/// `type` and `id` are included once, merging their members.
+pub fn normalize_value(records: &Value, descriptor: &ResourceDescriptor) -> Result<Document, NormalizeError> {
+    let mut included = Vec::new();
+    let pointer = JsonPointer::root();
+
+    let data = match *records {
+        Value::Null => PrimaryData::Null,
+        Value::Array(ref records) => {
+            let resources = records.iter()
+                .enumerate()
+                .map(|(index, record)| resource(record, descriptor, &pointer.child(index), true, &mut included))
+                .collect::<Result<_, _>>()?;
+
+            PrimaryData::Array(resources)
+        }
+        _ => PrimaryData::Single(resource(records, descriptor, &pointer, true, &mut included)?),
+    };
+
+    let mut document = Document::Data {
+        jsonapi: None,
+        data,
+        meta: None,
+        links: None,
+        included,
+    };
+
+    if let Some(conflict) = document.normalize_included().into_iter().next() {
+        return Err(NormalizeError::Conflict(conflict));
+    }
+
+    Ok(document)
+}
+
+/// Builds the resource object for `record`, adding the resource objects of the records it is
+/// related to to `included`.
+fn resource(record: &Value,
+            descriptor: &ResourceDescriptor,
+            pointer: &JsonPointer,
+            primary: bool,
+            included: &mut Vec<ResourceObject>)
+            -> Result<ResourceObject, NormalizeError> {
+    let record = match *record {
+        Value::Object(ref record) => record,
+        _ => return Err(NormalizeError::NotAnObject { pointer: pointer.clone() }),
+    };
+
+    // A resource object that originates at the client and represents a new resource to be created
+    // on the server may leave out its id, but a related record needs one to be linked to.
+    let id = match record.get(&descriptor.id) {
+        Some(id) => Some(record_id(id, &pointer.child(&descriptor.id))?),
+        None if primary => None,
+        None => return Err(NormalizeError::MissingId { pointer: pointer.clone() }),
+    };
+
+    let mut attributes = Object::new();
+    let mut relationships = BTreeMap::new();
+
+    for (key, value) in record {
+        let pointer = pointer.child(key);
+
+        if *key == descriptor.id {
+            continue;
+        }
+
+        let name = Name::parse(key.as_str()).map_err(|error| {
+            NormalizeError::InvalidName {
+                pointer: pointer.clone(),
+                error,
+            }
+        })?;
+
+        match descriptor.relationships.get(key) {
+            Some(related) => {
+                let data = linkage(value, related, &pointer, included)?;
+
+                relationships.insert(name, Relationship {
+                    links: None,
+                    data: Some(data),
+                    meta: None,
+                });
+            }
+            None => {
+                check_attribute(key, value).map_err(NormalizeError::Validation)?;
+
+                attributes.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    let resource = ResourceObject {
+        id,
+        lid: None,
+        type_: descriptor.type_.clone(),
+        attributes: if attributes.is_empty() { None } else { Some(Attributes(attributes)) },
+        relationships: if relationships.is_empty() { None } else { Some(Relationships(relationships)) },
+        links: None,
+        meta: None,
+    };
+
+    resource.check_fields().map_err(NormalizeError::Validation)?;
+
+    Ok(resource)
+}
+
+/// Builds the linkage of a relationship member, including the records it holds.
+fn linkage(value: &Value,
+           descriptor: &ResourceDescriptor,
+           pointer: &JsonPointer,
+           included: &mut Vec<ResourceObject>)
+           -> Result<ResourceLinkage, NormalizeError> {
+    match *value {
+        Value::Null => Ok(ResourceLinkage::Null),
+        Value::Array(ref values) => {
+            values.iter()
+                .enumerate()
+                .map(|(index, value)| identifier(value, descriptor, &pointer.child(index), included))
+                .collect::<Result<_, _>>()
+                .map(ResourceLinkage::Array)
+        }
+        _ => identifier(value, descriptor, pointer, included).map(ResourceLinkage::Single),
+    }
+}
+
+/// Builds the resource identifier object of a related record, or of the bare `id` of one.
+fn identifier(value: &Value,
+              descriptor: &ResourceDescriptor,
+              pointer: &JsonPointer,
+              included: &mut Vec<ResourceObject>)
+              -> Result<ResourceIdentifierObject, NormalizeError> {
+    let id = match *value {
+        Value::Object(_) => {
+            let resource = resource(value, descriptor, pointer, false, included)?;
+            let id = resource.id.clone().expect("related records have an id");
+            included.push(resource);
+            id
+        }
+        _ => record_id(value, pointer)?,
+    };
+
+    Ok(ResourceIdentifierObject::new(descriptor.type_.clone(), id))
+}
+
+/// The value of an `id` member **MUST** be a string, so numeric ids are converted to one.
+fn record_id(value: &Value, pointer: &JsonPointer) -> Result<Id, NormalizeError> {
+    match *value {
+        Value::String(ref id) => Ok(Id(id.clone())),
+        Value::Number(ref id) => Ok(Id(id.to_string())),
+        _ => Err(NormalizeError::InvalidId { pointer: pointer.clone() }),
+    }
+}
+
+/// Why records could not be normalized. Pointers reference the records being normalized.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NormalizeError {
+    /// A record, or a member described as a relationship, that is not an object, an array of them
+    /// or an id.
+    NotAnObject {
+        pointer: JsonPointer,
+    },
+
+    /// A related record without an `id`, which can't be linked to.
+    MissingId {
+        pointer: JsonPointer,
+    },
+
+    /// An `id` that is neither a string nor a number.
+    InvalidId {
+        pointer: JsonPointer,
+    },
+
+    /// A member whose key is not a valid member name.
+    InvalidName {
+        pointer: JsonPointer,
+        error: NameError,
+    },
+
+    /// A record whose members break the rules on attributes and fields.
+    Validation(ValidationError),
+
+    /// Two records of the same `type` and `id` that disagree on a member.
+    Conflict(MergeConflict),
+
+    /// The records could not be serialized to JSON.
+    Serialization {
+        message: String,
+    },
+}
+
+impl fmt::Display for NormalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NormalizeError::NotAnObject { ref pointer } => write!(f, "`{}` is not a record", pointer),
+            NormalizeError::MissingId { ref pointer } => write!(f, "related record `{}` has no id", pointer),
+            NormalizeError::InvalidId { ref pointer } => write!(f, "`{}` is not a string or number id", pointer),
+            NormalizeError::InvalidName { ref pointer, ref error } => write!(f, "`{}`: {}", pointer, error),
+            NormalizeError::Validation(ref error) => error.fmt(f),
+            NormalizeError::Conflict(ref conflict) => conflict.fmt(f),
+            NormalizeError::Serialization { ref message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for NormalizeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn type_(type_: &str) -> Type {
+        Type(Name::parse(type_).unwrap())
+    }
+
+    fn articles() -> ResourceDescriptor {
+        let people = ResourceDescriptor::new(type_("people"));
+        let comments = ResourceDescriptor::new(type_("comments")).relationship("author", people.clone());
+
+        ResourceDescriptor::new(type_("articles"))
+            .relationship("author", people)
+            .relationship("comments", comments)
+    }
+
+    #[derive(Serialize)]
+    struct Person {
+        id: u64,
+        name: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct Comment {
+        id: u64,
+        body: &'static str,
+        author: Person,
+    }
+
+    #[derive(Serialize)]
+    struct Article {
+        id: u64,
+        title: &'static str,
+        author: Option<Person>,
+        comments: Vec<Comment>,
+    }
+
+    #[test]
+    fn test_normalize_nested_tree() {
+        let records = vec![
+            Article {
+                id: 1,
+                title: "JSON API paints my bikeshed!",
+                author: Some(Person { id: 9, name: "Dan" }),
+                comments: vec![
+                    Comment { id: 5, body: "First!", author: Person { id: 2, name: "Yehuda" } },
+                    Comment { id: 12, body: "I like XML better", author: Person { id: 9, name: "Dan" } },
+                ],
+            },
+            Article {
+                id: 2,
+                title: "Rails is Omakase",
+                author: None,
+                comments: vec![Comment { id: 12, body: "I like XML better", author: Person { id: 9, name: "Dan" } }],
+            },
+        ];
+
+        let document = normalize(&records, &articles()).unwrap();
+        assert_eq!(serde_json::to_value(&document).unwrap(), json!({
+            "data": [
+                {
+                    "type": "articles",
+                    "id": "1",
+                    "attributes": {"title": "JSON API paints my bikeshed!"},
+                    "relationships": {
+                        "author": {"data": {"type": "people", "id": "9"}},
+                        "comments": {"data": [{"type": "comments", "id": "5"}, {"type": "comments", "id": "12"}]}
+                    }
+                },
+                {
+                    "type": "articles",
+                    "id": "2",
+                    "attributes": {"title": "Rails is Omakase"},
+                    "relationships": {
+                        "author": {"data": null},
+                        "comments": {"data": [{"type": "comments", "id": "12"}]}
+                    }
+                }
+            ],
+            "included": [
+                {"type": "people", "id": "9", "attributes": {"name": "Dan"}},
+                {"type": "people", "id": "2", "attributes": {"name": "Yehuda"}},
+                {
+                    "type": "comments",
+                    "id": "5",
+                    "attributes": {"body": "First!"},
+                    "relationships": {"author": {"data": {"type": "people", "id": "2"}}}
+                },
+                {
+                    "type": "comments",
+                    "id": "12",
+                    "attributes": {"body": "I like XML better"},
+                    "relationships": {"author": {"data": {"type": "people", "id": "9"}}}
+                }
+            ]
+        }));
+    }
+
+    #[test]
+    fn test_normalize_merges_partial_records() {
+        let document = normalize_value(&json!({
+            "id": 1,
+            "author": {"id": 9, "name": "Dan"},
+            "comments": [{"id": 5, "author": {"id": 9, "twitter": "dgeb"}}]
+        }), &articles()).unwrap();
+
+        assert_eq!(serde_json::to_value(&document).unwrap()["included"][0],
+                   json!({"type": "people", "id": "9", "attributes": {"name": "Dan", "twitter": "dgeb"}}));
+    }
+
+    #[test]
+    fn test_normalize_merges_copies_of_primary_records() {
+        let people = ResourceDescriptor::new(type_("people"));
+        let descriptor = people.clone().relationship("friends", people);
+
+        let document = normalize_value(&json!({"id": 9, "name": "Dan", "friends": [{"id": 9, "twitter": "dgeb"}]}),
+                                       &descriptor).unwrap();
+        let document = serde_json::to_value(&document).unwrap();
+        assert_eq!(document["data"]["attributes"], json!({"name": "Dan", "twitter": "dgeb"}));
+        assert_eq!(document.get("included"), None);
+
+        let error = normalize_value(&json!({"id": 9, "name": "Dan", "friends": [{"id": 9, "name": "Daniel"}]}),
+                                    &descriptor).unwrap_err();
+        assert_eq!(error.to_string(), "resource objects `people` `9` have different values for `/attributes/name`");
+    }
+
+    #[test]
+    fn test_normalize_bare_ids() {
+        let document = normalize_value(&json!({"id": "1", "author": 9, "comments": ["5", 12]}), &articles()).unwrap();
+        assert_eq!(serde_json::to_value(&document).unwrap(), json!({
+            "data": {
+                "type": "articles",
+                "id": "1",
+                "relationships": {
+                    "author": {"data": {"type": "people", "id": "9"}},
+                    "comments": {"data": [{"type": "comments", "id": "5"}, {"type": "comments", "id": "12"}]}
+                }
+            }
+        }));
+    }
+
+    #[test]
+    fn test_normalize_id_key() {
+        let descriptor = ResourceDescriptor::new(type_("people")).id_key("uuid");
+        let document = normalize_value(&json!({"uuid": "a1", "name": "Dan"}), &descriptor).unwrap();
+        assert_eq!(serde_json::to_value(&document).unwrap(),
+                   json!({"data": {"type": "people", "id": "a1", "attributes": {"name": "Dan"}}}));
+
+        assert_eq!(normalize_value(&json!({"uuid": "a1", "id": 9}), &descriptor),
+                   Err(NormalizeError::Validation(ValidationError::NameCollision { name: "id".to_owned() })));
+    }
+
+    #[test]
+    fn test_normalize_errors() {
+        fn error(records: Value) -> String {
+            normalize_value(&records, &articles()).unwrap_err().to_string()
+        }
+
+        assert_eq!(error(json!([{"id": 1}, "2"])), "`/1` is not a record");
+        assert_eq!(error(json!({"comments": [{"body": "First!"}]})), "related record `/comments/0` has no id");
+        assert_eq!(error(json!({"id": true})), "`/id` is not a string or number id");
+        assert_eq!(error(json!({"author": [1.5, {"id": false}]})), "`/author/1/id` is not a string or number id");
+        assert!(matches!(normalize_value(&json!({"-name": "Dan"}), &articles()),
+                         Err(NormalizeError::InvalidName { .. })));
+        assert_eq!(normalize_value(&json!({"links": {}}), &articles()),
+                   Err(NormalizeError::Validation(ValidationError::ReservedMember { name: "links".to_owned() })));
+        assert_eq!(normalize_value(&json!({"id": 1, "author": {"id": 9, "address": {"links": {}}}}), &articles()),
+                   Err(NormalizeError::Validation(ValidationError::ReservedMember { name: "links".to_owned() })));
+        assert_eq!(error(json!({
+                       "author": {"id": 9, "name": "Dan"},
+                       "comments": [{"id": 5, "author": {"id": 9, "name": "Daniel"}}]
+                   })),
+                   "resource objects `people` `9` have different values for `/attributes/name`");
+    }
+}
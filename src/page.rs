@@ -0,0 +1,402 @@
+//! http://jsonapi.org/format/#fetching-pagination
+//!
+//! A server **MAY** choose to limit the number of resources returned in a response to a subset
+//! ("page") of the whole set available.
+//!
+//! A server **MAY** provide links to traverse a paginated data set ("pagination links").
+//! Pagination links **MUST** appear in the links object that corresponds to a collection. To
+//! paginate the primary data, supply pagination links in the top-level `links` object.
+//!
+//! The following keys **MUST** be used for pagination links:
+//!
+//! * `first`: the first page of data
+//! * `last`: the last page of data
+//! * `prev`: the previous page of data
+//! * `next`: the next page of data
+//!
+//! Keys **MUST** either be omitted or have a `null` value to indicate that a particular link is
+//! unavailable.
+//!
+//! The `page` query parameter is reserved for pagination. Each strategy here reads its own
+//! members of the `page` family, and rejects the others with 400 Bad Request. Links keep every
+//! query parameter of the base URI outside the `page` family.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use error::ParameterError;
+use schema::{Meta, Object, Pagination};
+use uri::{parse_query, Uri};
+
+/// The page size used when a request doesn't give one, and the largest page size served. Larger
+/// requested sizes are clamped to `max`, and a size of 0 is rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageSize {
+    default: u64,
+    max: u64,
+}
+
+/// Page-number pagination with `page[number]`, counting from 1, and `page[size]`. A `number` or
+/// `size` of 0, which `parse` never returns, is treated as 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageNumber {
+    pub number: u64,
+    pub size: u64,
+}
+
+/// Offset-based pagination with `page[offset]`, counting from 0, and `page[limit]`. A `limit` of
+/// 0, which `parse` never returns, is treated as 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OffsetLimit {
+    pub offset: u64,
+    pub limit: u64,
+}
+
+/// Cursor-based pagination with `page[after]`, `page[before]` and `page[size]`. The cursors are
+/// opaque to the client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub size: u64,
+}
+
+impl PageSize {
+    /// A page size of `default`, up to `max`. Returns `None` unless `1 <= default <= max`.
+    pub fn new(default: u64, max: u64) -> Option<PageSize> {
+        if 0 < default && default <= max {
+            Some(PageSize { default, max })
+        } else {
+            None
+        }
+    }
+
+    pub fn default(&self) -> u64 {
+        self.default
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+}
+
+impl PageNumber {
+    /// Reads `page[number]` and `page[size]` from a query string.
+    pub fn parse(query: &str, size: PageSize) -> Result<PageNumber, ParameterError> {
+        let params = page_params(query, &["number", "size"])?;
+
+        let number = match params.get("number") {
+            Some(number) => {
+                match number.parse() {
+                    Ok(number) if number > 0 => number,
+                    _ => return Err(invalid("number", "a page number counting from 1")),
+                }
+            }
+            None => 1,
+        };
+
+        Ok(PageNumber {
+            number,
+            size: page_size(&params, "size", size)?,
+        })
+    }
+
+    /// The number of resources before this page.
+    pub fn offset(&self) -> u64 {
+        self.number.saturating_sub(1).saturating_mul(self.size())
+    }
+
+    /// The number of pages needed for `total` resources, which is at least 1.
+    pub fn pages(&self, total: u64) -> u64 {
+        total.div_ceil(self.size()).max(1)
+    }
+
+    fn size(&self) -> u64 {
+        self.size.max(1)
+    }
+
+    /// The pagination links for this page. Without a `total`, `last` is left out and `next` is
+    /// always given. Past the last page, `prev` links to the last page.
+    pub fn links(&self, base: &Uri, total: Option<u64>) -> Pagination {
+        let link = |number: u64| {
+            page_link(base, &[("page[number]", number.to_string()), ("page[size]", self.size().to_string())])
+        };
+        let last = total.map(|total| self.pages(total));
+        let prev = self.number.saturating_sub(1);
+
+        Pagination {
+            first: Some(link(1)),
+            last: last.map(link),
+            prev: if prev > 0 { Some(link(last.map_or(prev, |last| last.min(prev)))) } else { None },
+            next: if last.is_none_or(|last| self.number < last) { Some(link(self.number.max(1) + 1)) } else { None },
+        }
+    }
+
+    /// Top-level `meta` with the `total` number of resources and of pages.
+    pub fn meta(&self, total: u64) -> Meta {
+        total_meta(total, Some(self.pages(total)))
+    }
+}
+
+impl OffsetLimit {
+    /// Reads `page[offset]` and `page[limit]` from a query string.
+    pub fn parse(query: &str, size: PageSize) -> Result<OffsetLimit, ParameterError> {
+        let params = page_params(query, &["offset", "limit"])?;
+
+        let offset = match params.get("offset") {
+            Some(offset) => offset.parse().map_err(|_| invalid("offset", "a non-negative integer"))?,
+            None => 0,
+        };
+
+        Ok(OffsetLimit {
+            offset,
+            limit: page_size(&params, "limit", size)?,
+        })
+    }
+
+    /// The pagination links for this page. Without a `total`, `last` is left out and `next` is
+    /// always given. Past the last page, `prev` links to the last page.
+    pub fn links(&self, base: &Uri, total: Option<u64>) -> Pagination {
+        let link = |offset: u64| {
+            page_link(base, &[("page[offset]", offset.to_string()), ("page[limit]", self.limit().to_string())])
+        };
+        let limit = self.limit();
+        let next = self.offset.saturating_add(limit);
+        let last = total.map(|total| total.saturating_sub(1) / limit * limit);
+        let prev = self.offset.saturating_sub(limit);
+
+        Pagination {
+            first: Some(link(0)),
+            last: last.map(link),
+            prev: if self.offset > 0 { Some(link(last.map_or(prev, |last| last.min(prev)))) } else { None },
+            next: if total.is_none_or(|total| next < total) { Some(link(next)) } else { None },
+        }
+    }
+
+    /// Top-level `meta` with the `total` number of resources.
+    pub fn meta(&self, total: u64) -> Meta {
+        total_meta(total, None)
+    }
+
+    fn limit(&self) -> u64 {
+        self.limit.max(1)
+    }
+}
+
+impl Cursor {
+    /// Reads `page[after]`, `page[before]` and `page[size]` from a query string.
+    pub fn parse(query: &str, size: PageSize) -> Result<Cursor, ParameterError> {
+        let params = page_params(query, &["after", "before", "size"])?;
+
+        Ok(Cursor {
+            after: params.get("after").cloned(),
+            before: params.get("before").cloned(),
+            size: page_size(&params, "size", size)?,
+        })
+    }
+
+    /// The pagination links for this page, given the cursors of its first and last resources
+    /// when there are pages before and after it. `last` is left out, since a cursor can't
+    /// address it.
+    pub fn links(&self, base: &Uri, prev: Option<&str>, next: Option<&str>) -> Pagination {
+        let link = |cursor: Option<(&str, &str)>| {
+            let mut page = Vec::new();
+
+            if let Some((name, cursor)) = cursor {
+                page.push((name, cursor.to_owned()));
+            }
+
+            page.push(("page[size]", self.size.to_string()));
+            page_link(base, &page)
+        };
+
+        Pagination {
+            first: Some(link(None)),
+            last: None,
+            prev: prev.map(|cursor| link(Some(("page[before]", cursor)))),
+            next: next.map(|cursor| link(Some(("page[after]", cursor)))),
+        }
+    }
+
+    /// Top-level `meta` with the `total` number of resources.
+    pub fn meta(&self, total: u64) -> Meta {
+        total_meta(total, None)
+    }
+}
+
+/// Collects the `page[NAME]` parameters of a query string by `NAME`, rejecting any `NAME` not in
+/// `supported` and any other parameter of the `page` family.
+fn page_params(query: &str, supported: &[&str]) -> Result<BTreeMap<String, String>, ParameterError> {
+    let mut params = BTreeMap::new();
+
+    for (name, value) in parse_query(query) {
+        if name != "page" && !name.starts_with("page[") {
+            continue;
+        }
+
+        match name.strip_prefix("page[").and_then(|rest| rest.strip_suffix(']')) {
+            Some(member) if supported.contains(&member) => {
+                params.insert(member.to_owned(), value);
+            }
+            _ => {
+                let supported = supported.iter().map(|member| format!("`page[{}]`", member)).collect::<Vec<_>>();
+                let detail = format!("the endpoint only supports {} for pagination", supported.join(", "));
+
+                return Err(ParameterError::new(&name, detail));
+            }
+        }
+    }
+
+    Ok(params)
+}
+
+/// Reads the page size `page[member]`, clamped to the largest size served.
+fn page_size(params: &BTreeMap<String, String>, member: &str, size: PageSize) -> Result<u64, ParameterError> {
+    match params.get(member) {
+        Some(value) => {
+            match value.parse::<u64>() {
+                Ok(value) if value > 0 => Ok(value.min(size.max)),
+                _ => Err(invalid(member, "a positive integer")),
+            }
+        }
+        None => Ok(size.default),
+    }
+}
+
+fn invalid(member: &str, expected: &str) -> ParameterError {
+    let parameter = format!("page[{}]", member);
+    let detail = format!("`{}` MUST be {}", parameter, expected);
+
+    ParameterError::new(&parameter, detail)
+}
+
+/// `base` with its `page` family replaced by `page`.
+fn page_link(base: &Uri, page: &[(&str, String)]) -> Uri {
+    let pairs = base.query_pairs()
+        .into_iter()
+        .filter(|(name, _)| name != "page" && !name.starts_with("page["))
+        .chain(page.iter().map(|(name, value)| ((*name).to_owned(), value.clone())));
+
+    base.with_query_pairs(pairs)
+}
+
+fn total_meta(total: u64, pages: Option<u64>) -> Meta {
+    let mut meta = BTreeMap::new();
+    meta.insert("total".to_owned(), Value::from(total));
+
+    if let Some(pages) = pages {
+        meta.insert("pages".to_owned(), Value::from(pages));
+    }
+
+    Meta(Object(meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size() -> PageSize {
+        PageSize::new(10, 50).unwrap()
+    }
+
+    fn link(uri: Option<Uri>) -> Option<String> {
+        uri.map(|uri| uri.to_string())
+    }
+
+    #[test]
+    fn test_page_size_new() {
+        assert_eq!(PageSize::new(0, 10), None);
+        assert_eq!(PageSize::new(20, 10), None);
+        assert_eq!(PageSize::new(10, 10).map(|size| (size.default(), size.max())), Some((10, 10)));
+    }
+
+    #[test]
+    fn test_page_number_parse() {
+        assert_eq!(PageNumber::parse("", size()).unwrap(), PageNumber { number: 1, size: 10 });
+        assert_eq!(PageNumber::parse("page[number]=3&page[size]=100", size()).unwrap(),
+                   PageNumber { number: 3, size: 50 });
+
+        let errors = [("page[number]=0", "page[number]"),
+                      ("page[size]=0", "page[size]"),
+                      ("page[size]=-1", "page[size]"),
+                      ("page[offset]=1", "page[offset]"),
+                      ("page=1", "page")];
+
+        for &(query, parameter) in &errors {
+            assert_eq!(PageNumber::parse(query, size()).unwrap_err().parameter, parameter);
+        }
+    }
+
+    #[test]
+    fn test_page_number_arithmetic() {
+        let page = PageNumber { number: 3, size: 10 };
+        assert_eq!(page.offset(), 20);
+        assert_eq!(page.pages(0), 1);
+        assert_eq!(page.pages(21), 3);
+
+        // Values `parse` never returns don't divide by zero or underflow.
+        assert_eq!(PageNumber { number: 0, size: 10 }.offset(), 0);
+        assert_eq!(PageNumber { number: 2, size: 0 }.offset(), 1);
+        assert_eq!(PageNumber { number: 1, size: 0 }.pages(5), 5);
+        assert_eq!(PageNumber { number: u64::MAX, size: u64::MAX }.offset(), u64::MAX);
+    }
+
+    #[test]
+    fn test_page_number_links() {
+        let base = Uri::parse("/articles?sort=title&page[number]=2").unwrap();
+        let links = PageNumber { number: 2, size: 10 }.links(&base, Some(25));
+
+        assert_eq!(link(links.first).as_deref(), Some("/articles?sort=title&page[number]=1&page[size]=10"));
+        assert_eq!(link(links.last).as_deref(), Some("/articles?sort=title&page[number]=3&page[size]=10"));
+        assert_eq!(link(links.prev).as_deref(), Some("/articles?sort=title&page[number]=1&page[size]=10"));
+        assert_eq!(link(links.next).as_deref(), Some("/articles?sort=title&page[number]=3&page[size]=10"));
+
+        let links = PageNumber { number: 3, size: 10 }.links(&base, Some(25));
+        assert_eq!(link(links.prev).as_deref(), Some("/articles?sort=title&page[number]=2&page[size]=10"));
+        assert_eq!(links.next, None);
+
+        let links = PageNumber { number: 7, size: 10 }.links(&base, Some(25));
+        assert_eq!(link(links.prev).as_deref(), Some("/articles?sort=title&page[number]=3&page[size]=10"));
+        assert_eq!(links.next, None);
+
+        let links = PageNumber { number: 0, size: 0 }.links(&base, None);
+        assert_eq!(links.prev, None);
+        assert_eq!(link(links.next).as_deref(), Some("/articles?sort=title&page[number]=2&page[size]=1"));
+    }
+
+    #[test]
+    fn test_offset_limit() {
+        let page = OffsetLimit::parse("page[offset]=20&page[limit]=10", size()).unwrap();
+        assert_eq!(page, OffsetLimit { offset: 20, limit: 10 });
+
+        let base = Uri::parse("/articles").unwrap();
+        let links = page.links(&base, Some(45));
+        assert_eq!(link(links.last).as_deref(), Some("/articles?page[offset]=40&page[limit]=10"));
+        assert_eq!(link(links.prev).as_deref(), Some("/articles?page[offset]=10&page[limit]=10"));
+        assert_eq!(link(links.next).as_deref(), Some("/articles?page[offset]=30&page[limit]=10"));
+        assert_eq!(page.links(&base, Some(30)).next, None);
+
+        let links = OffsetLimit { offset: 100, limit: 10 }.links(&base, Some(45));
+        assert_eq!(link(links.prev).as_deref(), Some("/articles?page[offset]=40&page[limit]=10"));
+        assert_eq!(links.next, None);
+
+        let links = OffsetLimit { offset: 3, limit: 0 }.links(&base, Some(5));
+        assert_eq!(link(links.last).as_deref(), Some("/articles?page[offset]=4&page[limit]=1"));
+        assert_eq!(link(links.next).as_deref(), Some("/articles?page[offset]=4&page[limit]=1"));
+
+        assert!(OffsetLimit::parse("page[limit]=0", size()).is_err());
+        assert!(OffsetLimit::parse("page[number]=1", size()).is_err());
+    }
+
+    #[test]
+    fn test_cursor() {
+        let page = Cursor::parse("page[after]=abc", size()).unwrap();
+        assert_eq!(page, Cursor { after: Some("abc".to_owned()), before: None, size: 10 });
+
+        let links = page.links(&Uri::parse("/articles").unwrap(), Some("abd"), None);
+        assert_eq!(link(links.first).as_deref(), Some("/articles?page[size]=10"));
+        assert_eq!(link(links.prev).as_deref(), Some("/articles?page[before]=abd&page[size]=10"));
+        assert_eq!(links.next, None);
+    }
+}
@@ -0,0 +1,306 @@
+//! https://tools.ietf.org/html/rfc6901
+//!
+//! A JSON Pointer identifies a specific value within a JSON document. Error objects use one in
+//! `source.pointer` to reference the entity in the request document that caused the error.
+
+use std::error;
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_json::{self, Value};
+
+/// A parsed JSON Pointer, held as its sequence of unescaped reference tokens. The empty pointer
+/// references the whole document.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JsonPointer(Vec<String>);
+
+impl JsonPointer {
+    /// The pointer to the whole document.
+    pub fn root() -> JsonPointer {
+        JsonPointer(Vec::new())
+    }
+
+    /// Parses a pointer such as `/data/attributes/title`, unescaping `~1` to `/` and `~0` to `~`.
+    pub fn parse(pointer: &str) -> Result<JsonPointer, PointerError> {
+        if pointer.is_empty() {
+            return Ok(JsonPointer::root());
+        }
+
+        if !pointer.starts_with('/') {
+            return Err(PointerError::MissingLeadingSlash);
+        }
+
+        let mut tokens = Vec::new();
+        let mut token = String::new();
+        let mut chars = pointer.char_indices().skip(1);
+
+        while let Some((position, c)) = chars.next() {
+            match c {
+                '/' => tokens.push(mem::take(&mut token)),
+                '~' => {
+                    match chars.next() {
+                        Some((_, '0')) => token.push('~'),
+                        Some((_, '1')) => token.push('/'),
+                        _ => return Err(PointerError::InvalidEscape { position }),
+                    }
+                }
+                c => token.push(c),
+            }
+        }
+
+        tokens.push(token);
+
+        Ok(JsonPointer(tokens))
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.0
+    }
+
+    pub fn push<T: ToString>(&mut self, token: T) {
+        self.0.push(token.to_string());
+    }
+
+    /// Returns a new pointer to the member or element `token` of the value this pointer references.
+    pub fn child<T: ToString>(&self, token: T) -> JsonPointer {
+        let mut pointer = self.clone();
+        pointer.push(token);
+        pointer
+    }
+
+    /// Returns the pointer to the value containing the one this pointer references, or `None` for
+    /// the root pointer.
+    pub fn parent(&self) -> Option<JsonPointer> {
+        if self.0.is_empty() {
+            None
+        } else {
+            Some(JsonPointer(self.0[..self.0.len() - 1].to_vec()))
+        }
+    }
+
+    /// https://tools.ietf.org/html/rfc6901#section-4
+    ///
+    /// Evaluates this pointer against `value`. Array elements are referenced by their zero-based
+    /// index, written without leading zeros.
+    pub fn resolve<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.0.iter().try_fold(value, |value, token| {
+            match *value {
+                Value::Object(ref object) => object.get(token),
+                Value::Array(ref array) => array_index(token).and_then(|index| array.get(index)),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn resolve_mut<'a>(&self, value: &'a mut Value) -> Option<&'a mut Value> {
+        self.0.iter().try_fold(value, |value, token| {
+            match *value {
+                Value::Object(ref mut object) => object.get_mut(token),
+                Value::Array(ref mut array) => array_index(token).and_then(move |index| array.get_mut(index)),
+                _ => None,
+            }
+        })
+    }
+
+    /// Evaluates this pointer against the JSON form of `document`, such as a `spec::Document` or a
+    /// `schema::Document`, and returns a copy of the value it references.
+    pub fn resolve_document<T: Serialize>(&self, document: &T) -> Option<Value> {
+        let value = serde_json::to_value(document).ok()?;
+        self.resolve(&value).cloned()
+    }
+}
+
+fn array_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.len() > 1 && token.starts_with('0')) {
+        None
+    } else {
+        token.parse().ok()
+    }
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in &self.0 {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(pointer: &str) -> Result<JsonPointer, PointerError> {
+        JsonPointer::parse(pointer)
+    }
+}
+
+impl<T: ToString> FromIterator<T> for JsonPointer {
+    fn from_iter<I>(tokens: I) -> JsonPointer
+        where I: IntoIterator<Item = T>,
+    {
+        JsonPointer(tokens.into_iter().map(|token| token.to_string()).collect())
+    }
+}
+
+impl Serialize for JsonPointer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonPointer {
+    fn deserialize<D>(deserializer: D) -> Result<JsonPointer, D::Error>
+        where D: Deserializer<'de>,
+    {
+        JsonPointer::parse(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+/// Why a string is not a JSON Pointer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerError {
+    /// A non-empty pointer **MUST** start with `/`.
+    MissingLeadingSlash,
+
+    /// A `~` at the given byte offset that is not followed by `0` or `1`.
+    InvalidEscape {
+        position: usize,
+    },
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PointerError::MissingLeadingSlash => write!(f, "a JSON Pointer MUST be empty or start with `/`"),
+            PointerError::InvalidEscape { position } => {
+                write!(f, "`~` at position {} of a JSON Pointer MUST be followed by `0` or `1`", position)
+            }
+        }
+    }
+}
+
+impl error::Error for PointerError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use spec::Document;
+
+    /// https://tools.ietf.org/html/rfc6901#section-5
+    fn rfc_document() -> Value {
+        json!({
+            "foo": ["bar", "baz"],
+            "": 0,
+            "a/b": 1,
+            "c%d": 2,
+            "e^f": 3,
+            "g|h": 4,
+            "i\\j": 5,
+            "k\"l": 6,
+            " ": 7,
+            "m~n": 8
+        })
+    }
+
+    #[test]
+    fn test_resolve_rfc_examples() {
+        let document = rfc_document();
+
+        for &(pointer, ref expected) in &[
+            ("", document.clone()),
+            ("/foo", json!(["bar", "baz"])),
+            ("/foo/0", json!("bar")),
+            ("/", json!(0)),
+            ("/a~1b", json!(1)),
+            ("/c%d", json!(2)),
+            ("/e^f", json!(3)),
+            ("/g|h", json!(4)),
+            ("/i\\j", json!(5)),
+            ("/k\"l", json!(6)),
+            ("/ ", json!(7)),
+            ("/m~0n", json!(8)),
+        ] {
+            assert_eq!(JsonPointer::parse(pointer).unwrap().resolve(&document), Some(expected), "`{}`", pointer);
+        }
+    }
+
+    #[test]
+    fn test_escaping() {
+        let pointer = JsonPointer::parse("/a~1b/m~0n/~01/~10").unwrap();
+        assert_eq!(pointer.tokens(), ["a/b", "m~n", "~1", "/0"]);
+        assert_eq!(pointer.to_string(), "/a~1b/m~0n/~01/~10");
+
+        let pointer = JsonPointer::root().child("a/b").child("~").child(0);
+        assert_eq!(pointer.to_string(), "/a~1b/~0/0");
+        assert_eq!(JsonPointer::parse(&pointer.to_string()).unwrap(), pointer);
+        assert_eq!(JsonPointer::root().to_string(), "");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(JsonPointer::parse("foo"), Err(PointerError::MissingLeadingSlash));
+        assert_eq!(JsonPointer::parse("/foo~"), Err(PointerError::InvalidEscape { position: 4 }));
+        assert_eq!(JsonPointer::parse("/foo/~2"), Err(PointerError::InvalidEscape { position: 5 }));
+        assert_eq!(PointerError::InvalidEscape { position: 5 }.to_string(),
+                   "`~` at position 5 of a JSON Pointer MUST be followed by `0` or `1`");
+        assert!(serde_json::from_value::<JsonPointer>(json!("data")).is_err());
+    }
+
+    #[test]
+    fn test_array_indices() {
+        let document = json!({"foo": ["bar", "baz"], "0": "zero"});
+        let resolve = |pointer| JsonPointer::parse(pointer).unwrap().resolve(&document).cloned();
+
+        assert_eq!(resolve("/foo/1"), Some(json!("baz")));
+        assert_eq!(resolve("/0"), Some(json!("zero")));
+        assert_eq!(resolve("/foo/2"), None);
+        assert_eq!(resolve("/foo/-"), None);
+        assert_eq!(resolve("/foo/01"), None);
+        assert_eq!(resolve("/foo/+1"), None);
+        assert_eq!(resolve("/foo/-1"), None);
+        assert_eq!(resolve("/foo/bar"), None);
+        assert_eq!(resolve("/foo/0/0"), None);
+        assert_eq!(resolve("/missing"), None);
+
+        let mut document = document.clone();
+        *JsonPointer::parse("/foo/0").unwrap().resolve_mut(&mut document).unwrap() = json!("qux");
+        assert_eq!(document["foo"], json!(["qux", "baz"]));
+        assert!(JsonPointer::parse("/foo/00").unwrap().resolve_mut(&mut document).is_none());
+    }
+
+    #[test]
+    fn test_parent() {
+        let pointer = JsonPointer::parse("/data/0/attributes").unwrap();
+        assert_eq!(pointer.parent(), Some(JsonPointer::parse("/data/0").unwrap()));
+        assert_eq!(JsonPointer::parse("/data").unwrap().parent(), Some(JsonPointer::root()));
+        assert_eq!(JsonPointer::root().parent(), None);
+    }
+
+    #[test]
+    fn test_resolve_document() {
+        let document: Document = serde_json::from_value(json!({
+            "data": [{
+                "type": "articles",
+                "id": "1",
+                "attributes": {"title": "JSON API paints my bikeshed!", "stats": {"a/b": {"~": true}}}
+            }]
+        })).unwrap();
+
+        let resolve = |pointer| JsonPointer::parse(pointer).unwrap().resolve_document(&document);
+        assert_eq!(resolve("/data/0/attributes/title"), Some(json!("JSON API paints my bikeshed!")));
+        assert_eq!(resolve("/data/0/attributes/stats/a~1b/~0"), Some(json!(true)));
+        assert_eq!(resolve("/data/0/type"), Some(json!("articles")));
+        assert_eq!(resolve("/data/1"), None);
+        assert_eq!(resolve("/included"), None);
+    }
+}
@@ -0,0 +1,339 @@
+//! http://jsonapi.org/format/#query-parameters
+//!
+//! Implementation specific query parameters **MUST** adhere to the same constraints as member
+//! names with the additional requirement that they **MUST** contain at least one non a-z
+//! character (U+0061 to U+007A). It is **RECOMMENDED** that a U+002D HYPHEN-MINUS, "-", U+005F
+//! LOW LINE, "_", or capital letter is used (e.g. camelCasing).
+//!
+//! If a server encounters a query parameter that does not follow the naming conventions above,
+//! and the server does not know how to process it as a query parameter from this specification,
+//! it **MUST** return 400 Bad Request.
+//!
+//! `QueryParams` reads the parameters of the specification, `include`, `fields`, `sort`, `page`
+//! and `filter`, and keeps implementation specific parameters as they are. A parameter family is
+//! named by the part of the parameter name before any `[`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use error::ParameterError;
+use fieldsets::Fieldsets;
+use filter::{BracketSyntax, Filter, FilterParam, FilterSyntax};
+use include::{self, IncludePaths};
+use sort::{Direction, SortSpec};
+use spec::Name;
+use uri::{encode_query_component, parse_query};
+
+/// The parameters of a query string.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryParams {
+    /// `include`, which is `None` when the parameter is absent and empty when its value is.
+    pub include: Option<IncludePaths>,
+
+    /// The `fields[TYPE]` parameters.
+    pub fields: Fieldsets,
+
+    pub sort: Option<SortSpec>,
+
+    /// The `page[NAME]` parameters by `NAME`, to be read by one of the strategies in `page`.
+    pub page: BTreeMap<String, String>,
+
+    /// The `filter` family, read with `filter::BracketSyntax`.
+    pub filter: Option<Filter>,
+
+    /// Implementation specific parameters by name, with the values of repeated parameters in order.
+    pub custom: BTreeMap<String, Vec<String>>,
+}
+
+impl QueryParams {
+    pub fn new() -> QueryParams {
+        QueryParams::default()
+    }
+
+    /// Parses a raw query string, without the leading `?`, accepting include paths of at most
+    /// `include::MAX_DEPTH` relationships.
+    ///
+    /// The error names the failing parameter, and converts into a 400 Bad Request.
+    pub fn parse(query: &str) -> Result<QueryParams, ParameterError> {
+        QueryParams::parse_with_max_include_depth(query, include::MAX_DEPTH)
+    }
+
+    /// Parses a raw query string like `parse`, rejecting include paths of more than
+    /// `max_include_depth` relationships as `IncludePaths::parse` does.
+    pub fn parse_with_max_include_depth(query: &str,
+                                        max_include_depth: usize)
+                                        -> Result<QueryParams, ParameterError> {
+        let mut params = QueryParams::new();
+        let mut filter = Vec::new();
+
+        for (name, value) in parse_query(query) {
+            let family = name.find('[').map_or(name.as_str(), |end| &name[..end]);
+
+            match family {
+                "include" => {
+                    check_unbracketed(&name)?;
+
+                    let paths = IncludePaths::parse(&value, max_include_depth)?;
+                    params.include.get_or_insert_with(IncludePaths::new).merge(paths);
+                }
+                "fields" => params.fields.add_parameter(&name, &value)?,
+                "sort" => {
+                    check_unbracketed(&name)?;
+
+                    if params.sort.is_some() {
+                        return Err(ParameterError::new(&name, "`sort` MUST NOT be given more than once"));
+                    }
+
+                    params.sort = Some(SortSpec::parse(&value)?);
+                }
+                "page" => {
+                    match name.strip_prefix("page[").and_then(|rest| rest.strip_suffix(']')) {
+                        Some(member) if !member.is_empty() && !member.contains(['[', ']']) => {
+                            params.page.insert(member.to_owned(), value);
+                        }
+                        _ => {
+                            return Err(ParameterError::new(&name,
+                                                           "pagination parameters MUST be of the form `page[NAME]`"));
+                        }
+                    }
+                }
+                "filter" => filter.push(FilterParam::new(name, value)?),
+                _ => {
+                    check_custom(&name, family)?;
+                    params.custom.entry(name).or_default().push(value);
+                }
+            }
+        }
+
+        if !filter.is_empty() {
+            params.filter = Some(BracketSyntax.parse(&filter)?);
+        }
+
+        Ok(params)
+    }
+
+    /// The `page` parameters as a query string, to be read by one of the strategies in `page`.
+    pub fn page_query(&self) -> String {
+        self.page
+            .iter()
+            .map(|(member, value)| {
+                format!("{}={}",
+                        encode_query_component(&format!("page[{}]", member)),
+                        encode_query_component(value))
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// The decoded `name=value` pairs in canonical order: `include`, `fields`, `sort`, `page`,
+    /// `filter`, then the implementation specific parameters by name.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+
+        if let Some(ref include) = self.include {
+            pairs.push(("include".to_owned(), include.paths().join(",")));
+        }
+
+        for (type_, fields) in self.fields.iter() {
+            let fields = fields.iter().map(Name::as_str).collect::<Vec<_>>().join(",");
+            pairs.push((format!("fields[{}]", type_.as_str()), fields));
+        }
+
+        if let Some(ref sort) = self.sort {
+            let fields = sort.fields()
+                .iter()
+                .map(|field| {
+                    match field.direction {
+                        Direction::Ascending => field.path(),
+                        Direction::Descending => format!("-{}", field.path()),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            pairs.push(("sort".to_owned(), fields.join(",")));
+        }
+
+        for (member, value) in &self.page {
+            pairs.push((format!("page[{}]", member), value.clone()));
+        }
+
+        if let Some(ref filter) = self.filter {
+            pairs.extend(filter.query_pairs());
+        }
+
+        for (name, values) in &self.custom {
+            pairs.extend(values.iter().map(|value| (name.clone(), value.clone())));
+        }
+
+        pairs
+    }
+}
+
+/// The canonical query string, without the leading `?`. Parsing it gives back the same
+/// `QueryParams`.
+impl fmt::Display for QueryParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, (name, value)) in self.query_pairs().iter().enumerate() {
+            if index > 0 {
+                f.write_str("&")?;
+            }
+
+            write!(f, "{}={}", encode_query_component(name), encode_query_component(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn check_unbracketed(name: &str) -> Result<(), ParameterError> {
+    if name.contains('[') {
+        let family = &name[..name.find('[').unwrap_or(name.len())];
+        return Err(ParameterError::new(name, format!("`{}` does not take a bracketed member", family)));
+    }
+
+    Ok(())
+}
+
+/// Implementation specific parameter families **MUST** be member names with at least one non a-z
+/// character. The names that are only a-z are reserved for the specification.
+fn check_custom(name: &str, family: &str) -> Result<(), ParameterError> {
+    if !family.is_empty() && family.chars().all(|c| c.is_ascii_lowercase()) {
+        let detail = format!("`{}` is not a query parameter of the specification, and implementation specific \
+                              parameters MUST contain a character other than a-z",
+                             family);
+
+        return Err(ParameterError::new(name, detail));
+    }
+
+    Name::parse(family).map_err(|error| {
+        ParameterError::new(name, format!("invalid query parameter name `{}`: {}", family, error))
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(query: &str) -> ParameterError {
+        QueryParams::parse(query).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse() {
+        let query = "include=comments.author,author&fields[articles]=title,body&sort=-created,title\
+                     &page[number]=3&page[size]=10&filter[title]=JSON&filter[created][gt]=2017\
+                     &camelCase=1&camelCase=2&debug_mode=";
+        let params = QueryParams::parse(query).unwrap();
+
+        assert_eq!(params.include.as_ref().unwrap().paths(), vec!["author", "comments.author"]);
+        assert_eq!(params.fields, Fieldsets::parse("fields[articles]=body,title").unwrap());
+        assert_eq!(params.sort, Some(SortSpec::parse("-created,title").unwrap()));
+        assert_eq!(params.page_query(), "page[number]=3&page[size]=10");
+        assert_eq!(params.filter, Some(Filter::parse("filter[title]=JSON&filter[created][gt]=2017").unwrap()));
+        assert_eq!(params.custom["camelCase"], vec!["1", "2"]);
+        assert_eq!(params.custom["debug_mode"], vec![""]);
+    }
+
+    #[test]
+    fn test_parse_empty_values() {
+        let params = QueryParams::parse("include=&sort=").unwrap();
+        assert_eq!(params.include, Some(IncludePaths::new()));
+        assert_eq!(params.sort, Some(SortSpec::new()));
+        assert_eq!(QueryParams::parse("").unwrap(), QueryParams::new());
+    }
+
+    #[test]
+    fn test_parse_max_include_depth() {
+        let path = vec!["a"; include::MAX_DEPTH].join(".");
+        let params = QueryParams::parse(&format!("include={}", path)).unwrap();
+        assert_eq!(params.include.unwrap().paths(), vec![path.clone()]);
+
+        assert_eq!(error(&format!("include={}.a", path)).parameter, "include");
+        assert_eq!(error(&format!("include={}", vec!["a"; 20_000].join("."))).parameter, "include");
+
+        let query = "include=comments.author";
+        assert!(QueryParams::parse_with_max_include_depth(query, 1).is_err());
+        assert!(QueryParams::parse_with_max_include_depth(query, 2).is_ok());
+        assert_eq!(QueryParams::parse_with_max_include_depth("include=author", 0).unwrap_err().detail,
+                   "the endpoint does not support inclusion of related resources");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let query = "debug_mode=on&filter[or][0][title]=JSON API&filter[or][1][author.name][in]=Dan,Yehuda\
+                     &page%5Bcursor%5D=a%26b&sort=-created&include=author&include=comments.author\
+                     &fields[people]=&fields[articles]=title&camelCase=2&camelCase=1";
+        let params = QueryParams::parse(query).unwrap();
+
+        let canonical = params.to_string();
+        assert!(canonical.starts_with("include=author,comments.author&fields[articles]=title&fields[people]=\
+                                       &sort=-created&page[cursor]=a%26b&filter"),
+                "{}",
+                canonical);
+        assert!(canonical.ends_with("&camelCase=2&camelCase=1&debug_mode=on"), "{}", canonical);
+        assert_eq!(QueryParams::parse(&canonical).unwrap(), params);
+        assert_eq!(QueryParams::parse(&canonical).unwrap().to_string(), canonical);
+
+        let pairs = parse_query(&canonical);
+        assert_eq!(params.query_pairs(), pairs);
+        assert!(pairs.contains(&("page[cursor]".to_owned(), "a&b".to_owned())));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = self::error("fields=title");
+        assert_eq!(error.parameter, "fields");
+
+        let error = self::error("sort=title&sort=-created");
+        assert_eq!(error.parameter, "sort");
+        assert_eq!(error.detail, "`sort` MUST NOT be given more than once");
+
+        for name in &["page", "page[]", "page[a][b]", "page[size"] {
+            let error = self::error(&format!("{}=1", name));
+            assert_eq!(error.parameter, *name);
+            assert_eq!(error.detail, "pagination parameters MUST be of the form `page[NAME]`");
+        }
+
+        assert_eq!(self::error("include=a..b").parameter, "include");
+        assert_eq!(self::error("sort=-").parameter, "sort");
+        assert_eq!(self::error("filter=x").parameter, "filter");
+    }
+
+    #[test]
+    fn test_check_unbracketed() {
+        assert!(check_unbracketed("include").is_ok());
+
+        for name in &["include[author]", "sort[]", "include[a"] {
+            let error = check_unbracketed(name).unwrap_err();
+            assert_eq!(error.parameter, *name);
+        }
+
+        assert_eq!(check_unbracketed("sort[0]").unwrap_err().detail, "`sort` does not take a bracketed member");
+        assert_eq!(self::error("include[author]=comments").parameter, "include[author]");
+    }
+
+    #[test]
+    fn test_check_custom() {
+        for name in &["camelCase", "debug_mode", "x-trace", "page2", "include2", "Include"] {
+            assert!(check_custom(name, name).is_ok(), "{}", name);
+        }
+
+        for name in &["debug", "includes", "filters"] {
+            assert!(check_custom(name, name).is_err(), "{}", name);
+        }
+
+        let error = check_custom("debug[level]", "debug").unwrap_err();
+        assert_eq!(error.parameter, "debug[level]");
+        assert!(error.detail.starts_with("`debug` is not a query parameter of the specification"));
+
+        for name in &["-debug", "debug-", "Debug_", "_X", "trace@", ""] {
+            assert!(check_custom(name, name).is_err(), "{}", name);
+        }
+
+        let error = self::error("trace%40=1");
+        assert_eq!(error.parameter, "trace@");
+        assert!(error.detail.starts_with("invalid query parameter name `trace@`: "));
+    }
+}
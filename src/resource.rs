@@ -0,0 +1,305 @@
+//! Mapping Rust types to resource objects.
+//!
+//! `JsonApiResource` is usually derived. The struct names its resource `type`, and each field is
+//! the resource's `id`, a relationship, an attribute, or skipped:
+//!
+//! ```
+//! # extern crate jsonapi;
+//! # use jsonapi::JsonApiResource;
+//! # use jsonapi::spec::ResourceIdentifierObject;
+//! #[derive(JsonApiResource)]
+//! #[jsonapi(type = "articles")]
+//! struct Article {
+//!     #[jsonapi(id)]
+//!     id: u64,
+//!     title: String,
+//!     #[jsonapi(rename = "reading-time")]
+//!     reading_time: Option<u32>,
+//!     #[jsonapi(relationship)]
+//!     author: ResourceIdentifierObject,
+//!     #[jsonapi(relationship)]
+//!     comments: Vec<ResourceIdentifierObject>,
+//!     #[jsonapi(skip)]
+//!     cached: bool,
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! The type, attribute and relationship names are checked against the member name rules when the
+//! derive is expanded, so a struct that would produce an invalid resource object does not compile.
+//! An attribute can't use a reserved name:
+//!
+//! ```compile_fail
+//! # extern crate jsonapi;
+//! # use jsonapi::JsonApiResource;
+//! #[derive(JsonApiResource)]
+//! #[jsonapi(type = "articles")]
+//! struct Article {
+//!     #[jsonapi(rename = "links")]
+//!     urls: Vec<String>,
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Fields can't share a name with each other, `type` or `id`:
+//!
+//! ```compile_fail
+//! # extern crate jsonapi;
+//! # use jsonapi::JsonApiResource;
+//! # use jsonapi::spec::ResourceIdentifierObject;
+//! #[derive(JsonApiResource)]
+//! #[jsonapi(type = "articles")]
+//! struct Article {
+//!     author: String,
+//!     #[jsonapi(relationship, rename = "author")]
+//!     writer: ResourceIdentifierObject,
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! ```compile_fail
+//! # extern crate jsonapi;
+//! # use jsonapi::JsonApiResource;
+//! #[derive(JsonApiResource)]
+//! #[jsonapi(type = "articles")]
+//! struct Article {
+//!     #[jsonapi(rename = "type")]
+//!     kind: String,
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! And every name must be a valid member name:
+//!
+//! ```compile_fail
+//! # extern crate jsonapi;
+//! # use jsonapi::JsonApiResource;
+//! #[derive(JsonApiResource)]
+//! #[jsonapi(type = "-articles")]
+//! struct Article {
+//!     title: String,
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! ```compile_fail
+//! # extern crate jsonapi;
+//! # use jsonapi::JsonApiResource;
+//! #[derive(JsonApiResource)]
+//! #[jsonapi(type = "articles")]
+//! struct Article {
+//!     #[jsonapi(rename = "author.name")]
+//!     author_name: String,
+//! }
+//! # fn main() {}
+//! ```
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json::{self, Value};
+
+use spec::{Id, Name, Object, Relationship, ResourceIdentifierObject, ResourceLinkage, ResourceObject};
+
+/// A Rust type that is represented as a resource object of type `TYPE`.
+pub trait JsonApiResource: Sized {
+    /// The `type` member of the resource objects.
+    const TYPE: &'static str;
+
+    fn to_resource_object(&self) -> Result<ResourceObject, ResourceError>;
+
+    fn from_resource_object(resource: ResourceObject) -> Result<Self, ResourceError>;
+}
+
+/// A field that holds the resource linkage of a relationship.
+pub trait RelationshipField: Sized {
+    fn to_linkage(&self) -> ResourceLinkage;
+
+    /// Builds the field from the linkage of a relationship, or from `None` when the resource object
+    /// has no linkage for it.
+    fn from_linkage(linkage: Option<ResourceLinkage>) -> Result<Self, ResourceError>;
+}
+
+/// A non-empty to-one relationship.
+impl RelationshipField for ResourceIdentifierObject {
+    fn to_linkage(&self) -> ResourceLinkage {
+        ResourceLinkage::Single(self.clone())
+    }
+
+    fn from_linkage(linkage: Option<ResourceLinkage>) -> Result<Self, ResourceError> {
+        match linkage {
+            Some(ResourceLinkage::Single(identifier)) => Ok(identifier),
+            _ => Err(ResourceError::Linkage { expected: "a resource identifier object" }),
+        }
+    }
+}
+
+/// A to-one relationship, which is `null` when empty.
+impl RelationshipField for Option<ResourceIdentifierObject> {
+    fn to_linkage(&self) -> ResourceLinkage {
+        match *self {
+            Some(ref identifier) => ResourceLinkage::Single(identifier.clone()),
+            None => ResourceLinkage::Null,
+        }
+    }
+
+    fn from_linkage(linkage: Option<ResourceLinkage>) -> Result<Self, ResourceError> {
+        match linkage {
+            Some(ResourceLinkage::Single(identifier)) => Ok(Some(identifier)),
+            Some(ResourceLinkage::Null) | None => Ok(None),
+            Some(ResourceLinkage::Array(_)) => {
+                Err(ResourceError::Linkage { expected: "null or a resource identifier object" })
+            }
+        }
+    }
+}
+
+/// A to-many relationship.
+impl RelationshipField for Vec<ResourceIdentifierObject> {
+    fn to_linkage(&self) -> ResourceLinkage {
+        ResourceLinkage::Array(self.clone())
+    }
+
+    fn from_linkage(linkage: Option<ResourceLinkage>) -> Result<Self, ResourceError> {
+        match linkage {
+            Some(ResourceLinkage::Array(identifiers)) => Ok(identifiers),
+            None => Ok(Vec::new()),
+            _ => Err(ResourceError::Linkage { expected: "an array of resource identifier objects" }),
+        }
+    }
+}
+
+/// Why a resource object could not be converted to or from a Rust type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResourceError {
+    /// The resource object's `type` is not the one the Rust type is mapped to.
+    WrongType {
+        expected: &'static str,
+        found: String,
+    },
+
+    /// The Rust type has an id field, but the resource object has no `id`.
+    MissingId,
+
+    /// The resource object's `id` could not be parsed into the id field.
+    InvalidId {
+        id: String,
+    },
+
+    /// An attribute value could not be converted to or from its field.
+    Attribute {
+        name: String,
+        message: String,
+    },
+
+    /// A relationship's linkage does not have the shape its field expects.
+    Relationship {
+        name: String,
+        expected: &'static str,
+    },
+
+    /// The shape a `RelationshipField` expected, before the relationship's name is known.
+    Linkage {
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResourceError::WrongType { expected, ref found } => {
+                write!(f, "expected a resource object of type `{}`, found `{}`", expected, found)
+            }
+            ResourceError::MissingId => write!(f, "the resource object has no `id`"),
+            ResourceError::InvalidId { ref id } => write!(f, "invalid resource object id `{}`", id),
+            ResourceError::Attribute { ref name, ref message } => {
+                write!(f, "invalid attribute `{}`: {}", name, message)
+            }
+            ResourceError::Relationship { ref name, expected } => {
+                write!(f, "the linkage of relationship `{}` MUST be {}", name, expected)
+            }
+            ResourceError::Linkage { expected } => write!(f, "resource linkage MUST be {}", expected),
+        }
+    }
+}
+
+impl error::Error for ResourceError {}
+
+/// Helpers called by the code `#[derive(JsonApiResource)]` expands to.
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    pub use spec::{Attributes, Relationships, ResourceObject, Type};
+
+    /// Builds a name the derive has already checked.
+    pub fn name(name: &str) -> Name {
+        Name::parse(name).expect("member name checked by #[derive(JsonApiResource)]")
+    }
+
+    pub fn check_type(resource: &ResourceObject, expected: &'static str) -> Result<(), ResourceError> {
+        if resource.type_.as_str() == expected {
+            Ok(())
+        } else {
+            Err(ResourceError::WrongType { expected, found: resource.type_.as_str().to_owned() })
+        }
+    }
+
+    pub fn to_id<T: ToString>(id: &T) -> Id {
+        Id(id.to_string())
+    }
+
+    pub fn from_id<T: FromStr>(id: Option<&Id>) -> Result<T, ResourceError> {
+        let id = id.ok_or(ResourceError::MissingId)?;
+        id.as_str().parse().map_err(|_| ResourceError::InvalidId { id: id.as_str().to_owned() })
+    }
+
+    pub fn insert_attribute<T: Serialize>(attributes: &mut Object,
+                                          name: &str,
+                                          value: &T)
+                                          -> Result<(), ResourceError> {
+        let value = serde_json::to_value(value).map_err(|error| attribute_error(name, &error))?;
+        attributes.insert(name.to_owned(), value);
+        Ok(())
+    }
+
+    /// Takes an attribute out of `attributes`. A missing attribute is read as `null`, so it can
+    /// fill an `Option` field.
+    pub fn take_attribute<T: DeserializeOwned>(attributes: &mut Object, name: &str) -> Result<T, ResourceError> {
+        let value = attributes.remove(name).unwrap_or(Value::Null);
+        serde_json::from_value(value).map_err(|error| attribute_error(name, &error))
+    }
+
+    pub fn insert_relationship<T: RelationshipField>(relationships: &mut BTreeMap<Name, Relationship>,
+                                                     name: &str,
+                                                     value: &T) {
+        relationships.insert(self::name(name), Relationship {
+            links: None,
+            data: Some(value.to_linkage()),
+            meta: None,
+        });
+    }
+
+    pub fn take_relationship<T: RelationshipField>(relationships: &mut BTreeMap<Name, Relationship>,
+                                                   name: &str)
+                                                   -> Result<T, ResourceError> {
+        let linkage = relationships.remove(name).and_then(|relationship| relationship.data);
+
+        T::from_linkage(linkage).map_err(|error| {
+            match error {
+                ResourceError::Linkage { expected } => {
+                    ResourceError::Relationship { name: name.to_owned(), expected }
+                }
+                error => error,
+            }
+        })
+    }
+
+    fn attribute_error(name: &str, error: &serde_json::Error) -> ResourceError {
+        ResourceError::Attribute { name: name.to_owned(), message: error.to_string() }
+    }
+}
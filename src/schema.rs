@@ -1,4 +1,16 @@
-struct Object(BTreeMap<String, serde_json::Value>);
+use std::collections::BTreeMap;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde_json;
+
+use de::deserialize_some;
+use error::{check_attribute, check_fields, ValidationError};
+pub use pointer::JsonPointer;
+pub use uri::Uri;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Object(pub BTreeMap<String, serde_json::Value>);
+
 
 
 ///   "$schema": "http://json-schema.org/draft-04/schema#",
@@ -15,7 +27,10 @@ struct Object(BTreeMap<String, serde_json::Value>);
 ///       "$ref": "#/definitions/info"
 ///     }
 ///   ],
-enum Document {
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum Document {
     Success(Success),
     Failure(Failure),
     Info(Info),
@@ -59,12 +74,28 @@ enum Document {
 ///       },
 ///       "additionalProperties": false
 ///     },
-struct Success {
-    data: Data,
-    included: Option<Vec<Included>>,
-    meta: Option<Meta>,
-    links: Option<LinksAndPagination>,
-    jsonapi: Option<JsonAPI>,
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Success {
+    pub data: Data,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub included: Option<Vec<Resource>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<LinksAndPagination>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonAPI>,
+}
+
+/// The `allOf` of `#/definitions/links` and `#/definitions/pagination` used for the top-level links
+/// of a `success` document.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinksAndPagination {
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    #[serde(flatten)]
+    pub links: Links,
 }
 
 
@@ -90,10 +121,14 @@ struct Success {
 ///       },
 ///       "additionalProperties": false
 ///     },
-struct Failure {
-    errors: Vec<Error>,
-    meta: Option<Meta>,
-    jsonapi: Option<JsonAPI>,
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Failure {
+    pub errors: Vec<Error>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonAPI>,
 }
 
 
@@ -115,10 +150,14 @@ struct Failure {
 ///       },
 ///       "additionalProperties": false
 ///     },
-struct Info {
-    meta: Meta,
-    links: Option<Links>,
-    jsonapi: Option<JsonAPI>,
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Info {
+    pub meta: Meta,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonAPI>,
 }
 
 
@@ -127,7 +166,8 @@ struct Info {
 ///       "type": "object",
 ///       "additionalProperties": true
 ///     },
-struct Meta(Object);
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Meta(pub Object);
 
 
 ///     "data": {
@@ -146,7 +186,10 @@ struct Meta(Object);
 ///         }
 ///       ]
 ///     },
-enum Data {
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum Data {
     Single(Resource),
     Array(Vec<Resource>),
 }
@@ -181,13 +224,66 @@ enum Data {
 ///       },
 ///       "additionalProperties": false
 ///     },
-struct Resource {
-    type_: String,
-    id: String,
-    attributes: Option<Attributes>,
-    relationships: Option<Relationships>,
-    links: Option<Links>,
-    meta: Option<Meta>,
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Resource {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Attributes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relationships: Option<Relationships>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl Resource {
+    /// Checks that no attribute or relationship is named `type` or `id`, and that no attribute and
+    /// relationship share a name.
+    pub fn check_fields(&self) -> Result<(), ValidationError> {
+        let attributes = self.attributes
+            .iter()
+            .flat_map(|attributes| attributes.attributes.0.keys().map(String::as_str));
+        let relationships = self.relationships
+            .iter()
+            .flat_map(|relationships| relationships.relationships.keys().map(String::as_str));
+
+        check_fields(attributes, relationships)
+    }
+}
+
+impl<'de> Deserialize<'de> for Resource {
+    fn deserialize<D>(deserializer: D) -> Result<Resource, D::Error>
+        where D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RawResource {
+            #[serde(rename = "type")]
+            type_: String,
+            id: String,
+            attributes: Option<Attributes>,
+            relationships: Option<Relationships>,
+            links: Option<Links>,
+            meta: Option<Meta>,
+        }
+
+        let raw = RawResource::deserialize(deserializer)?;
+        let resource = Resource {
+            type_: raw.type_,
+            id: raw.id,
+            attributes: raw.attributes,
+            relationships: raw.relationships,
+            links: raw.links,
+            meta: raw.meta,
+        };
+
+        resource.check_fields().map_err(de::Error::custom)?;
+
+        Ok(resource)
+    }
 }
 
 
@@ -206,10 +302,14 @@ struct Resource {
 ///       },
 ///       "additionalProperties": true
 ///     },
-struct Links {
-    self_: Option<Uri>,
-    related: Option<Link>,
-    links: Option<Object>,
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Links {
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    pub self_: Option<Uri>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related: Option<Link>,
+    #[serde(flatten)]
+    pub links: Object,
 }
 
 
@@ -239,14 +339,42 @@ struct Links {
 ///         }
 ///       ]
 ///     },
-enum Link {
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Link {
     Url(Uri),
     Object {
         href: Uri,
+        #[serde(skip_serializing_if = "Option::is_none")]
         meta: Option<Meta>,
     },
 }
 
+impl<'de> Deserialize<'de> for Link {
+    fn deserialize<D>(deserializer: D) -> Result<Link, D::Error>
+        where D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawLink {
+            href: Uri,
+            meta: Option<Meta>,
+        }
+
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(href) => Uri::parse(&href).map(Link::Url).map_err(de::Error::custom),
+            value @ serde_json::Value::Object(_) => {
+                let link = serde_json::from_value::<RawLink>(value).map_err(de::Error::custom)?;
+
+                Ok(Link::Object {
+                    href: link.href,
+                    meta: link.meta,
+                })
+            }
+            _ => Err(de::Error::custom("a link MUST be either a string or a link object")),
+        }
+    }
+}
+
 
 ///     "attributes": {
 ///       "description": "Members of the attributes object (\"attributes\") represent information about the resource object in which it's defined.",
@@ -258,21 +386,42 @@ enum Link {
 ///       },
 ///       "additionalProperties": false
 ///     },
-struct Attributes {
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Attributes {
     attributes: Object,
 }
 
 impl Attributes {
-    fn new(attributes: Object) -> Attributes {
-        for (key, _) in attributes.iter() {
-            if not regex.search("^(?!relationships$|links$)\\w[-\\w_]*$", &key) {
-                panic!("invalid attribute name");
+    pub fn new(attributes: Object) -> Result<Attributes, ValidationError> {
+        for (key, value) in &attributes.0 {
+            // `^(?!relationships$|links$)\w[-\w_]*$`
+            check_attribute(key, value)?;
+
+            if !is_member_name(key) {
+                return Err(ValidationError::InvalidName { name: key.clone() });
             }
         }
 
-        Attributes {
-            attributes: attributes,
-        }
+        Ok(Attributes {
+            attributes,
+        })
+    }
+
+    pub fn attributes(&self) -> &Object {
+        &self.attributes
+    }
+
+    pub fn into_attributes(self) -> Object {
+        self.attributes
+    }
+}
+
+impl<'de> Deserialize<'de> for Attributes {
+    fn deserialize<D>(deserializer: D) -> Result<Attributes, D::Error>
+        where D: Deserializer<'de>,
+    {
+        Attributes::new(Object::deserialize(deserializer)?).map_err(de::Error::custom)
     }
 }
 
@@ -331,36 +480,97 @@ impl Attributes {
 ///       "properties": {},
 ///       "additionalProperties": false
 ///     },
-struct Relationships {
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Relationships {
     relationships: BTreeMap<String, Relationship>,
 }
 
 impl Relationships {
-    fn new(relationships: BTreeMap<String, Relationships>) -> Relationships {
-        for (key, _) in relationships.iter() {
-            if not regex.search("^\\w[-\\w_]*$", &key) {
-                panic!("invalid relationship name");
+    pub fn new(relationships: BTreeMap<String, Relationship>) -> Result<Relationships, ValidationError> {
+        for key in relationships.keys() {
+            // `^\w[-\w_]*$`
+            if !is_member_name(key) {
+                return Err(ValidationError::InvalidName { name: key.clone() });
             }
         }
 
-        Relationships {
-            relationships: relationships,
-        }
+        Ok(Relationships {
+            relationships,
+        })
+    }
+
+    pub fn relationships(&self) -> &BTreeMap<String, Relationship> {
+        &self.relationships
+    }
+
+    pub fn into_relationships(self) -> BTreeMap<String, Relationship> {
+        self.relationships
     }
 }
 
-struct Relationship {
-    links: Option<Links>,
-    data: RelationshipData,
-    meta: Option<Meta>,
+impl<'de> Deserialize<'de> for Relationships {
+    fn deserialize<D>(deserializer: D) -> Result<Relationships, D::Error>
+        where D: Deserializer<'de>,
+    {
+        Relationships::new(BTreeMap::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
 }
 
-enum RelationshipData {
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Relationship {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(default, deserialize_with = "deserialize_some", skip_serializing_if = "Option::is_none")]
+    pub data: Option<RelationshipData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// Either a `relationshipToOne` or a `relationshipToMany`. An `empty` relationship is accepted as
+/// either `null` or `{}`, and is always written as `null`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum RelationshipData {
     Empty,
     Single(Linkage),
     Array(Vec<Linkage>),
 }
 
+impl<'de> Deserialize<'de> for RelationshipData {
+    fn deserialize<D>(deserializer: D) -> Result<RelationshipData, D::Error>
+        where D: Deserializer<'de>,
+    {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::Null => Ok(RelationshipData::Empty),
+            serde_json::Value::Object(ref object) if object.is_empty() => Ok(RelationshipData::Empty),
+            value @ serde_json::Value::Object(_) => {
+                serde_json::from_value(value).map(RelationshipData::Single).map_err(de::Error::custom)
+            }
+            value @ serde_json::Value::Array(_) => {
+                serde_json::from_value(value).map(RelationshipData::Array).map_err(de::Error::custom)
+            }
+            _ => Err(de::Error::custom("expected null, an object or an array of linkage")),
+        }
+    }
+}
+
+/// Matches the `\w[-\w_]*` member name pattern used by `attributes` and `relationships`.
+fn is_member_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if is_word_char(c) => chars.all(|c| c == '-' || is_word_char(c)),
+        _ => false,
+    }
+}
+
+/// `\w` in an ECMA 262 regular expression.
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
 
 ///     "linkage": {
 ///       "description": "The \"type\" and \"id\" to non-empty members.",
@@ -379,9 +589,12 @@ enum RelationshipData {
 ///       },
 ///       "additionalProperties": false
 ///     },
-struct Linkage {
-    type_: String,
-    id: String,
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Linkage {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub id: String,
 }
 
 
@@ -418,11 +631,16 @@ struct Linkage {
 ///         }
 ///       }
 ///     },
-struct Pagination {
-    first: Option<Uri>,
-    last: Option<Uri>,
-    prev: Option<Uri>,
-    next: Option<Uri>,
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pagination {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<Uri>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last: Option<Uri>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<Uri>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<Uri>,
 }
 
 
@@ -439,9 +657,13 @@ struct Pagination {
 ///       },
 ///       "additionalProperties": false
 ///     },
-struct JsonAPI {
-    version: Option<String>,
-    meta: Option<Meta>,
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonAPI {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 
@@ -490,18 +712,108 @@ struct JsonAPI {
 ///       },
 ///       "additionalProperties": false
 ///     }
-struct Error {
-    id: Option<String>,
-    links: Option<Links>,
-    status: Option<String>,
-    code: Option<String>,
-    title: Option<String>,
-    detail: Option<String>,
-    source: Option<Source>,
-    meta: Option<Meta>,
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Error {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl Error {
+    /// A `400 Bad Request` error whose `source.parameter` names the query parameter that caused it.
+    pub fn bad_parameter<S: Into<String>>(parameter: &str, detail: S) -> Error {
+        Error {
+            status: Some("400".to_owned()),
+            title: Some("Invalid query parameter".to_owned()),
+            detail: Some(detail.into()),
+            source: Some(Source {
+                pointer: None,
+                parameter: Some(parameter.to_owned()),
+                header: None,
+            }),
+            ..Error::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<JsonPointer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
 }
 
-struct Source {
-    pointer: Option<JsonPointer>,
-    parameter: Option<String>,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: serde_json::Value) -> Object {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn relationships(value: serde_json::Value) -> BTreeMap<String, Relationship> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_attributes_new() {
+        let attributes = Attributes::new(object(json!({"title": "JSON API", "word_count": 2, "a-b": null}))).unwrap();
+        assert_eq!(attributes.attributes().0.len(), 3);
+
+        for name in &["relationships", "links"] {
+            assert_eq!(Attributes::new(object(json!({*name: {}}))),
+                       Err(ValidationError::ReservedMember { name: name.to_string() }));
+        }
+
+        for name in &["", "-title", "first name", "title!", "author.name"] {
+            assert_eq!(Attributes::new(object(json!({*name: 1}))),
+                       Err(ValidationError::InvalidName { name: name.to_string() }));
+        }
+
+        assert_eq!(Attributes::new(object(json!({"address": {"links": {"self": "http://example.com"}}}))),
+                   Err(ValidationError::ReservedMember { name: "links".to_owned() }));
+        assert_eq!(Attributes::new(object(json!({"tags": [{"relationships": []}]}))),
+                   Err(ValidationError::ReservedMember { name: "relationships".to_owned() }));
+    }
+
+    #[test]
+    fn test_relationships_new() {
+        let data = json!({"data": {"type": "people", "id": "9"}});
+        let valid = Relationships::new(relationships(json!({"author": data, "co_author": data, "_editor": data})))
+            .unwrap();
+        assert_eq!(valid.relationships().len(), 3);
+
+        for name in &["", "-author", "author name", "author.name", "author[0]"] {
+            assert_eq!(Relationships::new(relationships(json!({*name: data}))),
+                       Err(ValidationError::InvalidName { name: name.to_string() }));
+        }
+    }
+
+    #[test]
+    fn test_deserialize_uses_constructors() {
+        let error = serde_json::from_value::<Attributes>(json!({"links": {}})).unwrap_err();
+        assert_eq!(error.to_string(),
+                   "`links` is reserved and MUST NOT be used as an attribute name or within an attribute");
+
+        let error = serde_json::from_value::<Relationships>(json!({"-author": {"meta": {}}})).unwrap_err();
+        assert_eq!(error.to_string(), "invalid member name `-author`");
+    }
 }
@@ -0,0 +1,416 @@
+//! http://jsonapi.org/format/#fetching-sorting
+//!
+//! An endpoint **MAY** support requests to sort the primary data with a `sort` query parameter.
+//! The value for `sort` **MUST** represent sort fields.
+//!
+//! An endpoint **MAY** support multiple sort fields by allowing comma-separated (U+002C COMMA,
+//! ",") sort fields. Sort fields **SHOULD** be applied in the order specified.
+//!
+//! The sort order for each sort field **MUST** be ascending unless it is prefixed with a minus
+//! (U+002D HYPHEN-MINUS, "-"), in which case it **MUST** be descending.
+//!
+//! It is recommended that dot-separated (U+002E FULL-STOP, ".") sort fields be used to request
+//! sorting based upon relationship attributes. For example, a sort field of `author.name` could be
+//! used to request that the primary data be sorted based upon the `name` attribute of the `author`
+//! relationship.
+//!
+//! If the server does not support sorting as specified in the query parameter `sort`, it **MUST**
+//! return 400 Bad Request.
+//!
+//! ```text
+//! GET /articles?sort=-created,title
+//! ```
+
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+
+use serde_json::Value;
+
+use error::ParameterError;
+use schema;
+use spec::{Name, NameError, ResourceIndex, ResourceObject};
+use uri::encode_query_component;
+
+/// The sort fields of a `sort` parameter, in the order they are applied.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SortSpec(Vec<SortField>);
+
+/// A sort field, such as `-created` or `author.name`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortField {
+    /// The relationships to follow, if any, then the attribute to sort by. `id` sorts by the
+    /// resource's `id`. An empty path sorts every resource as equal.
+    pub path: Vec<Name>,
+    pub direction: Direction,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortSpec {
+    pub fn new() -> SortSpec {
+        SortSpec::default()
+    }
+
+    /// Parses the decoded value of a `sort` parameter. An empty value sorts by nothing, and a field
+    /// given again after its first occurrence is ignored.
+    pub fn parse(value: &str) -> Result<SortSpec, SortError> {
+        let mut spec = SortSpec::new();
+
+        if value.is_empty() {
+            return Ok(spec);
+        }
+
+        for field in value.split(',') {
+            let (direction, path) = match field.strip_prefix('-') {
+                Some(path) => (Direction::Descending, path),
+                None => (Direction::Ascending, field),
+            };
+
+            let path = path.split('.')
+                .map(Name::parse)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| SortError::InvalidField { field: field.to_owned(), error })?;
+
+            if spec.0.iter().all(|field| field.path != path) {
+                spec.0.push(SortField { path, direction });
+            }
+        }
+
+        Ok(spec)
+    }
+
+    pub fn push(&mut self, field: SortField) {
+        self.0.push(field);
+    }
+
+    pub fn fields(&self) -> &[SortField] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks that every sort field is one of the dot-separated `supported` fields.
+    pub fn check<'a, I>(&self, supported: I) -> Result<(), SortError>
+        where I: IntoIterator<Item = &'a str>,
+    {
+        let supported = supported.into_iter().collect::<Vec<_>>();
+
+        for field in &self.0 {
+            let path = field.path();
+
+            if !supported.contains(&path.as_str()) {
+                return Err(SortError::UnknownField { field: path });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sorts `resources` by the sort fields, keeping the order of resources that compare equal.
+    ///
+    /// Dot-separated fields follow to-one relationships to the resource objects in `resources` and
+    /// `included`. A field that a resource doesn't have, including one behind an empty or to-many
+    /// relationship, sorts as `null`. Values are compared with `compare`.
+    pub fn sort(&self, resources: &mut Vec<ResourceObject>, included: &[ResourceObject]) {
+        if self.0.is_empty() {
+            return;
+        }
+
+        let keys = {
+            let index = ResourceIndex::new(resources, included);
+
+            resources.iter()
+                .map(|resource| self.0.iter().map(|field| index.field(resource, &field.path)).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
+        let mut keyed = keys.into_iter().zip(resources.drain(..)).collect::<Vec<_>>();
+
+        keyed.sort_by(|(a, _), (b, _)| {
+            self.0
+                .iter()
+                .zip(a.iter().zip(b))
+                .map(|(field, (a, b))| {
+                    match field.direction {
+                        Direction::Ascending => compare(a, b),
+                        Direction::Descending => compare(b, a),
+                    }
+                })
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        resources.extend(keyed.into_iter().map(|(_, resource)| resource));
+    }
+}
+
+impl SortField {
+    /// The dot-separated form of `path`, without the direction.
+    pub fn path(&self) -> String {
+        self.path.iter().map(Name::as_str).collect::<Vec<_>>().join(".")
+    }
+}
+
+/// The value of the parameter, such as `-created,title`, encoded for use in a query string.
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, field) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+
+            if field.direction == Direction::Descending {
+                f.write_str("-")?;
+            }
+
+            f.write_str(&encode_query_component(&field.path()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Orders JSON values: `null`, then booleans, numbers, strings, arrays and objects. Numbers are
+/// compared by value, strings by code point, and arrays and objects element by element, with
+/// object members taken in key order.
+pub fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => {
+            let integer = |number: &serde_json::Number| {
+                number.as_i64().map(i128::from).or_else(|| number.as_u64().map(i128::from))
+            };
+
+            match (integer(a), integer(b)) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => {
+                    let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+                    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+                }
+            }
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| compare(a, b))
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            let mut a = a.iter().collect::<Vec<_>>();
+            let mut b = b.iter().collect::<Vec<_>>();
+            a.sort_by(|a, b| a.0.cmp(b.0));
+            b.sort_by(|a, b| a.0.cmp(b.0));
+
+            a.iter()
+                .zip(&b)
+                .map(|(a, b)| a.0.cmp(b.0).then_with(|| compare(a.1, b.1)))
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn rank(value: &Value) -> u8 {
+    match *value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+/// Why a `sort` parameter could not be used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SortError {
+    /// A sort field with a name that is not a valid member name.
+    InvalidField {
+        field: String,
+        error: NameError,
+    },
+
+    /// A sort field the endpoint does not support.
+    UnknownField {
+        field: String,
+    },
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SortError::InvalidField { ref field, ref error } => write!(f, "invalid sort field `{}`: {}", field, error),
+            SortError::UnknownField { ref field } => write!(f, "sorting by `{}` is not supported", field),
+        }
+    }
+}
+
+impl error::Error for SortError {}
+
+/// An error naming the `sort` parameter.
+impl From<SortError> for ParameterError {
+    fn from(error: SortError) -> ParameterError {
+        ParameterError::new("sort", error.to_string())
+    }
+}
+
+/// A 400 Bad Request whose `source.parameter` is `sort`.
+impl From<SortError> for schema::Error {
+    fn from(error: SortError) -> schema::Error {
+        ParameterError::from(error).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{self, json};
+
+    fn fields(spec: &SortSpec) -> Vec<(String, Direction)> {
+        spec.fields().iter().map(|field| (field.path(), field.direction)).collect()
+    }
+
+    fn resources(value: Value) -> Vec<ResourceObject> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn ids(resources: &[ResourceObject]) -> Vec<&str> {
+        resources.iter().map(|resource| resource.id.as_ref().unwrap().as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let spec = SortSpec::parse("-created,title,author.name,-created").unwrap();
+        assert_eq!(fields(&spec), vec![
+            ("created".to_owned(), Direction::Descending),
+            ("title".to_owned(), Direction::Ascending),
+            ("author.name".to_owned(), Direction::Ascending),
+        ]);
+        assert_eq!(spec.fields()[2].path.len(), 2);
+        assert!(SortSpec::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_fields() {
+        for value in &["title,", "--title", "author..name", "-", "title,-"] {
+            match SortSpec::parse(value) {
+                Err(SortError::InvalidField { .. }) => {}
+                result => panic!("`{}` parsed as {:?}", value, result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let spec = SortSpec::parse("-created,author.name,café").unwrap();
+        assert_eq!(spec.to_string(), "-created,author.name,caf%C3%A9");
+        assert_eq!(SortSpec::new().to_string(), "");
+    }
+
+    #[test]
+    fn test_check() {
+        let spec = SortSpec::parse("-created,author.name").unwrap();
+        assert_eq!(spec.check(vec!["created", "title", "author.name"]), Ok(()));
+        assert_eq!(spec.check(vec!["created", "author"]),
+                   Err(SortError::UnknownField { field: "author.name".to_owned() }));
+        assert_eq!(SortSpec::parse("title").unwrap().check(vec!["-title"]),
+                   Err(SortError::UnknownField { field: "title".to_owned() }));
+
+        let error = schema::Error::from(spec.check(Vec::new()).unwrap_err());
+        assert_eq!(error.status.as_deref(), Some("400"));
+        assert_eq!(error.detail.as_deref(), Some("sorting by `created` is not supported"));
+        assert_eq!(error.source.unwrap().parameter.as_deref(), Some("sort"));
+    }
+
+    #[test]
+    fn test_sort_stable_multi_key() {
+        let mut articles = resources(json!([
+            {"type": "articles", "id": "1", "attributes": {"year": 2015, "title": "B"}},
+            {"type": "articles", "id": "2", "attributes": {"year": 2016, "title": "A"}},
+            {"type": "articles", "id": "3", "attributes": {"year": 2015, "title": "A"}},
+            {"type": "articles", "id": "4", "attributes": {"title": "A"}},
+            {"type": "articles", "id": "5", "attributes": {"year": 2015, "title": "B"}},
+            {"type": "articles", "id": "6", "attributes": {"year": 2015, "title": "A"}}
+        ]));
+
+        SortSpec::parse("-year,title").unwrap().sort(&mut articles, &[]);
+        assert_eq!(ids(&articles), vec!["2", "3", "6", "1", "5", "4"]);
+
+        SortSpec::parse("title").unwrap().sort(&mut articles, &[]);
+        assert_eq!(ids(&articles), vec!["2", "3", "6", "4", "1", "5"]);
+
+        SortSpec::parse("-id").unwrap().sort(&mut articles, &[]);
+        assert_eq!(ids(&articles), vec!["6", "5", "4", "3", "2", "1"]);
+
+        SortSpec::new().sort(&mut articles, &[]);
+        assert_eq!(ids(&articles), vec!["6", "5", "4", "3", "2", "1"]);
+
+        let mut spec = SortSpec::new();
+        spec.push(SortField { path: Vec::new(), direction: Direction::Descending });
+        spec.sort(&mut articles, &[]);
+        assert_eq!(ids(&articles), vec!["6", "5", "4", "3", "2", "1"]);
+    }
+
+    #[test]
+    fn test_sort_by_relationship_field() {
+        let mut articles = resources(json!([
+            {"type": "articles", "id": "1", "relationships": {"author": {"data": {"type": "people", "id": "9"}}}},
+            {"type": "articles", "id": "2", "relationships": {"author": {"data": null}}},
+            {"type": "articles", "id": "3", "relationships": {"author": {"data": {"type": "people", "id": "2"}}}},
+            {"type": "articles", "id": "4", "relationships": {"author": {"data": {"type": "people", "id": "404"}}}},
+            {"type": "articles", "id": "5", "relationships": {"author": {"data": [{"type": "people", "id": "2"}]}}},
+            {"type": "articles", "id": "6"}
+        ]));
+        let included = resources(json!([
+            {"type": "people", "id": "9", "attributes": {"name": "Dan"}},
+            {"type": "people", "id": "2", "attributes": {"name": "Yehuda"}}
+        ]));
+
+        SortSpec::parse("-author.name").unwrap().sort(&mut articles, &included);
+        assert_eq!(ids(&articles), vec!["3", "1", "2", "4", "5", "6"]);
+
+        SortSpec::parse("author.id").unwrap().sort(&mut articles, &included);
+        assert_eq!(ids(&articles), vec!["2", "4", "5", "6", "3", "1"]);
+    }
+
+    #[test]
+    fn test_compare() {
+        let ordered = vec![
+            json!(null),
+            json!(false),
+            json!(true),
+            json!(-1),
+            json!(1.5),
+            json!(2),
+            json!(u64::MAX),
+            json!(""),
+            json!("B"),
+            json!("a"),
+            json!([]),
+            json!([1]),
+            json!([1, 2]),
+            json!([2]),
+            json!({}),
+            json!({"a": 1}),
+            json!({"a": 2}),
+            json!({"b": 0}),
+        ];
+
+        for (i, a) in ordered.iter().enumerate() {
+            for (j, b) in ordered.iter().enumerate() {
+                assert_eq!(compare(a, b), i.cmp(&j), "comparing {} with {}", a, b);
+            }
+        }
+    }
+}
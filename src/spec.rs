@@ -1,4 +1,27 @@
-type Object = serde_json::Value;
+use std::any::Any;
+use std::borrow::{Borrow, Cow};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error;
+use std::fmt;
+use std::slice;
+use std::vec;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer};
+use serde::ser::{self, Serialize, Serializer};
+use serde_json;
+
+use de::deserialize_some;
+use error::{check_attribute, check_fields, ValidationError};
+use jsonapi_name::check_name;
+pub use jsonapi_name::NameError;
+use fieldsets::Fieldsets;
+use filter::Filter;
+use sort::SortSpec;
+pub use pointer::JsonPointer;
+pub use uri::Uri;
+
+pub type Object = serde_json::Map<String, serde_json::Value>;
 
 /// http://jsonapi.org/format/#document-top-level
 ///
@@ -22,22 +45,1055 @@ type Object = serde_json::Value;
 ///
 /// If a document does not contain a top-level data key, the included member **MUST** NOT be present
 /// either.
-enum Document {
+///
+/// A document with only `meta` is valid, but this model has no variant for it, so reading one
+/// fails. `schema::Document::Info` represents it.
+///
+/// A document is serialized for the `Version` its `jsonapi` object declares. Members added in a
+/// later version than the declared one are left out, as `limit_to_version` does.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged, remote = "Self")]
+pub enum Document {
     Data {
+        #[serde(skip_serializing_if = "Option::is_none")]
         jsonapi: Option<JsonAPIObject>,
         data: PrimaryData,
+        #[serde(skip_serializing_if = "Option::is_none")]
         meta: Option<Meta>,
-        links: Links,
-        included: Vec<T>,
-    }
+        #[serde(skip_serializing_if = "Option::is_none")]
+        links: Option<TopLevelLinks>,
+
+        /// http://jsonapi.org/format/#document-compound-documents
+        ///
+        /// To reduce the number of HTTP requests, servers **MAY** allow responses that include related
+        /// resources along with the requested primary resources. Such responses are called "compound
+        /// documents".
+        ///
+        /// In a compound document, all included resources **MUST** be represented as an array of
+        /// resource objects in a top-level `included` member.
+        ///
+        /// Compound documents require "full linkage", meaning that every included resource **MUST**
+        /// be identified by at least one `resource identifier object` in the same document. These
+        /// resource identifier objects could either be primary data or represent resource linkage
+        /// contained within primary or included resources. The only exception to the full linkage
+        /// requirement is when relationship fields that would otherwise contain linkage data are
+        /// excluded via `sparse fieldsets`.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        included: Vec<ResourceObject>,
+    },
     Error {
-        jsonapi: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        jsonapi: Option<JsonAPIObject>,
         errors: Vec<Error>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         meta: Option<Meta>,
-        links: Links,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        links: Option<TopLevelLinks>,
+    },
+}
+
+impl Document {
+    pub fn links(&self) -> Option<&TopLevelLinks> {
+        match *self {
+            Document::Data { ref links, .. } | Document::Error { ref links, .. } => links.as_ref(),
+        }
+    }
+
+    pub fn jsonapi(&self) -> Option<&JsonAPIObject> {
+        match *self {
+            Document::Data { ref jsonapi, .. } | Document::Error { ref jsonapi, .. } => jsonapi.as_ref(),
+        }
+    }
+
+    /// The version of the specification declared by the `jsonapi` object. A document that
+    /// doesn't declare one is a JSON API 1.0 document.
+    pub fn version(&self) -> Version {
+        self.declared_version().unwrap_or(Version::V1_0)
+    }
+
+    /// The version of the specification given by the `version` member of the `jsonapi` object,
+    /// if there is one.
+    pub fn declared_version(&self) -> Option<Version> {
+        self.jsonapi().and_then(|jsonapi| jsonapi.version.as_deref()).map(Version::parse)
+    }
+
+    /// The earliest version of the specification that has every member used in this document.
+    pub fn required_version(&self) -> Version {
+        let uses_v1_1 = match *self {
+            Document::Data { ref jsonapi, ref data, ref links, ref included, .. } => {
+                jsonapi.as_ref().is_some_and(JsonAPIObject::uses_v1_1) ||
+                links.as_ref().is_some_and(TopLevelLinks::uses_v1_1) ||
+                data.resources().iter().chain(included).any(ResourceObject::uses_v1_1) ||
+                data.identifiers().iter().any(ResourceIdentifierObject::uses_v1_1)
+            }
+            Document::Error { ref jsonapi, ref errors, ref links, .. } => {
+                jsonapi.as_ref().is_some_and(JsonAPIObject::uses_v1_1) ||
+                links.as_ref().is_some_and(TopLevelLinks::uses_v1_1) ||
+                errors.iter().any(Error::uses_v1_1)
+            }
+        };
+
+        if uses_v1_1 { Version::V1_1 } else { Version::V1_0 }
+    }
+
+    /// Removes the members added after `version`, and lowers a declared version that is later
+    /// than `version`.
+    ///
+    /// A resource object or resource identifier object keeps its `lid` when it has no `id`, since
+    /// it would identify nothing without it.
+    pub fn limit_to_version(&mut self, version: Version) {
+        let (jsonapi, links) = match *self {
+            Document::Data { ref mut jsonapi, ref mut links, .. } |
+            Document::Error { ref mut jsonapi, ref mut links, .. } => (jsonapi, links),
+        };
+
+        if let Some(ref mut jsonapi) = *jsonapi {
+            if jsonapi.version() > version {
+                jsonapi.version = Some(version.as_str().to_owned());
+            }
+        }
+
+        if version >= Version::V1_1 {
+            return;
+        }
+
+        jsonapi.iter_mut().for_each(JsonAPIObject::strip_v1_1);
+        links.iter_mut().for_each(TopLevelLinks::strip_v1_1);
+
+        match *self {
+            Document::Data { ref mut data, ref mut included, .. } => {
+                data.resources_mut().iter_mut().chain(included).for_each(ResourceObject::strip_v1_1);
+                data.identifiers_mut().iter_mut().for_each(ResourceIdentifierObject::strip_v1_1);
+            }
+            Document::Error { ref mut errors, .. } => errors.iter_mut().for_each(Error::strip_v1_1),
+        }
+    }
+
+    /// The URL of the top-level `self` link, which relative references in the document are
+    /// resolved against.
+    pub fn base_uri(&self) -> Option<&Uri> {
+        self.links().and_then(|links| links.self_.as_ref()).map(Link::href)
+    }
+
+    /// Resolves a URI-reference found in this document, such as the href of a relative link,
+    /// against the document's `base_uri`. Without a base, the reference is returned unchanged.
+    pub fn resolve_uri(&self, reference: &Uri) -> Uri {
+        match self.base_uri() {
+            Some(base) => reference.resolve(base),
+            None => reference.clone(),
+        }
+    }
+
+    /// http://jsonapi.org/format/#document-compound-documents
+    ///
+    /// A compound document **MUST NOT** include more than one resource object for each `type` and
+    /// `id` pair. Merges included resources that share a `type` and `id` into the first of them,
+    /// and merges included resources that duplicate a resource object in the primary data into that
+    /// resource object.
+    ///
+    /// The attributes, relationships, links and meta of merged resources are unioned. Where two
+    /// resources give a member different values, the first value is kept and the member is returned
+    /// as a `MergeConflict`. The resource object in the primary data always comes first.
+    pub fn normalize_included(&mut self) -> Vec<MergeConflict> {
+        let (data, included) = match *self {
+            Document::Data { ref mut data, ref mut included, .. } => (data, included),
+            Document::Error { .. } => return Vec::new(),
+        };
+
+        let mut primary = BTreeMap::new();
+
+        for (index, resource) in data.resources().iter().enumerate() {
+            if let Some(ref id) = resource.id {
+                primary.entry((resource.type_.clone(), id.clone())).or_insert(index);
+            }
+        }
+
+        let mut conflicts = Vec::new();
+        let mut indices = BTreeMap::new();
+        let mut merged: Vec<ResourceObject> = Vec::with_capacity(included.len());
+
+        for resource in included.drain(..) {
+            let key = match resource.id {
+                Some(ref id) => (resource.type_.clone(), id.clone()),
+                None => {
+                    merged.push(resource);
+                    continue;
+                }
+            };
+
+            let target = match (primary.get(&key), indices.get(&key)) {
+                (Some(&index), _) => &mut data.resources_mut()[index],
+                (None, Some(&index)) => &mut merged[index],
+                (None, None) => {
+                    indices.insert(key, merged.len());
+                    merged.push(resource);
+                    continue;
+                }
+            };
+
+            let mut pointers = Vec::new();
+            merge_resource(target, resource, &mut pointers);
+
+            conflicts.extend(pointers.into_iter().map(|pointer| {
+                MergeConflict {
+                    type_: key.0.clone(),
+                    id: key.1.clone(),
+                    pointer,
+                }
+            }));
+        }
+
+        *included = merged;
+
+        conflicts
+    }
+
+    /// Finds the resource object with the given `type` and `id` in the primary data or in
+    /// `included`.
+    pub fn find(&self, type_: &str, id: &str) -> Option<&ResourceObject> {
+        self.resource_objects()
+            .into_iter()
+            .find(|resource| resource.type_.as_str() == type_ && resource.id.as_ref().map(Id::as_str) == Some(id))
+    }
+
+    /// Indexes the resource objects of this document by `type` and `id`, to resolve relationships
+    /// without scanning the document again. The `resolve` methods of `Document` build a new index
+    /// on every call, so build one here to resolve many relationships.
+    pub fn index(&self) -> ResourceIndex<'_> {
+        match *self {
+            Document::Data { ref data, ref included, .. } => ResourceIndex::new(data.resources(), included),
+            Document::Error { .. } => ResourceIndex::new(&[], &[]),
+        }
+    }
+
+    /// Resolves the linkage of the to-one `relationship` of `resource` to the resource object it
+    /// identifies, if that resource object is in this document.
+    pub fn resolve<'a>(&'a self, resource: &ResourceObject, relationship: &str) -> Option<&'a ResourceObject> {
+        self.index().resolve(resource, relationship)
+    }
+
+    /// Resolves the linkage of `relationship` of `resource` to the resource objects it identifies,
+    /// skipping any that are not in this document.
+    pub fn resolve_many<'a>(&'a self, resource: &ResourceObject, relationship: &str) -> Resources<'a> {
+        self.index().resolve_many(resource, relationship)
+    }
+
+    /// http://jsonapi.org/format/#fetching-includes
+    ///
+    /// Walks a relationship path such as `comments.author` from `resource`, returning the resource
+    /// objects at the end of the path. Each resource object is returned once, in the order it is
+    /// first reached.
+    pub fn resolve_path<'a>(&'a self, resource: &ResourceObject, path: &str) -> Resources<'a> {
+        self.index().resolve_path(resource, path)
+    }
+
+    /// Walks a relationship path such as `comments.author` from every resource object in the
+    /// primary data, as the `include` query parameter does.
+    pub fn resolve_include<'a>(&'a self, path: &str) -> Resources<'a> {
+        self.index().resolve_include(path)
+    }
+
+    /// Turns the primary data into a tree of plain JSON, in which the `data` of each relationship
+    /// is replaced by the resource objects its linkage identifies, recursively.
+    ///
+    /// Relationships are expanded `depth` levels deep. Linkage beyond that depth, or to resources
+    /// that are not in the document, is left as resource identifier objects. A resource that is
+    /// reached again while it is being expanded is handled according to `cycles`.
+    pub fn denormalize(&self, depth: usize, cycles: CyclePolicy) -> Result<serde_json::Value, DenormalizeError> {
+        let data = match *self {
+            Document::Data { ref data, .. } => data,
+            Document::Error { .. } => return Err(DenormalizeError::NoData),
+        };
+
+        let denormalizer = Denormalizer {
+            index: self.index(),
+            cycles,
+            ancestors: Vec::new(),
+        };
+
+        denormalizer.primary_data(data, depth)
+    }
+
+    /// http://jsonapi.org/format/#fetching-sparse-fieldsets
+    ///
+    /// Removes the fields not requested by `fieldsets` from the primary data and the included
+    /// resources, and drops included resources that no chain of resource linkage from the primary
+    /// data identifies.
+    ///
+    /// The only exception to the full linkage requirement is when relationship fields that would
+    /// otherwise contain linkage data are excluded via sparse fieldsets, so linkage is followed
+    /// before the fields are removed. A resource requested with `include=author&fields[articles]=title`
+    /// is kept even though the `author` relationship is not.
+    pub fn apply_fieldsets(&mut self, fieldsets: &Fieldsets) {
+        if let Document::Data { ref mut data, ref mut included, .. } = *self {
+            let mut linked = linked_included(data, included).into_iter();
+            included.retain(|_| linked.next().unwrap_or(false));
+
+            for resource in data.resources_mut().iter_mut().chain(included) {
+                fieldsets.prune(resource);
+            }
+        }
+    }
+
+    /// http://jsonapi.org/format/#fetching-sorting
+    ///
+    /// Sorts an array of resource objects in the primary data, following dot-separated sort fields
+    /// to the included resources.
+    pub fn sort_data(&mut self, sort: &SortSpec) {
+        if let Document::Data { data: PrimaryData::Array(ref mut resources), ref included, .. } = *self {
+            sort.sort(resources, included);
+        }
+    }
+
+    /// http://jsonapi.org/format/#fetching-filtering
+    ///
+    /// Removes the resource objects in the primary data that `filter` doesn't match, following
+    /// dot-separated fields to the included resources.
+    pub fn filter_data(&mut self, filter: &Filter) {
+        if let Document::Data { data: PrimaryData::Array(ref mut resources), ref included, .. } = *self {
+            filter.retain(resources, included);
+        }
+    }
+
+    /// The resource objects of the primary data, followed by the included resources.
+    fn resource_objects(&self) -> Vec<&ResourceObject> {
+        match *self {
+            Document::Data { ref data, ref included, .. } => data.resources().iter().chain(included).collect(),
+            Document::Error { .. } => Vec::new(),
+        }
+    }
+}
+
+/// `remote = "Self"` turns the derived serializer into the inherent `Document::serialize`, which
+/// this wraps.
+///
+/// A document that explicitly declares a version is serialized with `limit_to_version`, so it
+/// has no member added after that version. A document that declares no version is serialized
+/// as is, at its `required_version`.
+impl Serialize for Document {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        match self.declared_version() {
+            Some(version) if self.required_version() > version => {
+                let mut document = self.clone();
+                document.limit_to_version(version);
+                Document::serialize(&document, serializer)
+            }
+            _ => Document::serialize(self, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D>(deserializer: D) -> Result<Document, D::Error>
+        where D: Deserializer<'de>,
+    {
+        DocumentSeed::default().deserialize(deserializer)
     }
 }
 
+/// Deserializes a `Document`, using a `PrimaryDataSeed` to decide between the resource object and
+/// resource identifier object variants of its primary data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DocumentSeed(pub PrimaryDataSeed);
+
+impl<'de> DeserializeSeed<'de> for DocumentSeed {
+    type Value = Document;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Document, D::Error>
+        where D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawDocument {
+            jsonapi: Option<JsonAPIObject>,
+            #[serde(default, deserialize_with = "deserialize_some")]
+            data: Option<serde_json::Value>,
+            errors: Option<Vec<Error>>,
+            meta: Option<Meta>,
+            links: Option<TopLevelLinks>,
+            included: Option<Vec<ResourceObject>>,
+        }
+
+        let raw = RawDocument::deserialize(deserializer)?;
+
+        match (raw.data, raw.errors) {
+            (Some(_), Some(_)) => {
+                Err(de::Error::custom("the members data and errors MUST NOT coexist in the same document"))
+            }
+            (Some(data), None) => {
+                let data = self.0.deserialize(data).map_err(de::Error::custom)?;
+
+                Ok(Document::Data {
+                    jsonapi: raw.jsonapi,
+                    data,
+                    meta: raw.meta,
+                    links: raw.links,
+                    included: raw.included.unwrap_or_default(),
+                })
+            }
+            (None, Some(errors)) => {
+                if raw.included.is_some() {
+                    return Err(de::Error::custom(
+                        "if a document does not contain a top-level data key, the included member MUST NOT \
+                         be present either"));
+                }
+
+                Ok(Document::Error {
+                    jsonapi: raw.jsonapi,
+                    errors,
+                    meta: raw.meta,
+                    links: raw.links,
+                })
+            }
+            (None, None) => {
+                Err(de::Error::custom("a document without a top-level data or errors member, such as a meta-only \
+                                       document, can't be represented by spec::Document; read it as a \
+                                       schema::Document instead"))
+            }
+        }
+    }
+}
+
+
+/// http://jsonapi.org/format/#document-compound-documents
+///
+/// Builds a `Document::Data` whose `included` member has "full linkage". Candidate related
+/// resources are placed in `included` only if a chain of resource linkage starting at the primary
+/// data identifies them; what happens to the rest depends on the `UnlinkedResources` policy.
+///
+/// The only exception to the full linkage requirement is when relationship fields that would
+/// otherwise contain linkage data are excluded via sparse fieldsets. A fieldset given to the builder
+/// is applied after linkage has been followed, so a resource that is only linked through an
+/// excluded relationship is still included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompoundDocumentBuilder {
+    data: PrimaryData,
+    candidates: Vec<ResourceObject>,
+    fieldsets: Fieldsets,
+    unlinked: UnlinkedResources,
+    jsonapi: Option<JsonAPIObject>,
+    meta: Option<Meta>,
+    links: Option<TopLevelLinks>,
+}
+
+/// What `CompoundDocumentBuilder::build` does with a candidate resource that nothing links to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnlinkedResources {
+    /// Fail with `CompoundDocumentError::Unlinked`.
+    #[default]
+    Reject,
+
+    /// Leave the resource out of the document.
+    Drop,
+}
+
+impl CompoundDocumentBuilder {
+    pub fn new(data: PrimaryData) -> CompoundDocumentBuilder {
+        CompoundDocumentBuilder {
+            data,
+            candidates: Vec::new(),
+            fieldsets: Fieldsets::new(),
+            unlinked: UnlinkedResources::default(),
+            jsonapi: None,
+            meta: None,
+            links: None,
+        }
+    }
+
+    /// Adds a candidate for `included`.
+    pub fn include(mut self, resource: ResourceObject) -> CompoundDocumentBuilder {
+        self.candidates.push(resource);
+        self
+    }
+
+    pub fn include_all<I>(mut self, resources: I) -> CompoundDocumentBuilder
+        where I: IntoIterator<Item = ResourceObject>,
+    {
+        self.candidates.extend(resources);
+        self
+    }
+
+    /// http://jsonapi.org/format/#fetching-sparse-fieldsets
+    ///
+    /// Restricts the resource objects of `type_`, in the primary data and in `included`, to the
+    /// attributes and relationships named in `fields`.
+    pub fn fieldset<I>(mut self, type_: Type, fields: I) -> CompoundDocumentBuilder
+        where I: IntoIterator<Item = Name>,
+    {
+        self.fieldsets.insert(type_, fields);
+        self
+    }
+
+    /// Applies the fieldsets requested with `fields[TYPE]` parameters, adding to any already given.
+    pub fn fieldsets(mut self, fieldsets: &Fieldsets) -> CompoundDocumentBuilder {
+        for (type_, fields) in fieldsets.iter() {
+            self.fieldsets.insert(type_.clone(), fields.iter().cloned());
+        }
+
+        self
+    }
+
+    pub fn unlinked(mut self, unlinked: UnlinkedResources) -> CompoundDocumentBuilder {
+        self.unlinked = unlinked;
+        self
+    }
+
+    pub fn jsonapi(mut self, jsonapi: JsonAPIObject) -> CompoundDocumentBuilder {
+        self.jsonapi = Some(jsonapi);
+        self
+    }
+
+    pub fn meta(mut self, meta: Meta) -> CompoundDocumentBuilder {
+        self.meta = Some(meta);
+        self
+    }
+
+    pub fn links(mut self, links: TopLevelLinks) -> CompoundDocumentBuilder {
+        self.links = Some(links);
+        self
+    }
+
+    pub fn build(self) -> Result<Document, CompoundDocumentError> {
+        let CompoundDocumentBuilder { mut data, candidates, fieldsets, unlinked, jsonapi, meta, links } = self;
+
+        // A compound document MUST NOT include more than one resource object for each type and id
+        // pair, including the primary data.
+        let mut seen = data.resources()
+            .iter()
+            .filter_map(|resource| resource.id.as_ref().map(|id| (&resource.type_, id)))
+            .collect::<BTreeSet<_>>();
+
+        for candidate in &candidates {
+            match candidate.id {
+                Some(ref id) => {
+                    if !seen.insert((&candidate.type_, id)) {
+                        return Err(CompoundDocumentError::Duplicate {
+                            type_: candidate.type_.clone(),
+                            id: id.clone(),
+                        });
+                    }
+                }
+                None => {
+                    if unlinked == UnlinkedResources::Reject {
+                        return Err(CompoundDocumentError::Unlinked { type_: candidate.type_.clone(), id: None });
+                    }
+                }
+            }
+        }
+
+        let linked = linked_included(&data, &candidates);
+        let mut included = Vec::new();
+
+        for (candidate, linked) in candidates.into_iter().zip(linked) {
+            if linked {
+                included.push(candidate);
+            } else if unlinked == UnlinkedResources::Reject {
+                return Err(CompoundDocumentError::Unlinked { type_: candidate.type_, id: candidate.id });
+            }
+        }
+
+        for resource in data.resources_mut().iter_mut().chain(&mut included) {
+            fieldsets.prune(resource);
+        }
+
+        Ok(Document::Data {
+            jsonapi,
+            data,
+            meta,
+            links,
+            included,
+        })
+    }
+}
+
+/// Which of the `included` resources a chain of resource linkage starting at the primary data
+/// identifies.
+fn linked_included(data: &PrimaryData, included: &[ResourceObject]) -> Vec<bool> {
+    let mut indices = BTreeMap::new();
+
+    for (index, resource) in included.iter().enumerate() {
+        if let Some(ref id) = resource.id {
+            indices.entry((resource.type_.as_str(), id.as_str())).or_insert_with(Vec::new).push(index);
+        }
+    }
+
+    let mut pending = data.resources()
+        .iter()
+        .flat_map(ResourceObject::linkage)
+        .chain(data.identifiers())
+        .collect::<Vec<_>>();
+
+    let mut linked = vec![false; included.len()];
+
+    while let Some(identifier) = pending.pop() {
+        let key = match identifier.id {
+            Some(ref id) => (identifier.type_.as_str(), id.as_str()),
+            None => continue,
+        };
+
+        for &index in indices.get(&key).map_or(&[][..], Vec::as_slice) {
+            if !linked[index] {
+                linked[index] = true;
+                pending.extend(included[index].linkage());
+            }
+        }
+    }
+
+    linked
+}
+
+/// Why `CompoundDocumentBuilder::build` could not build a compound document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompoundDocumentError {
+    /// Every included resource **MUST** be identified by at least one resource identifier object
+    /// in the same document.
+    Unlinked {
+        type_: Type,
+        id: Option<Id>,
+    },
+
+    /// A compound document **MUST NOT** include more than one resource object for each `type` and
+    /// `id` pair.
+    Duplicate {
+        type_: Type,
+        id: Id,
+    },
+}
+
+impl fmt::Display for CompoundDocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompoundDocumentError::Unlinked { ref type_, id: Some(ref id) } => {
+                write!(f, "included resource `{}` `{}` MUST be identified by a resource identifier object",
+                       type_.as_str(),
+                       id.as_str())
+            }
+            CompoundDocumentError::Unlinked { ref type_, id: None } => {
+                write!(f, "included resource of type `{}` has no id, so it can never be linked", type_.as_str())
+            }
+            CompoundDocumentError::Duplicate { ref type_, ref id } => {
+                write!(f, "a compound document MUST NOT include more than one resource object for `{}` `{}`",
+                       type_.as_str(),
+                       id.as_str())
+            }
+        }
+    }
+}
+
+impl error::Error for CompoundDocumentError {}
+
+
+/// The resource objects of a `Document`, indexed by `type` and `id` by `Document::index`.
+#[derive(Clone, Debug)]
+pub struct ResourceIndex<'a> {
+    resources: BTreeMap<(&'a str, &'a str), &'a ResourceObject>,
+    primary: Vec<&'a ResourceObject>,
+}
+
+impl<'a> ResourceIndex<'a> {
+    /// Indexes `resources`, such as the primary data of a document, followed by `included`.
+    pub fn new(resources: &'a [ResourceObject], included: &'a [ResourceObject]) -> ResourceIndex<'a> {
+        let mut index = BTreeMap::new();
+
+        for resource in resources.iter().chain(included) {
+            if let Some(ref id) = resource.id {
+                index.entry((resource.type_.as_str(), id.as_str())).or_insert(resource);
+            }
+        }
+
+        ResourceIndex {
+            resources: index,
+            primary: resources.iter().collect(),
+        }
+    }
+
+    /// Finds the resource object with the given `type` and `id` in the primary data or in
+    /// `included`.
+    pub fn get(&self, type_: &str, id: &str) -> Option<&'a ResourceObject> {
+        self.resources.get(&(type_, id)).cloned()
+    }
+
+    /// Resolves the linkage of the to-one `relationship` of `resource` to the resource object it
+    /// identifies, if that resource object is in the document.
+    pub fn resolve(&self, resource: &ResourceObject, relationship: &str) -> Option<&'a ResourceObject> {
+        match *resource.relationships.as_ref()?.get(relationship)?.data.as_ref()? {
+            ResourceLinkage::Single(ref identifier) => {
+                self.get(identifier.type_.as_str(), identifier.id.as_ref()?.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolves the linkage of `relationship` of `resource` to the resource objects it identifies,
+    /// skipping any that are not in the document.
+    pub fn resolve_many(&self, resource: &ResourceObject, relationship: &str) -> Resources<'a> {
+        Resources(self.linked(resource, relationship).into_iter())
+    }
+
+    /// Walks a relationship path such as `comments.author` from `resource`, returning each resource
+    /// object at the end of the path once, in the order it is first reached.
+    pub fn resolve_path(&self, resource: &ResourceObject, path: &str) -> Resources<'a> {
+        let mut names = path.split('.');
+        let first = names.next().map(|name| self.linked(resource, name)).unwrap_or_default();
+
+        Resources(self.walk(first, names).into_iter())
+    }
+
+    /// Walks a relationship path such as `comments.author` from every resource object in the
+    /// primary data.
+    pub fn resolve_include(&self, path: &str) -> Resources<'a> {
+        Resources(self.walk(self.primary.clone(), path.split('.')).into_iter())
+    }
+
+    /// http://jsonapi.org/format/#fetching-sorting
+    ///
+    /// Reads a dot-separated field such as `author.name`, as sort and filter fields do: follows the
+    /// to-one relationships in `path` from `resource`, then reads the named attribute, or the `id`
+    /// for a name of `id`. A field behind an empty, to-many or unresolved relationship, or an empty
+    /// path, reads as `null`.
+    pub fn field(&self, resource: &ResourceObject, path: &[Name]) -> serde_json::Value {
+        let (name, relationships) = match path.split_last() {
+            Some(split) => split,
+            None => return serde_json::Value::Null,
+        };
+        let mut resource = resource;
+
+        for relationship in relationships {
+            resource = match self.resolve(resource, relationship.as_str()) {
+                Some(resource) => resource,
+                None => return serde_json::Value::Null,
+            };
+        }
+
+        if name.as_str() == "id" {
+            return resource.id.as_ref().map_or(serde_json::Value::Null, |id| serde_json::Value::String(id.0.clone()));
+        }
+
+        resource.attributes
+            .as_ref()
+            .and_then(|attributes| attributes.0.get(name.as_str()))
+            .cloned()
+            .unwrap_or(serde_json::Value::Null)
+    }
+
+    /// The resource objects that the linkage of `relationship` of `resource` identifies.
+    fn linked(&self, resource: &ResourceObject, relationship: &str) -> Vec<&'a ResourceObject> {
+        let linkage = resource.relationships
+            .as_ref()
+            .and_then(|relationships| relationships.get(relationship))
+            .and_then(|relationship| relationship.data.as_ref());
+
+        linkage.map_or(&[][..], ResourceLinkage::identifiers)
+            .iter()
+            .filter_map(|identifier| self.get(identifier.type_.as_str(), identifier.id.as_ref()?.as_str()))
+            .collect()
+    }
+
+    /// Follows each relationship in `names` from the resource objects in `current`.
+    fn walk<'b, I>(&self, mut current: Vec<&'a ResourceObject>, names: I) -> Vec<&'a ResourceObject>
+        where I: IntoIterator<Item = &'b str>,
+    {
+        for name in names {
+            let mut seen = BTreeSet::new();
+
+            current = current.iter()
+                .flat_map(|resource| self.linked(resource, name))
+                .filter(|resource| seen.insert(*resource as *const ResourceObject))
+                .collect();
+        }
+
+        current
+    }
+}
+
+/// An iterator over resource objects borrowed from a `Document`.
+#[derive(Clone, Debug)]
+pub struct Resources<'a>(vec::IntoIter<&'a ResourceObject>);
+
+impl<'a> Iterator for Resources<'a> {
+    type Item = &'a ResourceObject;
+
+    fn next(&mut self) -> Option<&'a ResourceObject> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Resources<'a> {
+    fn next_back(&mut self) -> Option<&'a ResourceObject> {
+        self.0.next_back()
+    }
+}
+
+impl<'a> ExactSizeIterator for Resources<'a> {}
+
+
+/// What `Document::denormalize` does when a resource is reached again while it is being expanded,
+/// such as an author whose articles link back to that author.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CyclePolicy {
+    /// Emit the resource object again, but leave the linkage of its relationships unexpanded.
+    #[default]
+    Stop,
+
+    /// Emit the resource identifier object instead of the resource object.
+    Identifier,
+
+    /// Fail with `DenormalizeError::Cycle`.
+    Error,
+}
+
+/// Why `Document::denormalize` could not build a tree.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DenormalizeError {
+    /// The document has no primary data.
+    NoData,
+
+    /// A resource was reached again while it was being expanded, under `CyclePolicy::Error`.
+    Cycle {
+        type_: Type,
+        id: Id,
+    },
+
+    /// A resource object could not be serialized.
+    Serialization {
+        message: String,
+    },
+}
+
+impl fmt::Display for DenormalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DenormalizeError::NoData => write!(f, "the document has no primary data to denormalize"),
+            DenormalizeError::Cycle { ref type_, ref id } => {
+                write!(f, "resource `{}` `{}` links back to itself", type_.as_str(), id.as_str())
+            }
+            DenormalizeError::Serialization { ref message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for DenormalizeError {}
+
+struct Denormalizer<'a> {
+    index: ResourceIndex<'a>,
+    cycles: CyclePolicy,
+    /// The resources being expanded, from the primary data down.
+    ancestors: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Denormalizer<'a> {
+    fn primary_data(mut self, data: &'a PrimaryData, depth: usize) -> Result<serde_json::Value, DenormalizeError> {
+        match *data {
+            PrimaryData::Null => Ok(serde_json::Value::Null),
+            PrimaryData::Single(ref resource) => self.resource(resource, depth),
+            PrimaryData::SingleIdentifier(ref identifier) => self.identifier(identifier, depth),
+            PrimaryData::Array(ref resources) => {
+                resources.iter()
+                    .map(|resource| self.resource(resource, depth))
+                    .collect::<Result<_, _>>()
+                    .map(serde_json::Value::Array)
+            }
+            PrimaryData::ArrayIdentifier(ref identifiers) => {
+                identifiers.iter()
+                    .map(|identifier| self.identifier(identifier, depth))
+                    .collect::<Result<_, _>>()
+                    .map(serde_json::Value::Array)
+            }
+        }
+    }
+
+    /// Serializes `resource`, expanding the linkage of its relationships `depth` levels deep.
+    fn resource(&mut self,
+                resource: &'a ResourceObject,
+                depth: usize)
+                -> Result<serde_json::Value, DenormalizeError> {
+        let mut value = to_json(resource)?;
+
+        let relationships = match resource.relationships {
+            Some(ref relationships) if depth > 0 => relationships,
+            _ => return Ok(value),
+        };
+
+        if let Some(ref id) = resource.id {
+            self.ancestors.push((resource.type_.as_str(), id.as_str()));
+        }
+
+        for (name, relationship) in &relationships.0 {
+            let data = match relationship.data {
+                Some(ResourceLinkage::Null) | None => continue,
+                Some(ResourceLinkage::Single(ref identifier)) => self.identifier(identifier, depth - 1)?,
+                Some(ResourceLinkage::Array(ref identifiers)) => {
+                    identifiers.iter()
+                        .map(|identifier| self.identifier(identifier, depth - 1))
+                        .collect::<Result<_, _>>()
+                        .map(serde_json::Value::Array)?
+                }
+            };
+
+            value["relationships"][name.as_str()]["data"] = data;
+        }
+
+        if resource.id.is_some() {
+            self.ancestors.pop();
+        }
+
+        Ok(value)
+    }
+
+    /// Replaces `identifier` with the resource object it identifies, if it is in the document.
+    fn identifier(&mut self,
+                  identifier: &'a ResourceIdentifierObject,
+                  depth: usize)
+                  -> Result<serde_json::Value, DenormalizeError> {
+        let key = match identifier.id {
+            Some(ref id) => (identifier.type_.as_str(), id.as_str()),
+            None => return to_json(identifier),
+        };
+
+        let resource = match self.index.get(key.0, key.1) {
+            Some(resource) => resource,
+            None => return to_json(identifier),
+        };
+
+        if !self.ancestors.contains(&key) {
+            return self.resource(resource, depth);
+        }
+
+        match self.cycles {
+            CyclePolicy::Stop => to_json(resource),
+            CyclePolicy::Identifier => to_json(identifier),
+            CyclePolicy::Error => {
+                Err(DenormalizeError::Cycle {
+                    type_: identifier.type_.clone(),
+                    id: Id(key.1.to_owned()),
+                })
+            }
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value, DenormalizeError> {
+    serde_json::to_value(value).map_err(|error| DenormalizeError::Serialization { message: error.to_string() })
+}
+
+
+/// A member that two resource objects with the same `type` and `id`, in `included` or in the
+/// primary data, gave different values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub type_: Type,
+    pub id: Id,
+    /// The member within the resource object, such as `/attributes/title`.
+    pub pointer: JsonPointer,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "resource objects `{}` `{}` have different values for `{}`",
+               self.type_.as_str(),
+               self.id.as_str(),
+               self.pointer)
+    }
+}
+
+fn merge_resource(target: &mut ResourceObject, source: ResourceObject, conflicts: &mut Vec<JsonPointer>) {
+    let pointer = JsonPointer::root();
+
+    if let Some(attributes) = source.attributes {
+        let target = target.attributes.get_or_insert_with(Attributes::default);
+        merge_object(&mut target.0, attributes.0, &pointer.child("attributes"), conflicts);
+    }
+
+    if let Some(relationships) = source.relationships {
+        let target = target.relationships.get_or_insert_with(Relationships::default);
+        let pointer = pointer.child("relationships");
+
+        for (name, relationship) in relationships.0 {
+            let pointer = pointer.child(&name);
+
+            match target.0.get_mut(&name) {
+                Some(target) => merge_relationship(target, relationship, &pointer, conflicts),
+                None => {
+                    target.0.insert(name, relationship);
+                }
+            }
+        }
+    }
+
+    if let Some(links) = source.links {
+        let target = target.links.get_or_insert_with(ResourceLinks::default);
+        merge_links(&mut (target.0).0, (links.0).0, &pointer.child("links"), conflicts);
+    }
+
+    if let Some(meta) = source.meta {
+        let target = target.meta.get_or_insert_with(Meta::default);
+        merge_object(&mut target.0, meta.0, &pointer.child("meta"), conflicts);
+    }
+}
+
+fn merge_relationship(target: &mut Relationship,
+                      source: Relationship,
+                      pointer: &JsonPointer,
+                      conflicts: &mut Vec<JsonPointer>) {
+    if let Some(links) = source.links {
+        let target = target.links.get_or_insert_with(Links::default);
+        merge_links(&mut target.0, links.0, &pointer.child("links"), conflicts);
+    }
+
+    if let Some(data) = source.data {
+        match target.data {
+            Some(ref existing) => {
+                if *existing != data {
+                    conflicts.push(pointer.child("data"));
+                }
+            }
+            None => target.data = Some(data),
+        }
+    }
+
+    if let Some(meta) = source.meta {
+        let target = target.meta.get_or_insert_with(Meta::default);
+        merge_object(&mut target.0, meta.0, &pointer.child("meta"), conflicts);
+    }
+}
+
+fn merge_links(target: &mut BTreeMap<Name, Link>,
+               source: BTreeMap<Name, Link>,
+               pointer: &JsonPointer,
+               conflicts: &mut Vec<JsonPointer>) {
+    for (name, link) in source {
+        match target.get(&name) {
+            Some(existing) => {
+                if *existing != link {
+                    conflicts.push(pointer.child(&name));
+                }
+            }
+            None => {
+                target.insert(name, link);
+            }
+        }
+    }
+}
+
+fn merge_object(target: &mut Object, source: Object, pointer: &JsonPointer, conflicts: &mut Vec<JsonPointer>) {
+    for (name, value) in source {
+        match target.get(&name) {
+            Some(existing) => {
+                if *existing != value {
+                    conflicts.push(pointer.child(&name));
+                }
+            }
+            None => {
+                target.insert(name, value);
+            }
+        }
+    }
+}
 
 
 /// The top-level links object **MAY** contain the following members:
@@ -45,10 +1101,32 @@ enum Document {
 /// * self: the link that generated the current response document.
 /// * related: a related resource link when the primary data represents a resource relationship.
 ///   pagination links for the primary data.
-struct TopLevelLinks {
-    self_: Option<Link>,
-    related: Option<Link>,
-    links: Links,
+/// * describedby: a link to a description document (e.g. OpenAPI or JSON Schema) for the current
+///   document. Added in JSON API 1.1.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TopLevelLinks {
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    pub self_: Option<Link>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related: Option<Link>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub describedby: Option<Link>,
+    #[serde(flatten)]
+    pub links: Links,
+}
+
+impl TopLevelLinks {
+    fn uses_v1_1(&self) -> bool {
+        self.describedby.is_some() ||
+        self.self_.iter().chain(&self.related).any(Link::uses_v1_1) ||
+        self.links.uses_v1_1()
+    }
+
+    fn strip_v1_1(&mut self) {
+        self.describedby = None;
+        self.self_.iter_mut().chain(&mut self.related).for_each(Link::strip_v1_1);
+        self.links.strip_v1_1();
+    }
 }
 
 
@@ -62,7 +1140,12 @@ struct TopLevelLinks {
 /// * an array of resource objects, an array of resource identifier objects, or an empty array ([]),
 ///   for requests that target resource collections
 ///
-enum PrimaryData {
+/// A resource object with only `type` and `id` members is indistinguishable from a resource
+/// identifier object, so deserializing `PrimaryData` on its own infers the variant from the members
+/// that are present. Use a `PrimaryDataSeed` when the endpoint is known.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum PrimaryData {
     Null,
     Single(ResourceObject),
     SingleIdentifier(ResourceIdentifierObject),
@@ -70,6 +1153,181 @@ enum PrimaryData {
     ArrayIdentifier(Vec<ResourceIdentifierObject>),
 }
 
+impl PrimaryData {
+    /// The resource objects of the primary data, which is empty when it is `null` or made of
+    /// resource identifier objects.
+    pub fn resources(&self) -> &[ResourceObject] {
+        match *self {
+            PrimaryData::Single(ref resource) => slice::from_ref(resource),
+            PrimaryData::Array(ref resources) => resources,
+            _ => &[],
+        }
+    }
+
+    pub fn resources_mut(&mut self) -> &mut [ResourceObject] {
+        match *self {
+            PrimaryData::Single(ref mut resource) => slice::from_mut(resource),
+            PrimaryData::Array(ref mut resources) => resources,
+            _ => &mut [],
+        }
+    }
+
+    /// The resource identifier objects of the primary data, which is empty when it is `null` or
+    /// made of resource objects.
+    pub fn identifiers(&self) -> &[ResourceIdentifierObject] {
+        match *self {
+            PrimaryData::SingleIdentifier(ref identifier) => slice::from_ref(identifier),
+            PrimaryData::ArrayIdentifier(ref identifiers) => identifiers,
+            _ => &[],
+        }
+    }
+
+    pub fn identifiers_mut(&mut self) -> &mut [ResourceIdentifierObject] {
+        match *self {
+            PrimaryData::SingleIdentifier(ref mut identifier) => slice::from_mut(identifier),
+            PrimaryData::ArrayIdentifier(ref mut identifiers) => identifiers,
+            _ => &mut [],
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PrimaryData {
+    fn deserialize<D>(deserializer: D) -> Result<PrimaryData, D::Error>
+        where D: Deserializer<'de>,
+    {
+        PrimaryDataSeed::default().deserialize(deserializer)
+    }
+}
+
+/// The kind of endpoint a document was sent to or fetched from. A request body is described by the
+/// endpoint it is sent to, so a `POST /articles` and a `GET /articles` both use `Endpoint::Resource`,
+/// while a `PATCH /articles/1/relationships/tags` uses `Endpoint::Relationship`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endpoint {
+    /// The endpoint is not known, so each object is classified by its members: one with
+    /// `attributes`, `relationships` or `links`, or without an `id` or `lid`, is a resource object,
+    /// and anything else is a resource identifier object.
+    #[default]
+    Unknown,
+
+    /// A resource or resource collection endpoint such as `/articles` or `/articles/1`, or a
+    /// related resource endpoint such as `/articles/1/author`. The primary data is made of resource
+    /// objects.
+    Resource,
+
+    /// http://jsonapi.org/format/#fetching-relationships
+    ///
+    /// A relationship endpoint such as `/articles/1/relationships/author`. The primary data is
+    /// resource linkage, made of resource identifier objects.
+    Relationship,
+}
+
+/// Deserializes `PrimaryData`, choosing between resource objects and resource identifier objects
+/// by the `endpoint` the data belongs to.
+///
+/// In `strict` mode, an array that mixes both kinds of object is rejected, as is a resource object
+/// found where the endpoint only allows resource identifier objects.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrimaryDataSeed {
+    pub endpoint: Endpoint,
+    pub strict: bool,
+}
+
+impl PrimaryDataSeed {
+    pub fn new(endpoint: Endpoint) -> PrimaryDataSeed {
+        PrimaryDataSeed {
+            endpoint,
+            strict: false,
+        }
+    }
+
+    pub fn strict(mut self, strict: bool) -> PrimaryDataSeed {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns true if `object` should be read as a resource object rather than a resource
+    /// identifier object.
+    fn is_resource_object(&self, object: &Object) -> Result<bool, String> {
+        let has_fields = object.contains_key("attributes") ||
+            object.contains_key("relationships") ||
+            object.contains_key("links");
+        let identified = object.contains_key("id") || object.contains_key("lid");
+        let looks_like_resource = !identified || has_fields;
+
+        match self.endpoint {
+            Endpoint::Unknown => Ok(looks_like_resource),
+            Endpoint::Resource => Ok(true),
+            Endpoint::Relationship => {
+                // A resource identifier object MAY have a `lid` instead of an `id`.
+                if self.strict && looks_like_resource {
+                    Err("a relationship endpoint's primary data MUST be resource identifier objects".to_owned())
+                } else {
+                    Ok(false)
+                }
+            }
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for PrimaryDataSeed {
+    type Value = PrimaryData;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<PrimaryData, D::Error>
+        where D: Deserializer<'de>,
+    {
+        fn from_value<T, E>(value: serde_json::Value) -> Result<T, E>
+            where T: de::DeserializeOwned,
+                  E: de::Error,
+        {
+            serde_json::from_value(value).map_err(E::custom)
+        }
+
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::Null => Ok(PrimaryData::Null),
+            serde_json::Value::Object(object) => {
+                if self.is_resource_object(&object).map_err(de::Error::custom)? {
+                    from_value(serde_json::Value::Object(object)).map(PrimaryData::Single)
+                } else {
+                    from_value(serde_json::Value::Object(object)).map(PrimaryData::SingleIdentifier)
+                }
+            }
+            serde_json::Value::Array(values) => {
+                let mut resources = 0;
+
+                for value in &values {
+                    match *value {
+                        serde_json::Value::Object(ref object) => {
+                            if self.is_resource_object(object).map_err(de::Error::custom)? {
+                                resources += 1;
+                            }
+                        }
+                        _ => return Err(de::Error::custom("primary data arrays MUST only contain objects")),
+                    }
+                }
+
+                if self.strict && resources != 0 && resources != values.len() {
+                    return Err(de::Error::custom(
+                        "primary data MUST NOT mix resource objects and resource identifier objects"));
+                }
+
+                let identifiers = match self.endpoint {
+                    Endpoint::Relationship => true,
+                    Endpoint::Resource => false,
+                    Endpoint::Unknown => !values.is_empty() && resources == 0,
+                };
+
+                if identifiers {
+                    from_value(serde_json::Value::Array(values)).map(PrimaryData::ArrayIdentifier)
+                } else {
+                    from_value(serde_json::Value::Array(values)).map(PrimaryData::Array)
+                }
+            }
+            _ => Err(de::Error::custom("primary data MUST be null, an object or an array")),
+        }
+    }
+}
+
 
 /// http://jsonapi.org/format/#document-resource-objects
 ///
@@ -81,7 +1339,9 @@ enum PrimaryData {
 /// * type
 ///
 /// Exception: The id member is not required when the resource object originates at the client and
-/// represents a new resource to be created on the server.
+/// represents a new resource to be created on the server. In that case, a client **MAY** include a
+/// `lid` member to uniquely identify the resource by `type` locally within the document. The `lid`
+/// member was added in JSON API 1.1.
 ///
 /// In addition, a resource object **MAY** contain any of these top-level members:
 ///
@@ -91,24 +1351,274 @@ enum PrimaryData {
 /// * links: a links object containing links related to the resource.
 /// * meta: a meta object containing non-standard meta-information about a resource that can not be
 ///   represented as an attribute or relationship.
-struct ResourceObject {
-    id: Id,
-    type_: Type,
-    attributes: Option<Attributes>,
-    relationships: Option<Relationships>,
-    links: Option<Links>,
-    meta: Option<Meta>,
+///
+/// The attributes and relationships are stored as `A` and `R`, which default to the untyped
+/// `Attributes` and `Relationships`. Any owned `Serialize` and `Deserialize` types can be used
+/// instead, such as a struct of the resource's attributes; the member name rules are still checked
+/// against their JSON form whenever the resource object is serialized or deserialized. The untyped
+/// defaults are checked in place rather than converted through `serde_json::Value`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceObject<A = Attributes, R = Relationships> {
+    pub id: Option<Id>,
+    pub lid: Option<String>,
+    pub type_: Type,
+    pub attributes: Option<A>,
+    pub relationships: Option<R>,
+    pub links: Option<ResourceLinks>,
+    pub meta: Option<Meta>,
+}
+
+impl ResourceObject {
+    /// http://jsonapi.org/format/#document-resource-object-fields
+    ///
+    /// Fields for a resource object **MUST** share a common namespace with each other and with
+    /// `type` and `id`.
+    pub fn check_fields(&self) -> Result<(), ValidationError> {
+        check_field_names(self.attributes.as_ref(), self.relationships.as_ref())
+    }
+
+    /// The resource identifier objects in the linkage of every relationship.
+    pub fn linkage(&self) -> Vec<&ResourceIdentifierObject> {
+        self.relationships
+            .iter()
+            .flat_map(|relationships| relationships.0.values())
+            .filter_map(|relationship| relationship.data.as_ref())
+            .flat_map(ResourceLinkage::identifiers)
+            .collect()
+    }
+
+    /// Converts the attributes and relationships into typed values.
+    pub fn into_typed<A, R>(self) -> Result<ResourceObject<A, R>, serde_json::Error>
+        where A: DeserializeOwned + Any,
+              R: DeserializeOwned + Any,
+    {
+        let attributes = match self.attributes.map(downcast) {
+            Some(Ok(attributes)) => Some(attributes),
+            Some(Err(attributes)) => Some(serde_json::from_value(serde_json::Value::Object(attributes.0))?),
+            None => None,
+        };
+
+        let relationships = match self.relationships.map(downcast) {
+            Some(Ok(relationships)) => Some(relationships),
+            Some(Err(relationships)) => Some(serde_json::from_value(serde_json::to_value(relationships)?)?),
+            None => None,
+        };
+
+        Ok(ResourceObject {
+            id: self.id,
+            lid: self.lid,
+            type_: self.type_,
+            attributes,
+            relationships,
+            links: self.links,
+            meta: self.meta,
+        })
+    }
+}
+
+impl<A, R> ResourceObject<A, R>
+    where A: Serialize + Any,
+          R: Serialize + Any,
+{
+    /// Converts typed attributes and relationships back into their untyped JSON form, checking the
+    /// member name rules along the way.
+    pub fn to_untyped(&self) -> Result<ResourceObject, serde_json::Error> {
+        let (attributes, relationships) = untyped_fields(self.attributes.as_ref(), self.relationships.as_ref())?;
+
+        Ok(ResourceObject {
+            id: self.id.clone(),
+            lid: self.lid.clone(),
+            type_: self.type_.clone(),
+            attributes: attributes.map(Cow::into_owned),
+            relationships: relationships.map(Cow::into_owned),
+            links: self.links.clone(),
+            meta: self.meta.clone(),
+        })
+    }
+}
+
+type UntypedFields<'a> = (Option<Cow<'a, Attributes>>, Option<Cow<'a, Relationships>>);
+
+/// Converts typed attributes and relationships to `Attributes` and `Relationships`, which checks
+/// their member names, then checks that they don't collide. Untyped fields are borrowed and checked
+/// in place.
+fn untyped_fields<'a, A, R>(attributes: Option<&'a A>, relationships: Option<&'a R>)
+                            -> Result<UntypedFields<'a>, serde_json::Error>
+    where A: Serialize + Any,
+          R: Serialize + Any,
+{
+    let attributes = match attributes.map(downcast_ref::<A, Attributes>) {
+        Some(Ok(attributes)) => {
+            attributes.check()?;
+            Some(Cow::Borrowed(attributes))
+        }
+        Some(Err(attributes)) => Some(Cow::Owned(Attributes::deserialize(serde_json::to_value(attributes)?)?)),
+        None => None,
+    };
+
+    // The keys of `Relationships` are `Name`s, so they are valid by construction.
+    let relationships = match relationships.map(downcast_ref::<R, Relationships>) {
+        Some(Ok(relationships)) => Some(Cow::Borrowed(relationships)),
+        Some(Err(relationships)) => {
+            Some(Cow::Owned(Relationships::deserialize(serde_json::to_value(relationships)?)?))
+        }
+        None => None,
+    };
+
+    check_field_names(attributes.as_deref(), relationships.as_deref()).map_err(ser::Error::custom)?;
+
+    Ok((attributes, relationships))
+}
+
+/// `fields` as a `T` if `F` is `T`, so that the default `Attributes` and `Relationships` skip the
+/// round trip through `serde_json::Value` that typed fields need.
+fn downcast<F: Any, T: Any>(fields: F) -> Result<T, F> {
+    let mut fields = Some(fields);
+
+    match (&mut fields as &mut dyn Any).downcast_mut::<Option<T>>() {
+        Some(typed) => Ok(typed.take().expect("fields are present")),
+        None => Err(fields.expect("fields are present")),
+    }
+}
+
+/// The borrowed form of `downcast`.
+fn downcast_ref<F: Any, T: Any>(fields: &F) -> Result<&T, &F> {
+    (fields as &dyn Any).downcast_ref().ok_or(fields)
+}
+
+fn check_field_names(attributes: Option<&Attributes>,
+                     relationships: Option<&Relationships>)
+                     -> Result<(), ValidationError> {
+    let attributes = attributes
+        .into_iter()
+        .flat_map(|attributes| attributes.0.keys().map(String::as_str));
+    let relationships = relationships
+        .into_iter()
+        .flat_map(|relationships| relationships.0.keys().map(Name::as_str));
+
+    check_fields(attributes, relationships)
+}
+
+impl<A, R> Serialize for ResourceObject<A, R>
+    where A: Serialize + Any,
+          R: Serialize + Any,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct RawResourceObject<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            id: Option<&'a Id>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            lid: Option<&'a str>,
+            #[serde(rename = "type")]
+            type_: &'a Type,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            attributes: Option<Cow<'a, Attributes>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            relationships: Option<Cow<'a, Relationships>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            links: Option<&'a ResourceLinks>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            meta: Option<&'a Meta>,
+        }
+
+        let (attributes, relationships) = untyped_fields(self.attributes.as_ref(), self.relationships.as_ref())
+            .map_err(ser::Error::custom)?;
+
+        RawResourceObject {
+            id: self.id.as_ref(),
+            lid: self.lid.as_deref(),
+            type_: &self.type_,
+            attributes,
+            relationships,
+            links: self.links.as_ref(),
+            meta: self.meta.as_ref(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de, A, R> Deserialize<'de> for ResourceObject<A, R>
+    where A: DeserializeOwned + Any,
+          R: DeserializeOwned + Any,
+{
+    fn deserialize<D>(deserializer: D) -> Result<ResourceObject<A, R>, D::Error>
+        where D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawResourceObject {
+            id: Option<Id>,
+            lid: Option<String>,
+            #[serde(rename = "type")]
+            type_: Type,
+            attributes: Option<Attributes>,
+            relationships: Option<Relationships>,
+            links: Option<ResourceLinks>,
+            meta: Option<Meta>,
+        }
+
+        let raw = RawResourceObject::deserialize(deserializer)?;
+        let resource = ResourceObject {
+            id: raw.id,
+            lid: raw.lid,
+            type_: raw.type_,
+            attributes: raw.attributes,
+            relationships: raw.relationships,
+            links: raw.links,
+            meta: raw.meta,
+        };
+
+        resource.check_fields().map_err(de::Error::custom)?;
+
+        resource.into_typed().map_err(de::Error::custom)
+    }
+}
+
+impl ResourceObject {
+    fn uses_v1_1(&self) -> bool {
+        self.lid.is_some() ||
+        self.links.as_ref().is_some_and(|links| links.0.uses_v1_1()) ||
+        self.relationships.iter().flat_map(|relationships| relationships.0.values()).any(Relationship::uses_v1_1)
+    }
 
-    /// data: Object,
+    fn strip_v1_1(&mut self) {
+        if self.id.is_some() {
+            self.lid = None;
+        }
+        self.links.iter_mut().for_each(|links| links.0.strip_v1_1());
+        self.relationships
+            .iter_mut()
+            .flat_map(|relationships| relationships.0.values_mut())
+            .for_each(Relationship::strip_v1_1);
+    }
 }
 
 
 /// http://jsonapi.org/format/#document-resource-object-identification
-struct Id(Name);
+///
+/// Unlike `type`, the value of an `id` member is not a member name, so it can be any string.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Id(pub String);
+
+impl Id {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
 
 
 /// http://jsonapi.org/format/#document-resource-object-identification
-struct Type(Name);
+///
+/// The values of `type` members **MUST** adhere to the same constraints as member names.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Type(pub Name);
+
+impl Type {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
 
 
 /// http://jsonapi.org/format/#document-resource-object-attributes
@@ -126,7 +1636,31 @@ struct Type(Name);
 ///
 /// Although has-one foreign keys (e.g. author_id) are often stored internally alongside other
 /// information to be represented in a resource object, these keys SHOULD NOT appear as attributes.
-struct Attributes(Object);
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Attributes(pub Object);
+
+impl<'de> Deserialize<'de> for Attributes {
+    fn deserialize<D>(deserializer: D) -> Result<Attributes, D::Error>
+        where D: Deserializer<'de>,
+    {
+        let attributes = Attributes(Object::deserialize(deserializer)?);
+        attributes.check().map_err(de::Error::custom)?;
+
+        Ok(attributes)
+    }
+}
+
+impl Attributes {
+    /// Checks the name of every attribute, and that no attribute uses a reserved member.
+    fn check(&self) -> Result<(), serde_json::Error> {
+        for (name, value) in &self.0 {
+            check_name(name).map_err(de::Error::custom)?;
+            check_attribute(name, value).map_err(de::Error::custom)?;
+        }
+
+        Ok(())
+    }
+}
 
 
 /// http://jsonapi.org/format/#document-resource-object-relationships
@@ -148,25 +1682,43 @@ struct Attributes(Object);
 ///
 /// A relationship object that represents a to-many relationship **MAY** also contain pagination links
 /// under the links member, as described below.
-struct Relationships {
-    links: Option<Links>,
-    data: Option<ResourceLinkage>,
-    meta: Option<Meta>,
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Relationships(pub BTreeMap<Name, Relationship>);
+
+impl Relationships {
+    pub fn get(&self, name: &str) -> Option<&Relationship> {
+        self.0.get(name)
+    }
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Relationship {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(default, deserialize_with = "deserialize_some", skip_serializing_if = "Option::is_none")]
+    pub data: Option<ResourceLinkage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
 
-/// http://jsonapi.org/format/#document-resource-object-related-resource-links
-///
-/// A "related resource link" provides access to resource objects linked in a relationship. When
-/// fetched, the related resource object(s) are returned as the response's primary data.
-///
-/// For example, an article's comments relationship could specify a link that returns a collection
-/// of comment resource objects when retrieved through a GET request.
-///
-/// If present, a related resource link **MUST** reference a valid URL, even if the relationship isn't
-/// currently associated with any target resources. Additionally, a related resource link **MUST** NOT
-/// change because its relationship's content changes.
-struct RelatedResourceLink { ... }
+impl Relationship {
+    fn uses_v1_1(&self) -> bool {
+        self.links.as_ref().is_some_and(Links::uses_v1_1) ||
+        self.data.as_ref().is_some_and(|data| data.identifiers().iter().any(ResourceIdentifierObject::uses_v1_1))
+    }
+
+    fn strip_v1_1(&mut self) {
+        self.links.iter_mut().for_each(Links::strip_v1_1);
+
+        match self.data {
+            Some(ResourceLinkage::Single(ref mut identifier)) => identifier.strip_v1_1(),
+            Some(ResourceLinkage::Array(ref mut identifiers)) => {
+                identifiers.iter_mut().for_each(ResourceIdentifierObject::strip_v1_1)
+            }
+            _ => {}
+        }
+    }
+}
 
 
 /// http://jsonapi.org/format/#document-resource-object-linkage
@@ -180,12 +1732,24 @@ struct RelatedResourceLink { ... }
 /// * an empty array ([]) for empty to-many relationships.
 /// * a single `resource identifier object` for non-empty to-one relationships.
 /// * an array of `resource identifier objects` for non-empty to-many relationships.
-enum ResourceLinkage {
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResourceLinkage {
     Null,
-    Single(ResourceIdentifierObject),,
+    Single(ResourceIdentifierObject),
     Array(Vec<ResourceIdentifierObject>),
 }
 
+impl ResourceLinkage {
+    pub fn identifiers(&self) -> &[ResourceIdentifierObject] {
+        match *self {
+            ResourceLinkage::Null => &[],
+            ResourceLinkage::Single(ref identifier) => slice::from_ref(identifier),
+            ResourceLinkage::Array(ref identifiers) => identifiers,
+        }
+    }
+}
+
 
 /// http://jsonapi.org/format/#document-resource-object-links
 ///
@@ -193,11 +1757,12 @@ enum ResourceLinkage {
 ///
 /// If present, this links object **MAY** contain a self link that identifies the resource represented by
 /// the resource object.
-struct ResourceLinks(Links);
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLinks(pub Links);
 
 impl ResourceLinks {
-    fn self_(&self) -> Option<&Link> {
-        links.find("self")
+    pub fn self_(&self) -> Option<&Link> {
+        self.0.self_()
     }
 }
 
@@ -206,37 +1771,77 @@ impl ResourceLinks {
 ///
 /// A "resource identifier object" is an object that identifies an individual resource.
 ///
-/// A "resource identifier object" **MUST** contain `type` and `id` members.
+/// A "resource identifier object" **MUST** contain a `type` member. It **MUST** also contain an `id`
+/// member, except when it represents a new resource to be created on the server. In this case, a
+/// `lid` member **MUST** be included that identifies the new resource. The `lid` member was added in
+/// JSON API 1.1.
 ///
 /// A "resource identifier object" **MAY** also include a meta member, whose value is a `meta` object
 /// that contains non-standard meta-information.
-struct ResourceIdentifierObject {
-    /// A "resource identifier object" **MUST** contain type and id members.
-    id: Id,
-    type_: Type,
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ResourceIdentifierObject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Id>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lid: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: Type,
 
     /// A "resource identifier object" **MAY** also include a meta member, whose value is a meta object
     /// that contains non-standard meta-information.
-    meta: Option<Meta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl ResourceIdentifierObject {
+    /// Identifies the resource of `type_` with the `id`.
+    pub fn new(type_: Type, id: Id) -> ResourceIdentifierObject {
+        ResourceIdentifierObject {
+            id: Some(id),
+            lid: None,
+            type_,
+            meta: None,
+        }
+    }
+
+    fn uses_v1_1(&self) -> bool {
+        self.lid.is_some()
+    }
+
+    fn strip_v1_1(&mut self) {
+        if self.id.is_some() {
+            self.lid = None;
+        }
+    }
 }
 
+impl<'de> Deserialize<'de> for ResourceIdentifierObject {
+    fn deserialize<D>(deserializer: D) -> Result<ResourceIdentifierObject, D::Error>
+        where D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawResourceIdentifierObject {
+            id: Option<Id>,
+            lid: Option<String>,
+            #[serde(rename = "type")]
+            type_: Type,
+            meta: Option<Meta>,
+        }
 
-/// http://jsonapi.org/format/#document-compound-documents
-///
-/// To reduce the number of HTTP requests, servers **MAY** allow responses that include related
-/// resources along with the requested primary resources. Such responses are called "compound
-/// documents".
-///
-/// In a compound document, all included resources **MUST** be represented as an array of resource
-/// objects in a top-level `included` member.
-///
-/// Compound documents require "full linkage", meaning that every included resource **MUST** be
-/// identified by at least one `resource identifier object` in the same document. These resource
-/// identifier objects could either be primary data or represent resource linkage contained within
-/// primary or included resources. The only exception to the full linkage requirement is when
-/// relationship fields that would otherwise contain linkage data are excluded via `sparse
-/// fieldsets`.
-struct CompoundDocuments { ... }
+        let raw = RawResourceIdentifierObject::deserialize(deserializer)?;
+
+        if raw.id.is_none() && raw.lid.is_none() {
+            return Err(de::Error::custom("a resource identifier object MUST contain an id or a lid member"));
+        }
+
+        Ok(ResourceIdentifierObject {
+            id: raw.id,
+            lid: raw.lid,
+            type_: raw.type_,
+            meta: raw.meta,
+        })
+    }
+}
 
 
 /// http://jsonapi.org/format/#document-meta
@@ -245,7 +1850,8 @@ struct CompoundDocuments { ... }
 /// of each meta member **MUST** be an object (a "meta object").
 ///
 /// Any members **MAY** be specified within meta objects.
-struct Meta(Object);
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Meta(pub Object);
 
 
 /// http://jsonapi.org/format/#document-links
@@ -258,19 +1864,175 @@ struct Meta(Object);
 /// * a string containing the link's URL.
 /// * an object ("link object") which can contain the following members:
 ///   * href: a string containing the link's URL.
+///   * rel: a string indicating the link's relation type.
+///   * describedby: a link to a description document (e.g. OpenAPI or JSON Schema) for the link
+///     target.
+///   * title: a string which serves as a label for the destination of a link such that it can be
+///     used as a human-readable identifier (e.g., a menu entry).
+///   * type: a string indicating the media type of the link's target.
+///   * hreflang: a string or an array of strings indicating the language(s) of the link's target.
 ///   * meta: a meta object containing non-standard meta-information about the link.
 ///
-struct Links(BTreeMap<Name, Link>);
+/// Every member of a link object other than `href` and `meta` was added in JSON API 1.1.
+///
+/// A link whose value is `null` is unavailable, and is treated the same as an omitted link.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Links(pub BTreeMap<Name, Link>);
+
+impl Links {
+    pub fn get(&self, name: &str) -> Option<&Link> {
+        self.0.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 
-enum Link {
-    URL(URL),
+    pub fn self_(&self) -> Option<&Link> {
+        self.get("self")
+    }
+
+    /// http://jsonapi.org/format/#document-resource-object-related-resource-links
+    ///
+    /// A "related resource link" provides access to resource objects linked in a relationship. When
+    /// fetched, the related resource object(s) are returned as the response's primary data.
+    ///
+    /// For example, an article's comments relationship could specify a link that returns a
+    /// collection of comment resource objects when retrieved through a GET request.
+    ///
+    /// If present, a related resource link **MUST** reference a valid URL, even if the relationship
+    /// isn't currently associated with any target resources. Additionally, a related resource link
+    /// **MUST** NOT change because its relationship's content changes.
+    pub fn related(&self) -> Option<&Link> {
+        self.get("related")
+    }
+
+    fn uses_v1_1(&self) -> bool {
+        self.0.values().any(Link::uses_v1_1)
+    }
+
+    fn strip_v1_1(&mut self) {
+        self.0.values_mut().for_each(Link::strip_v1_1);
+    }
+}
+
+impl<'de> Deserialize<'de> for Links {
+    fn deserialize<D>(deserializer: D) -> Result<Links, D::Error>
+        where D: Deserializer<'de>,
+    {
+        let links = BTreeMap::<Name, Option<Link>>::deserialize(deserializer)?;
+
+        Ok(Links(links.into_iter()
+            .filter_map(|(name, link)| link.map(|link| (name, link)))
+            .collect()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Link {
+    Url(Uri),
     Object {
-        href: URL,
-        meta: Meta,
+        href: Uri,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rel: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        describedby: Option<Box<Link>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+        type_: Option<String>,
+        /// A single language is serialized as a string.
+        #[serde(serialize_with = "serialize_hreflang", skip_serializing_if = "Vec::is_empty")]
+        hreflang: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        meta: Option<Meta>,
     },
 }
 
-struct URL { ... }
+impl<'de> Deserialize<'de> for Link {
+    fn deserialize<D>(deserializer: D) -> Result<Link, D::Error>
+        where D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Hreflang {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        #[derive(Deserialize)]
+        struct RawLink {
+            href: Uri,
+            rel: Option<String>,
+            describedby: Option<Link>,
+            title: Option<String>,
+            #[serde(rename = "type")]
+            type_: Option<String>,
+            hreflang: Option<Hreflang>,
+            meta: Option<Meta>,
+        }
+
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(href) => Uri::parse(&href).map(Link::Url).map_err(de::Error::custom),
+            value @ serde_json::Value::Object(_) => {
+                let link = serde_json::from_value::<RawLink>(value).map_err(de::Error::custom)?;
+
+                Ok(Link::Object {
+                    href: link.href,
+                    rel: link.rel,
+                    describedby: link.describedby.map(Box::new),
+                    title: link.title,
+                    type_: link.type_,
+                    hreflang: match link.hreflang {
+                        Some(Hreflang::One(hreflang)) => vec![hreflang],
+                        Some(Hreflang::Many(hreflang)) => hreflang,
+                        None => Vec::new(),
+                    },
+                    meta: link.meta,
+                })
+            }
+            _ => Err(de::Error::custom("a link MUST be either a string or a link object")),
+        }
+    }
+}
+
+impl Link {
+    pub fn href(&self) -> &Uri {
+        match *self {
+            Link::Url(ref href) | Link::Object { ref href, .. } => href,
+        }
+    }
+
+    fn uses_v1_1(&self) -> bool {
+        match *self {
+            Link::Url(_) => false,
+            Link::Object { ref rel, ref describedby, ref title, ref type_, ref hreflang, .. } => {
+                rel.is_some() || describedby.is_some() || title.is_some() || type_.is_some() || !hreflang.is_empty()
+            }
+        }
+    }
+
+    fn strip_v1_1(&mut self) {
+        if let Link::Object { ref mut rel, ref mut describedby, ref mut title, ref mut type_, ref mut hreflang, .. } =
+            *self {
+            *rel = None;
+            *describedby = None;
+            *title = None;
+            *type_ = None;
+            hreflang.clear();
+        }
+    }
+}
+
+fn serialize_hreflang<S>(hreflang: &[String], serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer,
+{
+    match *hreflang {
+        [ref hreflang] => hreflang.serialize(serializer),
+        _ => hreflang.serialize(serializer),
+    }
+}
 
 
 /// http://jsonapi.org/format/#document-jsonapi-object
@@ -280,9 +2042,147 @@ struct URL { ... }
 /// "jsonapi object"). The jsonapi object **MAY** contain a `version` member whose value is a
 /// string indicating the highest JSON API version supported. This object **MAY** also contain a
 /// `meta` member, whose value is a `meta` object that contains non-standard meta-information.
-struct JsonAPIObject {
-    version: Option<String>,
-    meta: Option<Meta>,
+///
+/// Since JSON API 1.1, the jsonapi object **MAY** also contain:
+///
+/// * ext: an array of URIs for all applied extensions.
+/// * profile: an array of URIs for all applied profiles.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JsonAPIObject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ext: Vec<Uri>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profile: Vec<Uri>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl JsonAPIObject {
+    /// The declared `version`. Without one, the document is a JSON API 1.0 document, and a later
+    /// version than this crate knows is read as the latest one it does.
+    pub fn version(&self) -> Version {
+        self.version.as_deref().map_or(Version::V1_0, Version::parse)
+    }
+
+    fn uses_v1_1(&self) -> bool {
+        !self.ext.is_empty() || !self.profile.is_empty()
+    }
+
+    fn strip_v1_1(&mut self) {
+        self.ext.clear();
+        self.profile.clear();
+    }
+}
+
+/// http://jsonapi.org/format/#document-jsonapi-object
+///
+/// A version of the specification, as declared by the `version` member of a jsonapi object.
+/// Versions are ordered, and each one is a superset of the ones before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    V1_0,
+    V1_1,
+}
+
+impl Version {
+    /// Reads a `MAJOR.MINOR` version string. `1.0` and anything this crate can't read are
+    /// `V1_0`, and any later version is `V1_1`.
+    pub fn parse(version: &str) -> Version {
+        let later = version.split_once('.')
+            .and_then(|(major, minor)| Some((major.parse::<u64>().ok()?, minor.parse::<u64>().ok()?)))
+            .is_some_and(|version| version >= (1, 1));
+
+        if later { Version::V1_1 } else { Version::V1_0 }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Version::V1_0 => "1.0",
+            Version::V1_1 => "1.1",
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+/// http://jsonapi.org/format/#error-objects
+///
+/// Error objects provide additional information about problems encountered while performing an
+/// operation. Error objects **MUST** be returned as an array keyed by `errors` in the top level of a
+/// JSON API document.
+///
+/// An error object **MAY** have the following members:
+///
+/// * id: a unique identifier for this particular occurrence of the problem.
+/// * links: a links object containing the following members:
+///   * about: a link that leads to further details about this particular occurrence of the problem.
+/// * status: the HTTP status code applicable to this problem, expressed as a string value.
+/// * code: an application-specific error code, expressed as a string value.
+/// * title: a short, human-readable summary of the problem that **SHOULD NOT** change from
+///   occurrence to occurrence of the problem, except for purposes of localization.
+/// * detail: a human-readable explanation specific to this occurrence of the problem.
+/// * source: an object containing references to the source of the error.
+/// * meta: a meta object containing non-standard meta-information about the error.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Error {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl Error {
+    fn uses_v1_1(&self) -> bool {
+        self.links.as_ref().is_some_and(Links::uses_v1_1) ||
+        self.source.as_ref().is_some_and(|source| source.header.is_some())
+    }
+
+    fn strip_v1_1(&mut self) {
+        self.links.iter_mut().for_each(Links::strip_v1_1);
+
+        if let Some(ref mut source) = self.source {
+            source.header = None;
+        }
+    }
+}
+
+/// http://jsonapi.org/format/#error-objects
+///
+/// An object containing references to the source of the error, optionally including any of the
+/// following members:
+///
+/// * pointer: a JSON Pointer [RFC6901] to the associated entity in the request document [e.g.
+///   "/data" for a primary data object, or "/data/attributes/title" for a specific attribute].
+/// * parameter: a string indicating which URI query parameter caused the error.
+/// * header: a string indicating the name of a single request header which caused the error. Added
+///   in JSON API 1.1.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<JsonPointer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
 }
 
 
@@ -348,9 +2248,914 @@ struct JsonAPIObject {
 /// * U+007C VERTICAL LINE, "|"
 /// * U+007D RIGHT CURLY BRACKET, "}"
 /// * U+007E TILDE, "~"
-struct Name(String);
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Name(String);
+
+impl Name {
+    /// Checks that `name` meets the conditions above.
+    pub fn parse<S: Into<String>>(name: S) -> Result<Name, NameError> {
+        let name = name.into();
+        check_name(&name)?;
+
+        Ok(Name(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+
+impl FromStr for Name {
+    type Err = NameError;
+
+    fn from_str(name: &str) -> Result<Name, NameError> {
+        Name::parse(name)
+    }
+}
+
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D>(deserializer: D) -> Result<Name, D::Error>
+        where D: Deserializer<'de>,
+    {
+        Name::parse(String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_meta_only_document() {
+        let error = serde_json::from_value::<Document>(json!({"meta": {"total": 0}})).unwrap_err();
+        assert!(error.to_string().contains("read it as a schema::Document"));
+    }
+
+    fn primary_data(endpoint: Endpoint, strict: bool, value: Value) -> Result<PrimaryData, serde_json::Error> {
+        PrimaryDataSeed::new(endpoint).strict(strict).deserialize(value)
+    }
+
+    fn kind(data: &PrimaryData) -> &'static str {
+        match *data {
+            PrimaryData::Null => "null",
+            PrimaryData::Single(_) => "single",
+            PrimaryData::SingleIdentifier(_) => "single identifier",
+            PrimaryData::Array(_) => "array",
+            PrimaryData::ArrayIdentifier(_) => "array identifier",
+        }
+    }
+
+    #[test]
+    fn test_primary_data_endpoints() {
+        let identifier = json!({"type": "people", "id": "9"});
+        let resource = json!({"type": "people", "id": "9", "attributes": {"name": "Dan"}});
+        let new_resource = json!({"type": "people", "lid": "a"});
+        let cases = [
+            (Endpoint::Unknown, identifier.clone(), "single identifier"),
+            (Endpoint::Unknown, resource.clone(), "single"),
+            (Endpoint::Unknown, new_resource.clone(), "single identifier"),
+            (Endpoint::Unknown, json!({"type": "people", "lid": "a", "attributes": {}}), "single"),
+            (Endpoint::Unknown, json!({"type": "people"}), "single"),
+            (Endpoint::Unknown, json!([new_resource, identifier]), "array identifier"),
+            (Endpoint::Unknown, json!([identifier, identifier]), "array identifier"),
+            (Endpoint::Unknown, json!([identifier, resource]), "array"),
+            (Endpoint::Unknown, json!([]), "array"),
+            (Endpoint::Unknown, json!(null), "null"),
+            (Endpoint::Resource, identifier.clone(), "single"),
+            (Endpoint::Resource, json!([identifier]), "array"),
+            (Endpoint::Resource, new_resource.clone(), "single"),
+            (Endpoint::Resource, json!([]), "array"),
+            (Endpoint::Resource, json!(null), "null"),
+            (Endpoint::Relationship, identifier.clone(), "single identifier"),
+            (Endpoint::Relationship, new_resource.clone(), "single identifier"),
+            (Endpoint::Relationship, resource.clone(), "single identifier"),
+            (Endpoint::Relationship, json!([identifier, resource]), "array identifier"),
+            (Endpoint::Relationship, json!([]), "array identifier"),
+            (Endpoint::Relationship, json!(null), "null"),
+        ];
+
+        for (endpoint, value, expected) in cases.iter().cloned() {
+            let data = primary_data(endpoint, false, value.clone()).unwrap();
+            assert_eq!(kind(&data), expected, "{:?} {}", endpoint, value);
+        }
+    }
+
+    #[test]
+    fn test_primary_data_strict() {
+        let identifier = json!({"type": "people", "id": "9"});
+        let resource = json!({"type": "people", "id": "9", "attributes": {"name": "Dan"}});
+
+        for value in &[resource.clone(), json!({"type": "people"}), json!([identifier, resource])] {
+            let error = primary_data(Endpoint::Relationship, true, value.clone()).unwrap_err();
+            assert!(error.to_string().contains("MUST be resource identifier objects"), "{}", value);
+        }
+
+        let error = primary_data(Endpoint::Unknown, true, json!([identifier, resource])).unwrap_err();
+        assert!(error.to_string().contains("MUST NOT mix resource objects and resource identifier objects"));
+
+        let data = primary_data(Endpoint::Relationship, true, json!({"type": "people", "lid": "a"})).unwrap();
+        assert_eq!(kind(&data), "single identifier");
+        assert_eq!(kind(&primary_data(Endpoint::Unknown, true, json!([])).unwrap()), "array");
+        assert_eq!(kind(&primary_data(Endpoint::Relationship, true, json!(null)).unwrap()), "null");
+    }
+
+    #[test]
+    fn test_primary_data_rejects_non_objects() {
+        for endpoint in &[Endpoint::Unknown, Endpoint::Resource, Endpoint::Relationship] {
+            assert!(primary_data(*endpoint, false, json!("people")).is_err());
+            assert!(primary_data(*endpoint, false, json!([null])).is_err());
+        }
+    }
+
+    #[test]
+    fn test_name_parse() {
+        for name in &["a", "Z", "9", "first-name", "first_name", "first name", "a-_ b", "camelCase", "1st"] {
+            assert_eq!(Name::parse(*name).map(|name| name.as_str().to_owned()), Ok(name.to_string()));
+        }
+
+        assert_eq!(Name::parse(""), Err(NameError::Empty));
+    }
+
+    #[test]
+    fn test_name_parse_boundaries() {
+        for &character in &['-', '_', ' '] {
+            let error = |position| Err(NameError::NotAllowedAtBoundary { character, position });
+
+            assert_eq!(Name::parse(format!("{}name", character)), error(0));
+            assert_eq!(Name::parse(format!("name{}", character)), error(4));
+            assert_eq!(Name::parse(character.to_string()), error(0));
+            assert!(Name::parse(format!("na{}me", character)).is_ok());
+        }
 
+        assert_eq!(Name::parse("é-").unwrap_err().to_string(),
+                   "character '-' at position 1 is not allowed at the start or end of a member name");
+    }
+
+    #[test]
+    fn test_name_parse_reserved_characters() {
+        for character in "+,.[]!\"#$%&'()*/:;<=>?@\\^`{|}~".chars() {
+            assert_eq!(Name::parse(format!("a{}b", character)),
+                       Err(NameError::ReservedCharacter { character, position: 1 }));
+        }
+
+        assert_eq!(Name::parse("author.name").unwrap_err().to_string(),
+                   "reserved character '.' at position 6 MUST NOT be used in member names");
+    }
+
+    #[test]
+    fn test_name_parse_invalid_characters() {
+        for &character in &['\u{0}', '\t', '\n', '\u{1f}', '\u{7f}'] {
+            assert_eq!(Name::parse(format!("a{}b", character)),
+                       Err(NameError::InvalidCharacter { character, position: 1 }));
+        }
+
+        assert_eq!(Name::parse("a\tb").unwrap_err().to_string(),
+                   "character '\\t' at position 1 is not allowed in member names");
+    }
+
+    #[test]
+    fn test_name_parse_non_ascii() {
+        for name in &["café", "名前", "ünïcödé-name", "\u{80}", "\u{10FFFF}", "a\u{a0}b", "😀"] {
+            assert!(Name::parse(*name).is_ok(), "{:?}", name);
+        }
+
+        assert_eq!(Name::parse("名前@"), Err(NameError::ReservedCharacter { character: '@', position: 2 }));
+        assert_eq!(Name::parse("名前 "), Err(NameError::NotAllowedAtBoundary { character: ' ', position: 2 }));
+    }
+
+    #[test]
+    fn test_attributes_reject_reserved_members() {
+        let error = |attributes: Value| {
+            serde_json::from_value::<Attributes>(attributes).unwrap_err().to_string()
+        };
+
+        assert_eq!(error(json!({"links": {}})),
+                   "`links` is reserved and MUST NOT be used as an attribute name or within an attribute");
+        assert_eq!(error(json!({"address": {"relationships": {}}})),
+                   "`relationships` is reserved and MUST NOT be used as an attribute name or within an attribute");
+        assert_eq!(error(json!({"tags": [{"name": "json"}, {"meta": {"links": null}}]})),
+                   "`links` is reserved and MUST NOT be used as an attribute name or within an attribute");
+
+        let attributes = json!({"address": {"street": "Main St", "link": "x"}, "tags": [["links"]]});
+        assert_eq!(serde_json::from_value::<Attributes>(attributes.clone()).unwrap().0,
+                   *attributes.as_object().unwrap());
+    }
+
+    fn resource(value: Value) -> ResourceObject {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn type_(type_: &str) -> Type {
+        Type(Name::parse(type_).unwrap())
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct ArticleAttributes {
+        title: String,
+        #[serde(rename = "word-count", skip_serializing_if = "Option::is_none")]
+        word_count: Option<u32>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct ArticleRelationships {
+        author: Relationship,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        comments: Option<Relationship>,
+    }
+
+    type Article = ResourceObject<ArticleAttributes, ArticleRelationships>;
+
+    fn typed_article() -> Value {
+        json!({
+            "type": "articles",
+            "id": "1",
+            "attributes": {"title": "JSON API paints my bikeshed!", "word-count": 5},
+            "relationships": {
+                "author": {
+                    "links": {"related": "http://example.com/articles/1/author"},
+                    "data": {"type": "people", "id": "9"}
+                }
+            },
+            "links": {"self": "http://example.com/articles/1"},
+            "meta": {"views": 10}
+        })
+    }
+
+    #[test]
+    fn test_typed_resource_object_round_trip() {
+        let untyped = resource(typed_article());
+        let typed: Article = untyped.clone().into_typed().unwrap();
+
+        let attributes = typed.attributes.as_ref().unwrap();
+        assert_eq!(attributes.title, "JSON API paints my bikeshed!");
+        assert_eq!(attributes.word_count, Some(5));
+
+        let relationships = typed.relationships.as_ref().unwrap();
+        assert_eq!(relationships.author.data,
+                   Some(ResourceLinkage::Single(ResourceIdentifierObject::new(type_("people"),
+                                                                              Id("9".to_owned())))));
+        assert_eq!(relationships.comments, None);
+
+        assert_eq!(typed.to_untyped().unwrap(), untyped);
+        assert_eq!(serde_json::to_value(&typed).unwrap(), typed_article());
+        assert_eq!(serde_json::from_value::<Article>(typed_article()).unwrap(), typed);
+    }
+
+    #[test]
+    fn test_typed_resource_object_without_fields() {
+        let value = json!({"type": "articles", "id": "1"});
+        let typed: Article = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(typed.attributes, None);
+        assert_eq!(typed.relationships, None);
+        assert_eq!(serde_json::to_value(&typed).unwrap(), value);
+        assert_eq!(typed.to_untyped().unwrap(), resource(value));
+    }
+
+    #[test]
+    fn test_typed_resource_object_errors() {
+        let mut value = typed_article();
+        value["attributes"] = json!({"word-count": 5});
+        assert!(serde_json::from_value::<Article>(value).unwrap_err().to_string().contains("missing field `title`"));
+
+        #[derive(Serialize)]
+        struct Reserved {
+            links: u32,
+        }
+
+        let reserved = ResourceObject::<Reserved, Relationships> {
+            id: Some(Id("1".to_owned())),
+            lid: None,
+            type_: type_("articles"),
+            attributes: Some(Reserved { links: 1 }),
+            relationships: None,
+            links: None,
+            meta: None,
+        };
+        assert!(reserved.to_untyped().is_err());
+        assert!(serde_json::to_value(&reserved).is_err());
+
+        #[derive(Serialize)]
+        struct Colliding {
+            author: String,
+        }
+
+        let colliding = ResourceObject::<Colliding, ArticleRelationships> {
+            attributes: Some(Colliding { author: "Dan".to_owned() }),
+            relationships: resource(typed_article()).into_typed::<ArticleAttributes, _>().unwrap().relationships,
+            id: reserved.id.clone(),
+            lid: None,
+            type_: type_("articles"),
+            links: None,
+            meta: None,
+        };
+        assert_eq!(colliding.to_untyped().unwrap_err().to_string(),
+                   "the field `author` collides with another field, `type` or `id`");
+        assert!(serde_json::to_value(&colliding).is_err());
+    }
+
+    #[test]
+    fn test_untyped_resource_object_checks_fields_when_serialized() {
+        let mut untyped = resource(typed_article());
+        assert_eq!(serde_json::to_value(&untyped).unwrap(), typed_article());
+
+        for (name, error) in &[("links", "`links` is reserved"),
+                               ("author", "the field `author` collides"),
+                               ("-title", "not allowed at the start or end")] {
+            let mut attributes = Object::new();
+            attributes.insert(name.to_string(), json!(1));
+            untyped.attributes = Some(Attributes(attributes));
+
+            assert!(serde_json::to_value(&untyped).unwrap_err().to_string().contains(error), "{}", name);
+            assert!(untyped.to_untyped().unwrap_err().to_string().contains(error), "{}", name);
+        }
+    }
+
+    fn article() -> PrimaryData {
+        PrimaryData::Single(resource(json!({
+            "type": "articles",
+            "id": "1",
+            "attributes": {"title": "JSON API paints my bikeshed!"},
+            "relationships": {"author": {"data": {"type": "people", "id": "9"}}}
+        })))
+    }
+
+    fn included(document: &Document) -> Vec<(&str, &str)> {
+        match *document {
+            Document::Data { ref included, .. } => {
+                included.iter()
+                    .map(|resource| (resource.type_.as_str(), resource.id.as_ref().unwrap().as_str()))
+                    .collect()
+            }
+            Document::Error { .. } => panic!("expected a data document"),
+        }
+    }
+
+    #[test]
+    fn test_builder_rejects_orphans() {
+        let error = CompoundDocumentBuilder::new(article())
+            .include(resource(json!({"type": "people", "id": "9"})))
+            .include(resource(json!({"type": "comments", "id": "5"})))
+            .build()
+            .unwrap_err();
+        assert_eq!(error, CompoundDocumentError::Unlinked { type_: type_("comments"), id: Some(Id("5".to_owned())) });
+
+        let document = CompoundDocumentBuilder::new(article())
+            .include(resource(json!({"type": "people", "id": "9"})))
+            .include(resource(json!({"type": "comments", "id": "5"})))
+            .unlinked(UnlinkedResources::Drop)
+            .build()
+            .unwrap();
+        assert_eq!(included(&document), vec![("people", "9")]);
+    }
+
+    #[test]
+    fn test_builder_follows_included_linkage() {
+        // The company is only linked from the included author, and listed before it.
+        let document = CompoundDocumentBuilder::new(article())
+            .include(resource(json!({"type": "companies", "id": "3"})))
+            .include(resource(json!({
+                "type": "people",
+                "id": "9",
+                "relationships": {"employer": {"data": {"type": "companies", "id": "3"}}}
+            })))
+            .build()
+            .unwrap();
+        assert_eq!(included(&document), vec![("companies", "3"), ("people", "9")]);
+    }
+
+    #[test]
+    fn test_builder_rejects_duplicates() {
+        let error = CompoundDocumentBuilder::new(article())
+            .include(resource(json!({"type": "articles", "id": "1"})))
+            .build()
+            .unwrap_err();
+        assert_eq!(error, CompoundDocumentError::Duplicate { type_: type_("articles"), id: Id("1".to_owned()) });
+    }
+
+    #[test]
+    fn test_builder_fieldsets_keep_linked_resources() {
+        let document = CompoundDocumentBuilder::new(article())
+            .include(resource(json!({"type": "people", "id": "9"})))
+            .fieldset(type_("articles"), vec![Name::parse("title").unwrap()])
+            .build()
+            .unwrap();
+        assert_eq!(included(&document), vec![("people", "9")]);
+
+        match document {
+            Document::Data { data: PrimaryData::Single(ref article), .. } => assert_eq!(article.relationships, None),
+            _ => panic!("expected a single resource"),
+        }
+    }
+
+    fn normalize(value: Value) -> (Value, Vec<String>) {
+        let mut document: Document = serde_json::from_value(value).unwrap();
+        let conflicts = document.normalize_included()
+            .iter()
+            .map(|conflict| {
+                format!("{} {} {}", conflict.type_.as_str(), conflict.id.as_str(), conflict.pointer)
+            })
+            .collect();
+        (serde_json::to_value(&document).unwrap(), conflicts)
+    }
+
+    #[test]
+    fn test_normalize_included_merges_duplicates() {
+        let (document, conflicts) = normalize(json!({
+            "data": {"type": "articles", "id": "1", "attributes": {"title": "JSON API paints my bikeshed!"}},
+            "included": [
+                {"type": "people", "id": "9", "attributes": {"first-name": "Dan"}},
+                {"type": "comments", "id": "5", "attributes": {"body": "First!"}},
+                {
+                    "type": "people",
+                    "id": "9",
+                    "attributes": {"last-name": "Gebhardt"},
+                    "relationships": {"articles": {"data": [{"type": "articles", "id": "1"}]}},
+                    "links": {"self": "http://example.com/people/9"},
+                    "meta": {"active": true}
+                },
+                {"type": "articles", "id": "1", "attributes": {"word-count": 5}}
+            ]
+        }));
+        assert_eq!(conflicts, Vec::<String>::new());
+        assert_eq!(document["data"]["attributes"], json!({"title": "JSON API paints my bikeshed!", "word-count": 5}));
+        assert_eq!(document["included"], json!([
+            {
+                "type": "people",
+                "id": "9",
+                "attributes": {"first-name": "Dan", "last-name": "Gebhardt"},
+                "relationships": {"articles": {"data": [{"type": "articles", "id": "1"}]}},
+                "links": {"self": "http://example.com/people/9"},
+                "meta": {"active": true}
+            },
+            {"type": "comments", "id": "5", "attributes": {"body": "First!"}}
+        ]));
+    }
+
+    #[test]
+    fn test_normalize_included_reports_conflicts_with_primary_data() {
+        let (document, conflicts) = normalize(json!({
+            "data": [{"type": "articles", "id": "1", "attributes": {"title": "JSON API paints my bikeshed!"}}],
+            "included": [{"type": "articles", "id": "1", "attributes": {"title": "Rails is Omakase"}}]
+        }));
+        assert_eq!(conflicts, vec!["articles 1 /attributes/title"]);
+        assert_eq!(document["data"][0]["attributes"], json!({"title": "JSON API paints my bikeshed!"}));
+        assert_eq!(document.get("included"), None);
+    }
+
+    #[test]
+    fn test_normalize_included_reports_conflicts() {
+        let (document, conflicts) = normalize(json!({
+            "data": [],
+            "included": [
+                {
+                    "type": "people",
+                    "id": "9",
+                    "attributes": {"first-name": "Dan", "twitter": "dgeb"},
+                    "relationships": {"employer": {
+                        "data": {"type": "companies", "id": "1"},
+                        "links": {"related": "http://example.com/people/9/employer"}
+                    }},
+                    "links": {"self": "http://example.com/people/9"},
+                    "meta": {"rank": 1}
+                },
+                {
+                    "type": "people",
+                    "id": "9",
+                    "attributes": {"first-name": "Daniel", "twitter": "dgeb"},
+                    "relationships": {"employer": {
+                        "data": {"type": "companies", "id": "2"},
+                        "links": {"related": "http://example.com/employers/9"}
+                    }},
+                    "links": {"self": "http://example.com/users/9"},
+                    "meta": {"rank": 2}
+                }
+            ]
+        }));
+        assert_eq!(conflicts, vec![
+            "people 9 /attributes/first-name",
+            "people 9 /relationships/employer/links/related",
+            "people 9 /relationships/employer/data",
+            "people 9 /links/self",
+            "people 9 /meta/rank",
+        ]);
+        assert_eq!(document["included"], json!([{
+            "type": "people",
+            "id": "9",
+            "attributes": {"first-name": "Dan", "twitter": "dgeb"},
+            "relationships": {"employer": {
+                "data": {"type": "companies", "id": "1"},
+                "links": {"related": "http://example.com/people/9/employer"}
+            }},
+            "links": {"self": "http://example.com/people/9"},
+            "meta": {"rank": 1}
+        }]));
+    }
 
-//////////////////////////////////////////////////////////////////////////////
+    #[test]
+    fn test_normalize_included_keeps_local_resources() {
+        let (document, conflicts) = normalize(json!({
+            "jsonapi": {"version": "1.1"},
+            "data": {"type": "articles", "id": "1"},
+            "included": [
+                {"type": "comments", "lid": "a", "attributes": {"body": "First!"}},
+                {"type": "comments", "lid": "a", "attributes": {"body": "Second!"}}
+            ]
+        }));
+        assert_eq!(conflicts, Vec::<String>::new());
+        assert_eq!(document["included"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_merge_conflict_display() {
+        let conflict = MergeConflict {
+            type_: type_("people"),
+            id: Id("9".to_owned()),
+            pointer: JsonPointer::parse("/attributes/first-name").unwrap(),
+        };
+        assert_eq!(conflict.to_string(),
+                   "resource objects `people` `9` have different values for `/attributes/first-name`");
+    }
+
+    /// An article by people `9` with comments `5`, `12` and `99`, of which `99` is not included.
+    fn compound() -> Document {
+        serde_json::from_value(json!({
+            "data": {
+                "type": "articles",
+                "id": "1",
+                "attributes": {"title": "JSON API paints my bikeshed!"},
+                "relationships": {
+                    "author": {"data": {"type": "people", "id": "9"}},
+                    "editor": {"data": null},
+                    "reviewer": {"data": {"type": "people", "id": "404"}},
+                    "comments": {"data": [
+                        {"type": "comments", "id": "5"},
+                        {"type": "comments", "id": "99"},
+                        {"type": "comments", "id": "12"}
+                    ]}
+                }
+            },
+            "included": [
+                {"type": "people", "id": "9", "attributes": {"first-name": "Dan"}},
+                {"type": "people", "id": "2", "attributes": {"first-name": "Yehuda"}},
+                {
+                    "type": "comments",
+                    "id": "5",
+                    "attributes": {"body": "First!"},
+                    "relationships": {"author": {"data": {"type": "people", "id": "2"}}}
+                },
+                {
+                    "type": "comments",
+                    "id": "12",
+                    "attributes": {"body": "I like XML better"},
+                    "relationships": {"author": {"data": {"type": "people", "id": "9"}}}
+                }
+            ]
+        })).unwrap()
+    }
+
+    fn primary(document: &Document) -> &ResourceObject {
+        match *document {
+            Document::Data { data: PrimaryData::Single(ref resource), .. } => resource,
+            _ => panic!("expected a single resource object"),
+        }
+    }
+
+    fn keys<'a, I: IntoIterator<Item = &'a ResourceObject>>(resources: I) -> Vec<String> {
+        resources.into_iter()
+            .map(|resource| format!("{} {}", resource.type_.as_str(), resource.id.as_ref().unwrap().as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let document = compound();
+        let article = primary(&document);
+        assert_eq!(keys(document.resolve(article, "author")), vec!["people 9"]);
+        assert_eq!(keys(document.resolve(article, "editor")), Vec::<String>::new());
+        assert_eq!(keys(document.resolve(article, "reviewer")), Vec::<String>::new());
+        assert_eq!(keys(document.resolve(article, "comments")), Vec::<String>::new());
+        assert_eq!(keys(document.resolve(article, "publisher")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_resolve_many() {
+        let document = compound();
+        let article = primary(&document);
+        assert_eq!(keys(document.resolve_many(article, "comments")), vec!["comments 5", "comments 12"]);
+        assert_eq!(keys(document.resolve_many(article, "author")), vec!["people 9"]);
+        assert_eq!(keys(document.resolve_many(article, "editor")), Vec::<String>::new());
+        assert_eq!(keys(document.resolve_many(article, "reviewer")), Vec::<String>::new());
+        assert_eq!(keys(document.resolve_many(article, "publisher")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let document = compound();
+        let article = primary(&document);
+        assert_eq!(keys(document.resolve_path(article, "comments.author")), vec!["people 2", "people 9"]);
+        assert_eq!(keys(document.resolve_path(article, "comments.author.articles")), Vec::<String>::new());
+        assert_eq!(keys(document.resolve_path(article, "editor.articles")), Vec::<String>::new());
+        assert_eq!(keys(document.resolve_path(article, "author")), vec!["people 9"]);
+        assert_eq!(keys(document.resolve_include("comments.author")), vec!["people 2", "people 9"]);
+    }
+
+    #[test]
+    fn test_resolve_path_returns_each_resource_once() {
+        let mut document = compound();
+        if let Document::Data { ref mut included, .. } = document {
+            included[3].relationships = Some(resource(json!({
+                "type": "comments",
+                "relationships": {"author": {"data": {"type": "people", "id": "2"}}}
+            })).relationships.unwrap());
+        }
+        let article = primary(&document);
+        assert_eq!(keys(document.resolve_path(article, "comments.author")), vec!["people 2"]);
+    }
+
+    #[test]
+    fn test_resource_index() {
+        let document = compound();
+        let article = primary(&document);
+        let index = document.index();
+        assert_eq!(keys(index.get("people", "9")), vec!["people 9"]);
+        assert_eq!(keys(index.get("people", "404")), Vec::<String>::new());
+        assert_eq!(keys(index.resolve(article, "author")), vec!["people 9"]);
+        assert_eq!(keys(index.resolve_many(article, "comments")), vec!["comments 5", "comments 12"]);
+        assert_eq!(keys(index.resolve_path(article, "comments.author")), vec!["people 2", "people 9"]);
+        assert_eq!(keys(index.resolve_include("comments.author")), vec!["people 2", "people 9"]);
+    }
+
+    #[test]
+    fn test_denormalize() {
+        let tree = compound().denormalize(2, CyclePolicy::Error).unwrap();
+        assert_eq!(tree["relationships"]["author"]["data"]["attributes"], json!({"first-name": "Dan"}));
+        assert_eq!(tree["relationships"]["editor"]["data"], Value::Null);
+        assert_eq!(tree["relationships"]["reviewer"]["data"], json!({"type": "people", "id": "404"}));
+
+        let comments = &tree["relationships"]["comments"]["data"];
+        assert_eq!(comments[0]["relationships"]["author"]["data"]["attributes"], json!({"first-name": "Yehuda"}));
+        assert_eq!(comments[1], json!({"type": "comments", "id": "99"}));
+        assert_eq!(comments[2]["relationships"]["author"]["data"]["attributes"], json!({"first-name": "Dan"}));
+    }
+
+    #[test]
+    fn test_denormalize_depth() {
+        let document = compound();
+        assert_eq!(document.denormalize(0, CyclePolicy::Error).unwrap(),
+                   serde_json::to_value(primary(&document)).unwrap());
+
+        let tree = document.denormalize(1, CyclePolicy::Error).unwrap();
+        let comment = &tree["relationships"]["comments"]["data"][0];
+        assert_eq!(comment["attributes"], json!({"body": "First!"}));
+        assert_eq!(comment["relationships"]["author"]["data"], json!({"type": "people", "id": "2"}));
+    }
+
+    #[test]
+    fn test_denormalize_no_data() {
+        let document: Document = serde_json::from_value(json!({"errors": [{"status": "404"}]})).unwrap();
+        assert_eq!(document.denormalize(1, CyclePolicy::Stop), Err(DenormalizeError::NoData));
+    }
+
+    /// An article whose author links back to it.
+    fn cyclic() -> Document {
+        serde_json::from_value(json!({
+            "data": {
+                "type": "articles",
+                "id": "1",
+                "attributes": {"title": "JSON API paints my bikeshed!"},
+                "relationships": {"author": {"data": {"type": "people", "id": "9"}}}
+            },
+            "included": [{
+                "type": "people",
+                "id": "9",
+                "attributes": {"first-name": "Dan"},
+                "relationships": {"articles": {"data": [{"type": "articles", "id": "1"}]}}
+            }]
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_denormalize_cycle_stop() {
+        let tree = cyclic().denormalize(5, CyclePolicy::Stop).unwrap();
+        let article = &tree["relationships"]["author"]["data"]["relationships"]["articles"]["data"][0];
+        assert_eq!(article["attributes"], json!({"title": "JSON API paints my bikeshed!"}));
+        assert_eq!(article["relationships"]["author"]["data"], json!({"type": "people", "id": "9"}));
+    }
+
+    #[test]
+    fn test_denormalize_cycle_identifier() {
+        let tree = cyclic().denormalize(5, CyclePolicy::Identifier).unwrap();
+        assert_eq!(tree["relationships"]["author"]["data"]["relationships"]["articles"]["data"],
+                   json!([{"type": "articles", "id": "1"}]));
+    }
+
+    #[test]
+    fn test_denormalize_cycle_error() {
+        let error = cyclic().denormalize(5, CyclePolicy::Error).unwrap_err();
+        assert_eq!(error, DenormalizeError::Cycle { type_: type_("articles"), id: Id("1".to_owned()) });
+        assert_eq!(error.to_string(), "resource `articles` `1` links back to itself");
+
+        assert!(cyclic().denormalize(1, CyclePolicy::Error).is_ok());
+    }
+
+    #[test]
+    fn test_denormalize_self_reference() {
+        let document: Document = serde_json::from_value(json!({
+            "data": {
+                "type": "people",
+                "id": "9",
+                "attributes": {"first-name": "Dan"},
+                "relationships": {"manager": {"data": {"type": "people", "id": "9"}}}
+            }
+        })).unwrap();
+
+        let tree = document.denormalize(5, CyclePolicy::Stop).unwrap();
+        assert_eq!(tree["relationships"]["manager"]["data"], serde_json::to_value(primary(&document)).unwrap());
+
+        let tree = document.denormalize(5, CyclePolicy::Identifier).unwrap();
+        assert_eq!(tree["relationships"]["manager"]["data"], json!({"type": "people", "id": "9"}));
+
+        assert_eq!(document.denormalize(5, CyclePolicy::Error),
+                   Err(DenormalizeError::Cycle { type_: type_("people"), id: Id("9".to_owned()) }));
+    }
+
+    fn reserialize(value: Value) -> Value {
+        let document: Document = serde_json::from_value(value).unwrap();
+        serde_json::to_value(&document).unwrap()
+    }
+
+    /// Serializes `value` as a document declaring `version`.
+    fn reserialize_as(version: &str, mut value: Value) -> Value {
+        value["jsonapi"]["version"] = json!(version);
+        let mut serialized = reserialize(value);
+        assert_eq!(serialized["jsonapi"]["version"], version);
+        serialized.as_object_mut().unwrap().remove("jsonapi");
+        serialized
+    }
+
+    fn link_object() -> Value {
+        json!({
+            "href": "http://example.com/articles/1",
+            "rel": "self",
+            "describedby": "http://example.com/schemas/article",
+            "title": "An article",
+            "type": "application/vnd.api+json",
+            "hreflang": ["en", "de"]
+        })
+    }
+
+    #[test]
+    fn test_undeclared_version_keeps_v1_1_members() {
+        let value = json!({
+            "jsonapi": {"ext": ["https://jsonapi.org/ext/atomic"]},
+            "links": {"describedby": "http://example.com/schema"},
+            "data": {"type": "articles", "lid": "a", "links": {"self": link_object()}}
+        });
+        let document: Document = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(document.declared_version(), None);
+        assert_eq!(document.version(), Version::V1_0);
+        assert_eq!(document.required_version(), Version::V1_1);
+        assert_eq!(serde_json::to_value(&document).unwrap(), value);
+    }
+
+    #[test]
+    fn test_declared_v1_1_keeps_v1_1_members() {
+        let value = json!({
+            "jsonapi": {"version": "1.1", "profile": ["http://example.com/profile"]},
+            "data": {"type": "articles", "id": "1", "lid": "a", "links": {"self": link_object()}}
+        });
+        assert_eq!(reserialize(value.clone()), value);
+    }
+
+    #[test]
+    fn test_declared_v1_0_strips_lid() {
+        let value = json!({
+            "data": [{
+                "type": "articles",
+                "id": "1",
+                "lid": "a",
+                "relationships": {
+                    "author": {"data": {"type": "people", "id": "9", "lid": "p"}},
+                    "editors": {"data": [{"type": "people", "lid": "q"}]}
+                }
+            }, {
+                "type": "articles",
+                "lid": "b"
+            }]
+        });
+        // A `lid` is only kept where there's no `id` to identify the resource.
+        assert_eq!(reserialize_as("1.0", value),
+                   json!({
+                       "data": [{
+                           "type": "articles",
+                           "id": "1",
+                           "relationships": {
+                               "author": {"data": {"type": "people", "id": "9"}},
+                               "editors": {"data": [{"type": "people", "lid": "q"}]}
+                           }
+                       }, {
+                           "type": "articles",
+                           "lid": "b"
+                       }]
+                   }));
+
+        let value = json!({"data": {"type": "people", "id": "9", "lid": "p"}});
+        assert_eq!(reserialize_as("1.0", value), json!({"data": {"type": "people", "id": "9"}}));
+    }
+
+    #[test]
+    fn test_declared_v1_0_strips_describedby() {
+        let value = json!({
+            "links": {"self": "http://example.com/articles", "describedby": "http://example.com/schema"},
+            "data": []
+        });
+        assert_eq!(reserialize_as("1.0", value),
+                   json!({"links": {"self": "http://example.com/articles"}, "data": []}));
+    }
 
+    #[test]
+    fn test_declared_v1_0_strips_link_members() {
+        let value = json!({
+            "links": {"self": link_object()},
+            "data": {
+                "type": "articles",
+                "id": "1",
+                "links": {"self": link_object()},
+                "relationships": {"author": {"links": {"related": link_object()}}}
+            }
+        });
+        let href = json!({"href": "http://example.com/articles/1"});
+        assert_eq!(reserialize_as("1.0", value),
+                   json!({
+                       "links": {"self": href},
+                       "data": {
+                           "type": "articles",
+                           "id": "1",
+                           "links": {"self": href},
+                           "relationships": {"author": {"links": {"related": href}}}
+                       }
+                   }));
 
+        // A single language is written as a string.
+        let mut link = link_object();
+        link["hreflang"] = json!("en");
+        let value = json!({"links": {"self": link}, "data": null});
+        assert_eq!(reserialize_as("1.1", value.clone()), value);
+    }
+
+    #[test]
+    fn test_declared_v1_0_strips_ext_and_profile() {
+        let value = json!({
+            "jsonapi": {"ext": ["https://jsonapi.org/ext/atomic"], "profile": ["http://example.com/profile"]},
+            "meta": {"copyright": "Example"},
+            "data": null
+        });
+        assert_eq!(reserialize_as("1.0", value), json!({"meta": {"copyright": "Example"}, "data": null}));
+    }
+
+    #[test]
+    fn test_declared_v1_0_strips_error_members() {
+        let value = json!({
+            "errors": [{
+                "status": "406",
+                "links": {"type": link_object()},
+                "source": {"header": "Accept"}
+            }]
+        });
+        assert_eq!(reserialize_as("1.0", value),
+                   json!({
+                       "errors": [{
+                           "status": "406",
+                           "links": {"type": {"href": "http://example.com/articles/1"}},
+                           "source": {}
+                       }]
+                   }));
+    }
+
+    #[test]
+    fn test_limit_to_version_lowers_declared_version() {
+        let mut document: Document = serde_json::from_value(json!({
+            "jsonapi": {"version": "1.1"},
+            "data": {"type": "articles", "id": "1", "lid": "a"}
+        })).unwrap();
+        document.limit_to_version(Version::V1_0);
+        assert_eq!(document.declared_version(), Some(Version::V1_0));
+        assert_eq!(document.required_version(), Version::V1_0);
+    }
+}
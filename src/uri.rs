@@ -0,0 +1,563 @@
+//! https://tools.ietf.org/html/rfc3986
+//!
+//! A URI-reference is either a URI or a relative reference. Links in a document **MUST** reference
+//! a valid URL, but a server may use relative references, which are resolved against the document's
+//! top-level `self` link.
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// A parsed RFC 3986 URI-reference.
+///
+/// ```text
+///   foo://example.com:8042/over/there?name=ferret#nose
+///   \_/   \______________/\_________/ \_________/ \__/
+///    |           |            |            |        |
+/// scheme     authority       path        query   fragment
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uri {
+    scheme: Option<String>,
+    authority: Option<String>,
+    path: String,
+    query: Option<String>,
+    fragment: Option<String>,
+}
+
+impl Uri {
+    /// https://tools.ietf.org/html/rfc3986#appendix-B
+    ///
+    /// Splits `uri` into its components, then checks that each component only contains the
+    /// characters its grammar allows.
+    pub fn parse(uri: &str) -> Result<Uri, UriError> {
+        let mut rest = uri;
+        let mut offset = 0;
+
+        let scheme = match uri.find([':', '/', '?', '#']) {
+            Some(end) if uri[end..].starts_with(':') => {
+                let scheme = &uri[..end];
+                check_scheme(scheme)?;
+
+                rest = &uri[end + 1..];
+                offset = end + 1;
+
+                Some(scheme.to_owned())
+            }
+            _ => None,
+        };
+
+        let fragment = match rest.find('#') {
+            Some(start) => {
+                let fragment = &rest[start + 1..];
+                check_chars(fragment, offset + start + 1, is_query_char)?;
+
+                rest = &rest[..start];
+
+                Some(fragment.to_owned())
+            }
+            None => None,
+        };
+
+        let query = match rest.find('?') {
+            Some(start) => {
+                let query = &rest[start + 1..];
+                check_chars(query, offset + start + 1, is_query_char)?;
+
+                rest = &rest[..start];
+
+                Some(query.to_owned())
+            }
+            None => None,
+        };
+
+        let authority = if rest.starts_with("//") {
+            let end = rest[2..].find('/').map_or(rest.len(), |end| end + 2);
+            let authority = &rest[2..end];
+            check_chars(authority, offset + 2, is_authority_char)?;
+
+            rest = &rest[end..];
+            offset += end;
+
+            Some(authority.to_owned())
+        } else {
+            None
+        };
+
+        check_chars(rest, offset, is_path_char)?;
+
+        Ok(Uri {
+            scheme,
+            authority,
+            path: rest.to_owned(),
+            query,
+            fragment,
+        })
+    }
+
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The query component, without the leading `?`.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// The fragment component, without the leading `#`.
+    pub fn fragment(&self) -> Option<&str> {
+        self.fragment.as_deref()
+    }
+
+    /// A URI has a scheme, while a relative reference does not.
+    pub fn is_absolute(&self) -> bool {
+        self.scheme.is_some()
+    }
+
+    /// The `name=value` pairs of the query component, split on `&` and percent-decoded, with `+`
+    /// decoded as a space.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        self.query.as_ref().map_or_else(Vec::new, |query| parse_query(query))
+    }
+
+    /// Replaces the query component with the percent-encoded `name=value` pairs, or removes it when
+    /// there are none.
+    pub fn with_query_pairs<I, N, V>(&self, pairs: I) -> Uri
+        where I: IntoIterator<Item = (N, V)>,
+              N: AsRef<str>,
+              V: AsRef<str>,
+    {
+        let query = pairs.into_iter()
+            .map(|(name, value)| {
+                format!("{}={}", encode_query_component(name.as_ref()), encode_query_component(value.as_ref()))
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        Uri {
+            query: if query.is_empty() { None } else { Some(query) },
+            ..self.clone()
+        }
+    }
+
+    /// https://tools.ietf.org/html/rfc3986#section-5.2.2
+    ///
+    /// Resolves this URI-reference against `base`. The result keeps this reference's fragment, and
+    /// takes every component this reference lacks from `base`.
+    pub fn resolve(&self, base: &Uri) -> Uri {
+        if self.scheme.is_some() {
+            return Uri {
+                path: remove_dot_segments(&self.path),
+                ..self.clone()
+            };
+        }
+
+        let (authority, path, query) = if self.authority.is_some() {
+            (self.authority.clone(), remove_dot_segments(&self.path), self.query.clone())
+        } else if self.path.is_empty() {
+            (base.authority.clone(), base.path.clone(), self.query.clone().or_else(|| base.query.clone()))
+        } else if self.path.starts_with('/') {
+            (base.authority.clone(), remove_dot_segments(&self.path), self.query.clone())
+        } else {
+            (base.authority.clone(), remove_dot_segments(&merge(base, &self.path)), self.query.clone())
+        };
+
+        Uri {
+            scheme: base.scheme.clone(),
+            authority,
+            path,
+            query,
+            fragment: self.fragment.clone(),
+        }
+    }
+}
+
+/// https://tools.ietf.org/html/rfc3986#section-5.3
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref scheme) = self.scheme {
+            write!(f, "{}:", scheme)?;
+        }
+
+        if let Some(ref authority) = self.authority {
+            write!(f, "//{}", authority)?;
+        }
+
+        f.write_str(&self.path)?;
+
+        if let Some(ref query) = self.query {
+            write!(f, "?{}", query)?;
+        }
+
+        if let Some(ref fragment) = self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Uri {
+    type Err = UriError;
+
+    fn from_str(uri: &str) -> Result<Uri, UriError> {
+        Uri::parse(uri)
+    }
+}
+
+impl Serialize for Uri {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Uri {
+    fn deserialize<D>(deserializer: D) -> Result<Uri, D::Error>
+        where D: Deserializer<'de>,
+    {
+        Uri::parse(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+/// Why a string is not a URI-reference. Positions are byte offsets from the start of the string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UriError {
+    /// `scheme = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )`
+    InvalidScheme {
+        scheme: String,
+    },
+
+    /// A character that is not allowed in the component it appears in.
+    InvalidCharacter {
+        character: char,
+        position: usize,
+    },
+
+    /// A `%` that is not followed by two hexadecimal digits.
+    InvalidPercentEncoding {
+        position: usize,
+    },
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UriError::InvalidScheme { ref scheme } => write!(f, "invalid URI scheme `{}`", scheme),
+            UriError::InvalidCharacter { character, position } => {
+                write!(f, "character {:?} at position {} is not allowed in a URI", character, position)
+            }
+            UriError::InvalidPercentEncoding { position } => {
+                write!(f, "invalid percent-encoding at position {} of a URI", position)
+            }
+        }
+    }
+}
+
+impl error::Error for UriError {}
+
+/// Decodes `%XX` escapes, returning the string unchanged where an escape is malformed.
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() && is_hex(bytes[i + 1]) && is_hex(bytes[i + 2]) {
+            decoded.push(hex_value(bytes[i + 1]) << 4 | hex_value(bytes[i + 2]));
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Splits an `application/x-www-form-urlencoded` style query into decoded `name=value` pairs.
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = match pair.find('=') {
+                Some(i) => (&pair[..i], &pair[i + 1..]),
+                None => (pair, ""),
+            };
+
+            (percent_decode(&name.replace('+', " ")), percent_decode(&value.replace('+', " ")))
+        })
+        .collect()
+}
+
+/// Percent-encodes `input` for use as a name or value in a query, leaving the characters that are
+/// allowed there unencoded except for the `&`, `=` and `+` delimiters.
+pub fn encode_query_component(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        if is_query_char(c) && !"&=+".contains(c) {
+            encoded.push(c);
+        } else {
+            let mut bytes = [0; 4];
+
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+
+    encoded
+}
+
+fn check_scheme(scheme: &str) -> Result<(), UriError> {
+    let mut chars = scheme.chars();
+    let valid = match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)),
+        _ => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(UriError::InvalidScheme { scheme: scheme.to_owned() })
+    }
+}
+
+/// Checks that every character of `component` is either percent-encoded or accepted by `allowed`.
+fn check_chars<F>(component: &str, offset: usize, allowed: F) -> Result<(), UriError>
+    where F: Fn(char) -> bool,
+{
+    let bytes = component.as_bytes();
+
+    for (i, c) in component.char_indices() {
+        if c == '%' {
+            if i + 2 >= bytes.len() || !is_hex(bytes[i + 1]) || !is_hex(bytes[i + 2]) {
+                return Err(UriError::InvalidPercentEncoding { position: offset + i });
+            }
+        } else if !allowed(c) {
+            return Err(UriError::InvalidCharacter { character: c, position: offset + i });
+        }
+    }
+
+    Ok(())
+}
+
+/// `unreserved = ALPHA / DIGIT / "-" / "." / "_" / "~"`
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~".contains(c)
+}
+
+/// `sub-delims = "!" / "$" / "&" / "'" / "(" / ")" / "*" / "+" / "," / ";" / "="`
+fn is_sub_delim(c: char) -> bool {
+    "!$&'()*+,;=".contains(c)
+}
+
+/// `pchar = unreserved / pct-encoded / sub-delims / ":" / "@"`
+fn is_pchar(c: char) -> bool {
+    is_unreserved(c) || is_sub_delim(c) || c == ':' || c == '@'
+}
+
+/// `authority = [ userinfo "@" ] host [ ":" port ]`, including IP literals in brackets.
+fn is_authority_char(c: char) -> bool {
+    is_unreserved(c) || is_sub_delim(c) || ":@[]".contains(c)
+}
+
+fn is_path_char(c: char) -> bool {
+    is_pchar(c) || c == '/'
+}
+
+/// `query = *( pchar / "/" / "?" )`, which is also the grammar of `fragment`.
+///
+/// `[` and `]` are accepted as well. They must be percent-encoded in practice, but the examples
+/// in the specification leave them unencoded "simply for readability", as in `page[offset]=2`.
+fn is_query_char(c: char) -> bool {
+    is_pchar(c) || "/?[]".contains(c)
+}
+
+fn is_hex(byte: u8) -> bool {
+    (byte as char).is_ascii_hexdigit()
+}
+
+fn hex_value(byte: u8) -> u8 {
+    (byte as char).to_digit(16).expect("hex digit") as u8
+}
+
+/// https://tools.ietf.org/html/rfc3986#section-5.2.3
+fn merge(base: &Uri, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        format!("/{}", path)
+    } else {
+        match base.path.rfind('/') {
+            Some(i) => format!("{}{}", &base.path[..i + 1], path),
+            None => path.to_owned(),
+        }
+    }
+}
+
+/// https://tools.ietf.org/html/rfc3986#section-5.2.4
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output = String::with_capacity(path.len());
+
+    while !input.is_empty() {
+        if input.starts_with("../") {
+            input = &input[3..];
+        } else if input.starts_with("./") || input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+
+            match output.rfind('/') {
+                Some(i) => output.truncate(i),
+                None => output.clear(),
+            }
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = if input.starts_with('/') { 1 } else { 0 };
+            let end = input[start..].find('/').map_or(input.len(), |end| end + start);
+
+            output.push_str(&input[..end]);
+            input = &input[end..];
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "http://a/b/c/d;p?q";
+
+    fn check_resolve(examples: &[(&str, &str)]) {
+        let base = Uri::parse(BASE).unwrap();
+
+        for &(reference, expected) in examples {
+            let resolved = Uri::parse(reference).unwrap().resolve(&base);
+            assert_eq!(resolved.to_string(), expected, "resolving `{}`", reference);
+        }
+    }
+
+    /// https://tools.ietf.org/html/rfc3986#section-5.4.1
+    #[test]
+    fn test_resolve_normal_examples() {
+        check_resolve(&[
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            ("g;x", "http://a/b/c/g;x"),
+            ("g;x?y#s", "http://a/b/c/g;x?y#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../", "http://a/"),
+            ("../../g", "http://a/g"),
+        ]);
+    }
+
+    /// https://tools.ietf.org/html/rfc3986#section-5.4.2
+    #[test]
+    fn test_resolve_abnormal_examples() {
+        check_resolve(&[
+            ("../../../g", "http://a/g"),
+            ("../../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            (".g", "http://a/b/c/.g"),
+            ("g..", "http://a/b/c/g.."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("./g/.", "http://a/b/c/g/"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+            ("g?y/./x", "http://a/b/c/g?y/./x"),
+            ("g?y/../x", "http://a/b/c/g?y/../x"),
+            ("g#s/./x", "http://a/b/c/g#s/./x"),
+            ("g#s/../x", "http://a/b/c/g#s/../x"),
+            ("http:g", "http:g"),
+        ]);
+    }
+
+    #[test]
+    fn test_resolve_against_empty_path() {
+        let base = Uri::parse("http://example.com").unwrap();
+        assert_eq!(Uri::parse("articles/1").unwrap().resolve(&base).to_string(), "http://example.com/articles/1");
+        assert_eq!(Uri::parse("?page=2").unwrap().resolve(&base).to_string(), "http://example.com?page=2");
+    }
+
+    /// https://tools.ietf.org/html/rfc3986#section-5.2.4
+    #[test]
+    fn test_remove_dot_segments() {
+        for &(path, expected) in &[
+            ("/a/b/c/./../../g", "/a/g"),
+            ("mid/content=5/../6", "mid/6"),
+            ("", ""),
+            ("/", "/"),
+            (".", ""),
+            ("..", ""),
+            ("../a", "a"),
+            ("./a", "a"),
+            ("/.", "/"),
+            ("/..", "/"),
+            ("/a/..", "/"),
+            ("/a/.", "/a/"),
+            ("/a/b/..", "/a/"),
+            ("/a/../../b", "/b"),
+            ("a/../../b", "/b"),
+            ("/a//../b", "/a/b"),
+            ("/a/.../b", "/a/.../b"),
+        ] {
+            assert_eq!(remove_dot_segments(path), expected, "removing dot segments from `{}`", path);
+        }
+    }
+
+    #[test]
+    fn test_parse_components() {
+        let uri = Uri::parse("foo://example.com:8042/over/there?name=ferret#nose").unwrap();
+        assert_eq!(uri.scheme(), Some("foo"));
+        assert_eq!(uri.authority(), Some("example.com:8042"));
+        assert_eq!(uri.path(), "/over/there");
+        assert_eq!(uri.query(), Some("name=ferret"));
+        assert_eq!(uri.fragment(), Some("nose"));
+        assert!(uri.is_absolute());
+        assert_eq!(uri.to_string(), "foo://example.com:8042/over/there?name=ferret#nose");
+
+        let reference = Uri::parse("../articles?sort=-id").unwrap();
+        assert!(!reference.is_absolute());
+        assert_eq!(reference.authority(), None);
+        assert_eq!(reference.path(), "../articles");
+    }
+}
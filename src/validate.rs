@@ -0,0 +1,863 @@
+//! http://jsonapi.org/format/
+//!
+//! Checks a JSON value against the rules of the specification quoted in the `spec` model, and
+//! reports every rule it breaks rather than stopping at the first one, as deserializing a
+//! `spec::Document` does.
+//!
+//! The sparse fieldsets exception to full linkage can't be seen in a document on its own, so a
+//! response built with sparse fieldsets may be reported as lacking full linkage.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use pointer::JsonPointer;
+use spec::{Name, Version};
+
+const TOP_LEVEL: &str = "http://jsonapi.org/format/#document-top-level";
+const RESOURCE_OBJECTS: &str = "http://jsonapi.org/format/#document-resource-objects";
+const IDENTIFICATION: &str = "http://jsonapi.org/format/#document-resource-object-identification";
+const FIELDS: &str = "http://jsonapi.org/format/#document-resource-object-fields";
+const ATTRIBUTES: &str = "http://jsonapi.org/format/#document-resource-object-attributes";
+const RELATIONSHIPS: &str = "http://jsonapi.org/format/#document-resource-object-relationships";
+const LINKAGE: &str = "http://jsonapi.org/format/#document-resource-object-linkage";
+const IDENTIFIER_OBJECTS: &str = "http://jsonapi.org/format/#document-resource-identifier-objects";
+const COMPOUND_DOCUMENTS: &str = "http://jsonapi.org/format/#document-compound-documents";
+const META: &str = "http://jsonapi.org/format/#document-meta";
+const LINKS: &str = "http://jsonapi.org/format/#document-links";
+const MEMBER_NAMES: &str = "http://jsonapi.org/format/#document-member-names";
+const ERROR_OBJECTS: &str = "http://jsonapi.org/format/#error-objects";
+
+/// How strongly the specification states a rule, following RFC 2119.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Recommended,
+    Should,
+    Must,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Recommended => f.write_str("RECOMMENDED"),
+            Severity::Should => f.write_str("SHOULD"),
+            Severity::Must => f.write_str("MUST"),
+        }
+    }
+}
+
+/// A rule of the specification that a document breaks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The value in the document that breaks the rule.
+    pub pointer: JsonPointer,
+    pub severity: Severity,
+    pub message: String,
+    /// The section of the specification that states the rule.
+    pub spec: &'static str,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at `{}`: {} ({})", self.severity, self.pointer, self.message, self.spec)
+    }
+}
+
+/// Checks `document` and returns every violation found, in document order.
+pub fn validate(document: &Value) -> Vec<Violation> {
+    let mut validator = Validator {
+        violations: Vec::new(),
+        version: document.pointer("/jsonapi/version").and_then(Value::as_str).map_or(Version::V1_0, Version::parse),
+    };
+    validator.document(document);
+    validator.violations
+}
+
+struct Validator {
+    violations: Vec<Violation>,
+    /// The version the document declares, which decides the members it may use.
+    version: Version,
+}
+
+/// The `type` and `id` of a resource object or resource identifier object.
+type Key = (String, String);
+
+impl Validator {
+    fn report<M: Into<String>>(&mut self, pointer: &JsonPointer, severity: Severity, message: M, spec: &'static str) {
+        self.violations.push(Violation {
+            pointer: pointer.clone(),
+            severity,
+            message: message.into(),
+            spec,
+        });
+    }
+
+    fn document(&mut self, document: &Value) {
+        let root = JsonPointer::root();
+
+        let document = match *document {
+            Value::Object(ref document) => document,
+            _ => {
+                self.report(&root, Severity::Must, "a JSON object MUST be at the root of every document", TOP_LEVEL);
+                return;
+            }
+        };
+
+        if !document.contains_key("data") && !document.contains_key("errors") && !document.contains_key("meta") {
+            self.report(&root,
+                        Severity::Must,
+                        "a document MUST contain at least one of data, errors or meta",
+                        TOP_LEVEL);
+        }
+
+        if document.contains_key("data") && document.contains_key("errors") {
+            self.report(&root,
+                        Severity::Must,
+                        "the members data and errors MUST NOT coexist in the same document",
+                        TOP_LEVEL);
+        }
+
+        if document.contains_key("included") && !document.contains_key("data") {
+            self.report(&root.child("included"),
+                        Severity::Must,
+                        "if a document does not contain a top-level data key, the included member MUST NOT be \
+                         present either",
+                        TOP_LEVEL);
+        }
+
+        let mut resources = Vec::new();
+        let mut linked = Vec::new();
+
+        if let Some(data) = document.get("data") {
+            let pointer = root.child("data");
+
+            match *data {
+                Value::Null => {}
+                Value::Object(ref object) => {
+                    self.primary(&pointer, object, &mut resources, &mut linked);
+                }
+                Value::Array(ref array) => {
+                    for (index, value) in array.iter().enumerate() {
+                        let pointer = pointer.child(index);
+
+                        match *value {
+                            Value::Object(ref object) => self.primary(&pointer, object, &mut resources, &mut linked),
+                            _ => {
+                                self.report(&pointer,
+                                            Severity::Must,
+                                            "primary data arrays MUST only contain objects",
+                                            TOP_LEVEL)
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    self.report(&pointer,
+                                Severity::Must,
+                                "primary data MUST be null, an object or an array",
+                                TOP_LEVEL)
+                }
+            }
+        }
+
+        let mut included = Vec::new();
+
+        if let Some(value) = document.get("included") {
+            let pointer = root.child("included");
+
+            match *value {
+                Value::Array(ref array) => {
+                    for (index, value) in array.iter().enumerate() {
+                        let pointer = pointer.child(index);
+
+                        match *value {
+                            Value::Object(ref object) => {
+                                let mut linkage = Vec::new();
+                                let key = self.resource_object(&pointer, object, false, &mut linkage);
+                                included.push((pointer, key, linkage));
+                            }
+                            _ => {
+                                self.report(&pointer,
+                                            Severity::Must,
+                                            "included resources MUST be resource objects",
+                                            COMPOUND_DOCUMENTS)
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    self.report(&pointer,
+                                Severity::Must,
+                                "included resources MUST be represented as an array of resource objects",
+                                COMPOUND_DOCUMENTS)
+                }
+            }
+        }
+
+        self.unique(&resources, &included);
+        self.full_linkage(linked, &included);
+
+        if let Some(errors) = document.get("errors") {
+            self.errors(&root.child("errors"), errors);
+        }
+
+        if let Some(meta) = document.get("meta") {
+            self.meta(&root.child("meta"), meta);
+        }
+
+        if let Some(links) = document.get("links") {
+            self.links(&root.child("links"), links);
+        }
+
+        if let Some(jsonapi) = document.get("jsonapi") {
+            if !jsonapi.is_object() {
+                self.report(&root.child("jsonapi"),
+                            Severity::Must,
+                            "the value of the jsonapi member MUST be an object",
+                            "http://jsonapi.org/format/#document-jsonapi-object");
+            }
+        }
+    }
+
+    /// An object in the primary data, which is a resource identifier object if it has nothing but
+    /// `type`, `id` and `meta`, or from version 1.1 `type`, `lid` and `meta`.
+    fn primary(&mut self,
+               pointer: &JsonPointer,
+               object: &Map<String, Value>,
+               resources: &mut Vec<(JsonPointer, Key)>,
+               linked: &mut Vec<Key>) {
+        let lid = self.version >= Version::V1_1;
+        let identifier = (object.contains_key("id") || lid && object.contains_key("lid")) &&
+            object.keys().all(|name| name == "type" || name == "id" || name == "meta" || lid && name == "lid");
+
+        if identifier {
+            if let Some(key) = self.identifier(pointer, object) {
+                linked.push(key);
+            }
+        } else if let Some(key) = self.resource_object(pointer, object, true, linked) {
+            resources.push((pointer.clone(), key));
+        }
+    }
+
+    /// Checks a resource object, adds the identifiers of its resource linkage to `linkage`, and
+    /// returns its `type` and `id` if it has both.
+    fn resource_object(&mut self,
+                       pointer: &JsonPointer,
+                       object: &Map<String, Value>,
+                       primary: bool,
+                       linkage: &mut Vec<Key>)
+                       -> Option<Key> {
+        let type_ = self.type_(pointer, object, RESOURCE_OBJECTS);
+        self.lid(pointer, object);
+        let id = match object.get("id") {
+            Some(Value::String(id)) => Some(id.clone()),
+            Some(_) => {
+                self.report(&pointer.child("id"), Severity::Must, "the value of the id member MUST be a string",
+                            IDENTIFICATION);
+                None
+            }
+            // A resource object that originates at the client and represents a new resource to be
+            // created on the server is the only one that may leave out its id.
+            None if primary => None,
+            None => {
+                self.report(pointer, Severity::Must, "a resource object MUST contain an id member", RESOURCE_OBJECTS);
+                None
+            }
+        };
+
+        let mut attribute_names = BTreeSet::new();
+
+        if let Some(attributes) = object.get("attributes") {
+            let pointer = pointer.child("attributes");
+
+            match *attributes {
+                Value::Object(ref attributes) => {
+                    for (name, value) in attributes {
+                        let pointer = pointer.child(name);
+                        self.member_name(&pointer, name);
+
+                        if name == "relationships" || name == "links" {
+                            self.report(&pointer,
+                                        Severity::Must,
+                                        format!("`{}` is reserved and MUST NOT be used as an attribute name", name),
+                                        ATTRIBUTES);
+                        }
+
+                        self.attribute_value(&pointer, value);
+                        attribute_names.insert(name.as_str());
+                    }
+                }
+                _ => self.report(&pointer, Severity::Must, "the value of the attributes key MUST be an object",
+                                 ATTRIBUTES),
+            }
+        }
+
+        let mut relationship_names = BTreeSet::new();
+
+        if let Some(relationships) = object.get("relationships") {
+            let pointer = pointer.child("relationships");
+
+            match *relationships {
+                Value::Object(ref relationships) => {
+                    for (name, relationship) in relationships {
+                        let pointer = pointer.child(name);
+                        self.member_name(&pointer, name);
+                        self.relationship(&pointer, relationship, linkage);
+                        relationship_names.insert(name.as_str());
+                    }
+                }
+                _ => self.report(&pointer, Severity::Must, "the value of the relationships key MUST be an object",
+                                 RELATIONSHIPS),
+            }
+        }
+
+        for &name in attribute_names.union(&relationship_names) {
+            let collides = name == "type" || name == "id" ||
+                (attribute_names.contains(name) && relationship_names.contains(name));
+
+            if collides {
+                let member = if attribute_names.contains(name) { "attributes" } else { "relationships" };
+                self.report(&pointer.child(member).child(name),
+                            Severity::Must,
+                            format!("the field `{}` MUST NOT collide with another field, `type` or `id`", name),
+                            FIELDS);
+            }
+        }
+
+        // Although has-one foreign keys (e.g. author_id) are often stored internally alongside
+        // other information to be represented in a resource object, these keys SHOULD NOT appear as
+        // attributes.
+        for &name in &attribute_names {
+            let relationship = ["_id", "-id", "Id"]
+                .iter()
+                .filter(|suffix| name.len() > suffix.len() && name.ends_with(*suffix))
+                .map(|suffix| &name[..name.len() - suffix.len()])
+                .find(|relationship| relationship_names.contains(relationship));
+
+            if let Some(relationship) = relationship {
+                self.report(&pointer.child("attributes").child(name),
+                            Severity::Should,
+                            format!("the foreign key of relationship `{}` SHOULD NOT appear as an attribute",
+                                    relationship),
+                            ATTRIBUTES);
+            }
+        }
+
+        if let Some(links) = object.get("links") {
+            self.links(&pointer.child("links"), links);
+        }
+
+        if let Some(meta) = object.get("meta") {
+            self.meta(&pointer.child("meta"), meta);
+        }
+
+        match (type_, id) {
+            (Some(type_), Some(id)) => Some((type_, id)),
+            _ => None,
+        }
+    }
+
+    /// Checks the `lid` member that resource objects and resource identifier objects may have from
+    /// version 1.1, and returns whether it is there. A 1.0 document's `lid` is an unknown member.
+    fn lid(&mut self, pointer: &JsonPointer, object: &Map<String, Value>) -> bool {
+        if self.version < Version::V1_1 {
+            return false;
+        }
+
+        match object.get("lid") {
+            Some(Value::String(_)) => true,
+            Some(_) => {
+                self.report(&pointer.child("lid"), Severity::Must, "the value of the lid member MUST be a string",
+                            IDENTIFICATION);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Checks the `type` member shared by resource objects and resource identifier objects.
+    fn type_(&mut self, pointer: &JsonPointer, object: &Map<String, Value>, spec: &'static str) -> Option<String> {
+        match object.get("type") {
+            Some(Value::String(type_)) => {
+                self.member_name(&pointer.child("type"), type_);
+                Some(type_.clone())
+            }
+            Some(_) => {
+                self.report(&pointer.child("type"), Severity::Must, "the value of the type member MUST be a string",
+                            IDENTIFICATION);
+                None
+            }
+            None => {
+                self.report(pointer, Severity::Must, "the object MUST contain a type member", spec);
+                None
+            }
+        }
+    }
+
+    /// Checks a resource identifier object, and returns its `type` and `id` if it has both. From
+    /// version 1.1, an identifier of a resource that the document creates has a `lid` instead of an
+    /// `id`, and isn't returned.
+    fn identifier(&mut self, pointer: &JsonPointer, object: &Map<String, Value>) -> Option<Key> {
+        let type_ = self.type_(pointer, object, IDENTIFIER_OBJECTS);
+        let lid = self.lid(pointer, object);
+        let id = match object.get("id") {
+            Some(Value::String(id)) => Some(id.clone()),
+            Some(_) => {
+                self.report(&pointer.child("id"), Severity::Must, "the value of the id member MUST be a string",
+                            IDENTIFICATION);
+                None
+            }
+            None if lid => None,
+            None if self.version >= Version::V1_1 => {
+                self.report(pointer,
+                            Severity::Must,
+                            "a resource identifier object MUST contain a type member and an id or lid member",
+                            IDENTIFIER_OBJECTS);
+                None
+            }
+            None => {
+                self.report(pointer,
+                            Severity::Must,
+                            "a resource identifier object MUST contain type and id members",
+                            IDENTIFIER_OBJECTS);
+                None
+            }
+        };
+
+        if let Some(meta) = object.get("meta") {
+            self.meta(&pointer.child("meta"), meta);
+        }
+
+        match (type_, id) {
+            (Some(type_), Some(id)) => Some((type_, id)),
+            _ => None,
+        }
+    }
+
+    /// Any object that constitutes or is contained in an attribute **MUST NOT** contain a
+    /// `relationships` or `links` member, and its member names follow the usual rules.
+    fn attribute_value(&mut self, pointer: &JsonPointer, value: &Value) {
+        match *value {
+            Value::Object(ref object) => {
+                for (name, value) in object {
+                    let pointer = pointer.child(name);
+                    self.member_name(&pointer, name);
+
+                    if name == "relationships" || name == "links" {
+                        self.report(&pointer,
+                                    Severity::Must,
+                                    format!("an object contained in an attribute MUST NOT contain a `{}` member",
+                                            name),
+                                    ATTRIBUTES);
+                    }
+
+                    self.attribute_value(&pointer, value);
+                }
+            }
+            Value::Array(ref array) => {
+                for (index, value) in array.iter().enumerate() {
+                    self.attribute_value(&pointer.child(index), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn relationship(&mut self, pointer: &JsonPointer, relationship: &Value, linkage: &mut Vec<Key>) {
+        let relationship = match *relationship {
+            Value::Object(ref relationship) => relationship,
+            _ => {
+                self.report(pointer, Severity::Must, "a relationship MUST be an object", RELATIONSHIPS);
+                return;
+            }
+        };
+
+        if !relationship.contains_key("links") && !relationship.contains_key("data") &&
+           !relationship.contains_key("meta") {
+            self.report(pointer,
+                        Severity::Must,
+                        "a relationship object MUST contain at least one of links, data or meta",
+                        RELATIONSHIPS);
+        }
+
+        if let Some(data) = relationship.get("data") {
+            let pointer = pointer.child("data");
+
+            match *data {
+                Value::Null => {}
+                Value::Object(ref object) => linkage.extend(self.identifier(&pointer, object)),
+                Value::Array(ref array) => {
+                    for (index, value) in array.iter().enumerate() {
+                        let pointer = pointer.child(index);
+
+                        match *value {
+                            Value::Object(ref object) => linkage.extend(self.identifier(&pointer, object)),
+                            _ => {
+                                self.report(&pointer,
+                                            Severity::Must,
+                                            "resource linkage MUST only contain resource identifier objects",
+                                            LINKAGE)
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    self.report(&pointer,
+                                Severity::Must,
+                                "resource linkage MUST be null, an array or a resource identifier object",
+                                LINKAGE)
+                }
+            }
+        }
+
+        if let Some(links) = relationship.get("links") {
+            self.links(&pointer.child("links"), links);
+        }
+
+        if let Some(meta) = relationship.get("meta") {
+            self.meta(&pointer.child("meta"), meta);
+        }
+    }
+
+    fn links(&mut self, pointer: &JsonPointer, links: &Value) {
+        let links = match *links {
+            Value::Object(ref links) => links,
+            _ => {
+                self.report(pointer, Severity::Must, "the value of each links member MUST be an object", LINKS);
+                return;
+            }
+        };
+
+        for (name, link) in links {
+            let pointer = pointer.child(name);
+            self.member_name(&pointer, name);
+
+            match *link {
+                Value::Null | Value::String(_) => {}
+                Value::Object(ref object) => {
+                    match object.get("href") {
+                        Some(&Value::String(_)) | None => {}
+                        Some(_) => {
+                            self.report(&pointer.child("href"),
+                                        Severity::Must,
+                                        "the href of a link object MUST be a string containing the link's URL",
+                                        LINKS)
+                        }
+                    }
+
+                    if let Some(meta) = object.get("meta") {
+                        self.meta(&pointer.child("meta"), meta);
+                    }
+                }
+                _ => self.report(&pointer, Severity::Must, "a link MUST be a string or a link object", LINKS),
+            }
+        }
+    }
+
+    fn meta(&mut self, pointer: &JsonPointer, meta: &Value) {
+        if !meta.is_object() {
+            self.report(pointer, Severity::Must, "the value of each meta member MUST be an object", META);
+        }
+    }
+
+    fn errors(&mut self, pointer: &JsonPointer, errors: &Value) {
+        let errors = match *errors {
+            Value::Array(ref errors) => errors,
+            _ => {
+                self.report(pointer, Severity::Must, "error objects MUST be returned as an array", ERROR_OBJECTS);
+                return;
+            }
+        };
+
+        for (index, error) in errors.iter().enumerate() {
+            let pointer = pointer.child(index);
+
+            let error = match *error {
+                Value::Object(ref error) => error,
+                _ => {
+                    self.report(&pointer, Severity::Must, "errors MUST be error objects", ERROR_OBJECTS);
+                    continue;
+                }
+            };
+
+            if let Some(links) = error.get("links") {
+                self.links(&pointer.child("links"), links);
+            }
+
+            if let Some(meta) = error.get("meta") {
+                self.meta(&pointer.child("meta"), meta);
+            }
+
+            let source_pointer = error.get("source").and_then(|source| source.get("pointer"));
+
+            if let Some(source_pointer) = source_pointer {
+                let valid = source_pointer.as_str().is_some_and(|p| JsonPointer::parse(p).is_ok());
+
+                if !valid {
+                    self.report(&pointer.child("source").child("pointer"),
+                                Severity::Must,
+                                "source.pointer MUST be a JSON Pointer",
+                                ERROR_OBJECTS);
+                }
+            }
+        }
+    }
+
+    /// Member names **MUST** meet the conditions of `spec::Name`, and it is **RECOMMENDED** that
+    /// they only use non-reserved, URL safe characters.
+    fn member_name(&mut self, pointer: &JsonPointer, name: &str) {
+        match Name::parse(name) {
+            Ok(_) => {
+                if name.chars().any(|c| c == ' ' || !c.is_ascii()) {
+                    self.report(pointer,
+                                Severity::Recommended,
+                                format!("it is RECOMMENDED that member name `{}` only use URL safe characters", name),
+                                MEMBER_NAMES);
+                }
+            }
+            Err(error) => self.report(pointer, Severity::Must, format!("`{}`: {}", name, error), MEMBER_NAMES),
+        }
+    }
+
+    /// A compound document **MUST NOT** include more than one resource object for each `type` and
+    /// `id` pair.
+    fn unique(&mut self, resources: &[(JsonPointer, Key)], included: &[(JsonPointer, Option<Key>, Vec<Key>)]) {
+        let mut seen = BTreeSet::new();
+        let included = included.iter().filter_map(|(pointer, key, _)| key.as_ref().map(|key| (pointer, key)));
+
+        for (pointer, key) in resources.iter().map(|(pointer, key)| (pointer, key)).chain(included) {
+            if !seen.insert(key) {
+                self.report(pointer,
+                            Severity::Must,
+                            format!("a compound document MUST NOT include more than one resource object for \
+                                     `{}` `{}`",
+                                    key.0,
+                                    key.1),
+                            COMPOUND_DOCUMENTS);
+            }
+        }
+    }
+
+    /// Every included resource **MUST** be identified via a chain of resource linkage starting at
+    /// the primary data.
+    fn full_linkage(&mut self, mut pending: Vec<Key>, included: &[(JsonPointer, Option<Key>, Vec<Key>)]) {
+        let mut linkage = BTreeMap::new();
+
+        for (_, key, identifiers) in included {
+            if let Some(ref key) = *key {
+                linkage.entry(key).or_insert_with(Vec::new).extend(identifiers);
+            }
+        }
+
+        let mut linked = BTreeSet::new();
+
+        while let Some(key) = pending.pop() {
+            if let Some(identifiers) = linkage.get(&key) {
+                if linked.insert(key) {
+                    pending.extend(identifiers.iter().map(|&key| key.clone()));
+                }
+            }
+        }
+
+        for (pointer, key, _) in included {
+            if key.as_ref().is_some_and(|key| !linked.contains(key)) {
+                self.report(pointer,
+                            Severity::Must,
+                            "every included resource MUST be identified by at least one resource identifier object",
+                            COMPOUND_DOCUMENTS);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A resource object with `attributes` and `relationships`.
+    fn resource(attributes: Value, relationships: Value) -> Value {
+        json!({"data": {"type": "articles", "id": "1", "attributes": attributes, "relationships": relationships}})
+    }
+
+    /// Checks that `document` breaks exactly one rule, at `pointer`.
+    fn check(document: Value, pointer: &str, severity: Severity, message: &str) {
+        let violations = validate(&document);
+        assert_eq!(violations.len(), 1, "{}: {:?}", document, violations);
+        assert_eq!(violations[0].pointer.to_string(), pointer, "{}", document);
+        assert_eq!(violations[0].severity, severity, "{}", document);
+        assert!(violations[0].message.contains(message), "{}: {}", document, violations[0].message);
+    }
+
+    fn must(document: Value, pointer: &str, message: &str) {
+        check(document, pointer, Severity::Must, message);
+    }
+
+    #[test]
+    fn test_valid() {
+        let document = json!({
+            "data": {
+                "type": "articles",
+                "id": "1",
+                "attributes": {"title": "Rails is Omakase", "tags": [{"name": "rails"}]},
+                "relationships": {"author": {"data": {"type": "people", "id": "9"}, "links": {"self": "/a"}}},
+                "links": {"self": {"href": "http://example.com/articles/1", "meta": {}}}
+            },
+            "included": [{"type": "people", "id": "9"}],
+            "meta": {},
+            "jsonapi": {"version": "1.0"}
+        });
+        assert_eq!(validate(&document), Vec::new());
+        assert_eq!(validate(&json!({"errors": [{"source": {"pointer": "/data/attributes/title"}}]})), Vec::new());
+    }
+
+    #[test]
+    fn test_top_level() {
+        must(json!([]), "", "a JSON object MUST be at the root");
+        must(json!({}), "", "at least one of data, errors or meta");
+        must(json!({"data": null, "errors": []}), "", "MUST NOT coexist");
+        must(json!({"meta": {}, "included": []}), "/included", "the included member MUST NOT be present");
+        must(json!({"data": 1}), "/data", "primary data MUST be null, an object or an array");
+        must(json!({"data": [1]}), "/data/0", "primary data arrays MUST only contain objects");
+        must(json!({"meta": {}, "jsonapi": 1}), "/jsonapi", "jsonapi member MUST be an object");
+        must(json!({"meta": 1}), "/meta", "meta member MUST be an object");
+    }
+
+    #[test]
+    fn test_resource_objects() {
+        must(json!({"data": {"id": "1", "attributes": {}}}), "/data", "MUST contain a type member");
+        must(json!({"data": {"type": 1, "id": "1", "attributes": {}}}), "/data/type", "type member MUST be a string");
+        must(json!({"data": {"type": "articles", "id": 1, "attributes": {}}}), "/data/id",
+             "id member MUST be a string");
+        must(json!({"data": {"type": "articles", "attributes": 1}}), "/data/attributes",
+             "attributes key MUST be an object");
+        must(json!({"data": {"type": "articles", "relationships": 1}}), "/data/relationships",
+             "relationships key MUST be an object");
+
+        // Only a resource object in the primary data may leave out its id.
+        must(json!({
+                 "data": {"type": "articles", "relationships": {"author": {"data": {"type": "people", "id": "9"}}}},
+                 "included": [{"type": "people"}]
+             }),
+             "/included/0",
+             "a resource object MUST contain an id member");
+    }
+
+    #[test]
+    fn test_fields() {
+        must(resource(json!({"links": 1}), json!({})), "/data/attributes/links", "`links` is reserved");
+        must(resource(json!({"address": {"relationships": 1}}), json!({})),
+             "/data/attributes/address/relationships",
+             "an object contained in an attribute MUST NOT contain a `relationships` member");
+        must(resource(json!({"tags": [{"links": 1}]}), json!({})), "/data/attributes/tags/0/links",
+             "an object contained in an attribute");
+        must(resource(json!({"type": "essay"}), json!({})), "/data/attributes/type", "MUST NOT collide");
+        must(resource(json!({"author": "Dan"}), json!({"author": {"data": null}})), "/data/attributes/author",
+             "MUST NOT collide");
+        check(resource(json!({"author_id": "9"}), json!({"author": {"data": null}})),
+              "/data/attributes/author_id",
+              Severity::Should,
+              "the foreign key of relationship `author` SHOULD NOT appear as an attribute");
+    }
+
+    #[test]
+    fn test_member_names() {
+        must(resource(json!({"-title": 1}), json!({})), "/data/attributes/-title", "`-title`");
+        must(resource(json!({"title+": 1}), json!({})), "/data/attributes/title+", "reserved character");
+        check(resource(json!({"first name": 1}), json!({})),
+              "/data/attributes/first name",
+              Severity::Recommended,
+              "RECOMMENDED");
+        check(resource(json!({"prénom": 1}), json!({})),
+              "/data/attributes/prénom",
+              Severity::Recommended,
+              "URL safe");
+    }
+
+    #[test]
+    fn test_relationships() {
+        must(resource(json!({}), json!({"author": 1})), "/data/relationships/author", "MUST be an object");
+        must(resource(json!({}), json!({"author": {}})), "/data/relationships/author",
+             "at least one of links, data or meta");
+        must(resource(json!({}), json!({"author": {"data": "9"}})), "/data/relationships/author/data",
+             "resource linkage MUST be null, an array or a resource identifier object");
+        must(resource(json!({}), json!({"tags": {"data": [1]}})), "/data/relationships/tags/data/0",
+             "resource linkage MUST only contain resource identifier objects");
+        must(resource(json!({}), json!({"author": {"data": {"type": "people"}}})),
+             "/data/relationships/author/data",
+             "a resource identifier object MUST contain type and id members");
+    }
+
+    #[test]
+    fn test_lid() {
+        let v1_1 = |mut document: Value| {
+            document["jsonapi"] = json!({"version": "1.1"});
+            document
+        };
+        let linkage = json!({
+            "author": {"data": {"type": "people", "lid": "a"}},
+            "tags": {"data": [{"type": "tags", "lid": "b"}]}
+        });
+
+        assert_eq!(validate(&v1_1(resource(json!({}), linkage.clone()))), Vec::new());
+        assert_eq!(validate(&v1_1(json!({"data": {"type": "people", "lid": "a", "attributes": {"name": "Dan"}}}))),
+                   Vec::new());
+        assert_eq!(validate(&v1_1(json!({"data": [{"type": "tags", "lid": "b", "meta": {}}]}))), Vec::new());
+
+        must(v1_1(resource(json!({}), json!({"author": {"data": {"type": "people", "lid": 1}}}))),
+             "/data/relationships/author/data/lid",
+             "the value of the lid member MUST be a string");
+        must(v1_1(resource(json!({}), json!({"author": {"data": {"type": "people"}}}))),
+             "/data/relationships/author/data",
+             "MUST contain a type member and an id or lid member");
+        must(v1_1(json!({"data": {"type": "people", "lid": []}})), "/data/lid", "lid member MUST be a string");
+
+        // A 1.0 identifier still needs an id.
+        let violations = validate(&resource(json!({}), linkage));
+        assert_eq!(violations.len(), 2, "{:?}", violations);
+        assert!(violations.iter().all(|violation| violation.message.contains("MUST contain type and id members")));
+    }
+
+    #[test]
+    fn test_links() {
+        must(json!({"data": null, "links": 1}), "/links", "links member MUST be an object");
+        must(json!({"data": null, "links": {"self": 1}}), "/links/self", "a link MUST be a string or a link object");
+        must(json!({"data": null, "links": {"self": {"href": 1}}}), "/links/self/href", "href of a link object");
+        must(json!({"data": null, "links": {"self": {"href": "/", "meta": []}}}), "/links/self/meta",
+             "meta member MUST be an object");
+    }
+
+    #[test]
+    fn test_compound_documents() {
+        must(json!({"data": null, "included": {}}), "/included", "MUST be represented as an array");
+        must(json!({"data": null, "included": [1]}), "/included/0", "included resources MUST be resource objects");
+        must(json!({
+                 "data": {"type": "articles", "id": "1", "attributes": {}},
+                 "included": [{"type": "people", "id": "9"}]
+             }),
+             "/included/0",
+             "every included resource MUST be identified");
+
+        // The primary resource links to itself, so only the duplicate is reported.
+        must(json!({
+                 "data": {
+                     "type": "articles",
+                     "id": "1",
+                     "relationships": {"related": {"data": {"type": "articles", "id": "1"}}}
+                 },
+                 "included": [{"type": "articles", "id": "1"}]
+             }),
+             "/included/0",
+             "MUST NOT include more than one resource object for `articles` `1`");
+    }
+
+    #[test]
+    fn test_errors() {
+        must(json!({"errors": {}}), "/errors", "error objects MUST be returned as an array");
+        must(json!({"errors": [1]}), "/errors/0", "errors MUST be error objects");
+        must(json!({"errors": [{"source": {"pointer": "data"}}]}), "/errors/0/source/pointer",
+             "source.pointer MUST be a JSON Pointer");
+        must(json!({"errors": [{"meta": 1}]}), "/errors/0/meta", "meta member MUST be an object");
+    }
+}
@@ -0,0 +1,114 @@
+extern crate jsonapi;
+extern crate serde_json;
+
+use jsonapi::JsonApiResource;
+use jsonapi::resource::ResourceError;
+use jsonapi::spec::{Id, Name, ResourceIdentifierObject, ResourceObject, Type};
+use serde_json::{json, Value};
+
+#[derive(Debug, PartialEq, JsonApiResource)]
+#[jsonapi(type = "articles")]
+struct Article {
+    #[jsonapi(id)]
+    id: u64,
+    title: String,
+    #[jsonapi(rename = "reading-time")]
+    reading_time: Option<u32>,
+    #[jsonapi(relationship)]
+    author: ResourceIdentifierObject,
+    #[jsonapi(relationship, rename = "co-authors")]
+    coauthors: Vec<ResourceIdentifierObject>,
+    #[jsonapi(relationship)]
+    editor: Option<ResourceIdentifierObject>,
+    #[jsonapi(skip)]
+    cached: bool,
+}
+
+fn person(id: &str) -> ResourceIdentifierObject {
+    ResourceIdentifierObject::new(Type(Name::parse("people").unwrap()), Id(id.to_owned()))
+}
+
+fn article() -> Article {
+    Article {
+        id: 1,
+        title: "JSON API paints my bikeshed!".to_owned(),
+        reading_time: Some(5),
+        author: person("9"),
+        coauthors: vec![person("10"), person("11")],
+        editor: None,
+        cached: false,
+    }
+}
+
+fn from_json(value: Value) -> Result<Article, ResourceError> {
+    Article::from_resource_object(serde_json::from_value::<ResourceObject>(value).unwrap())
+}
+
+#[test]
+fn test_type() {
+    assert_eq!(Article::TYPE, "articles");
+}
+
+#[test]
+fn test_round_trip() {
+    let value = json!({
+        "type": "articles",
+        "id": "1",
+        "attributes": {
+            "title": "JSON API paints my bikeshed!",
+            "reading-time": 5
+        },
+        "relationships": {
+            "author": {"data": {"type": "people", "id": "9"}},
+            "co-authors": {"data": [{"type": "people", "id": "10"}, {"type": "people", "id": "11"}]},
+            "editor": {"data": null}
+        }
+    });
+
+    let mut article = article();
+    assert_eq!(serde_json::to_value(article.to_resource_object().unwrap()).unwrap(), value);
+    assert_eq!(from_json(value).unwrap(), article);
+
+    // A skipped field isn't written, and is read as its default.
+    article.cached = true;
+    let resource = article.to_resource_object().unwrap();
+    assert!(!Article::from_resource_object(resource).unwrap().cached);
+}
+
+#[test]
+fn test_missing_members() {
+    let article = from_json(json!({
+        "type": "articles",
+        "id": "1",
+        "attributes": {"title": "Ember Hamster"},
+        "relationships": {"author": {"data": {"type": "people", "id": "9"}}}
+    })).unwrap();
+
+    assert_eq!(article.reading_time, None);
+    assert_eq!(article.coauthors, Vec::new());
+    assert_eq!(article.editor, None);
+}
+
+#[test]
+fn test_errors() {
+    let error = from_json(json!({"type": "people", "id": "1"})).unwrap_err();
+    assert_eq!(error, ResourceError::WrongType { expected: "articles", found: "people".to_owned() });
+
+    let error = from_json(json!({"type": "articles", "attributes": {"title": "Ember Hamster"}})).unwrap_err();
+    assert_eq!(error, ResourceError::MissingId);
+
+    let error = from_json(json!({"type": "articles", "id": "one"})).unwrap_err();
+    assert_eq!(error, ResourceError::InvalidId { id: "one".to_owned() });
+
+    let error = from_json(json!({"type": "articles", "id": "1", "attributes": {"title": 1}})).unwrap_err();
+    assert!(matches!(error, ResourceError::Attribute { ref name, .. } if name == "title"));
+
+    let error = from_json(json!({
+        "type": "articles",
+        "id": "1",
+        "attributes": {"title": "Ember Hamster"},
+        "relationships": {"author": {"data": [{"type": "people", "id": "9"}]}}
+    })).unwrap_err();
+    assert_eq!(error,
+               ResourceError::Relationship { name: "author".to_owned(), expected: "a resource identifier object" });
+}